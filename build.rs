@@ -0,0 +1,15 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=proto/golem.proto");
+    // The gRPC interface is generated from proto/golem.proto with a bundled
+    // protoc, so building it needs nothing installed.
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("no bundled protoc for this platform");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::configure()
+            .build_client(false)
+            .compile(&["proto/golem.proto"], &["proto"])
+            .expect("Failed to compile proto/golem.proto");
+    }
+}
@@ -0,0 +1,436 @@
+use std::sync::Arc;
+
+use chrono::{Local, Weekday};
+use serde_json::{json, Value};
+use tracing::{debug, info, warn};
+
+use crate::audit;
+use crate::config::{ApiConfig, ApiTokenConfig, Scope, StatusPageConfig};
+use crate::control::{Command, SharedStatus, Status};
+use crate::dashboard::{self, constant_time_eq};
+use crate::feed::{self, LogFeed};
+use crate::http::{self, Request, Response};
+use crate::instance::{Handle, Instances};
+use crate::jobs::BackupJobs;
+use crate::lifecycle;
+use crate::notify::Escalations;
+use crate::prometheus;
+use crate::queue::{Action, ActionQueue, When};
+use crate::rate_limit::RateLimiter;
+use crate::schedule::{self, Hours};
+use crate::status_page;
+
+/// Serves the JSON control API: `GET /status`, `POST /start`, `/stop`,
+/// `/restart`, `/watchdog/reset`, `/extend` (`{"minutes": 30}`) and `/command` (`{"command": "say hi"}`),
+/// `POST /backups` to start a backup and `GET /backups/<id>` to follow it,
+/// `GET /schedule` and `PATCH /schedule` (`{"friday": "18:00-24:00"}`),
+/// `GET /logs?lines=100` for the console's last lines, `GET /queue`,
+/// `POST /queue` and `DELETE /queue/<id>` for actions that wait for an empty
+/// server or a free golem, `POST /alerts/ack` to acknowledge escalating alerts
+/// (every server's; `/servers/<name>/alerts/ack` for one),
+/// plus the live log as a WebSocket at `/ws/logs?filter=<regex>`. With tokens
+/// configured, each request needs `Authorization: Bearer <token>` (or, as
+/// browsers cannot set headers on WebSockets, `?token=<token>`), except for
+/// the read-only `GET /public/status` and the players' page at `GET /public`.
+/// `GET /metrics` is for Prometheus to scrape. A token's scope limits it to
+/// reading (any GET), control (everything but `/command`) or admin, and it
+/// may carry a rate limit of its own. With `[[servers]]`, `GET /servers`
+/// lists them and `/servers/<name>/...` reaches any route of one, e.g.
+/// `POST /servers/creative/start`; the bare routes are the first server's.
+pub fn spawn(config: &ApiConfig, instances: Instances, jobs: BackupJobs) {
+    let tls = match &config.tls {
+        Some(tls) => match http::load_tls(&tls.cert_path, &tls.key_path) {
+            Ok(tls) => Some(tls),
+            Err(e) => {
+                warn!("API: not started, TLS setup failed: {}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+    if config.dashboard.as_ref().is_some_and(|d| d.token.is_none() && d.password.is_none()) {
+        warn!("Dashboard: no token or username/password configured, so every request will be refused.");
+    }
+    if config.tokens.is_empty() && !is_loopback(&config.bind) {
+        warn!("API: no tokens configured, so anyone who can reach {} can control the server.", config.bind);
+    }
+
+    let settings = config.clone();
+    let limiter = RateLimiter::default();
+    let handler = Arc::new(move |request: &Request| {
+        let status = instances.primary().interpreter.status;
+        let response = if request.method == "OPTIONS" {
+            Response::empty(204)
+                .with_header("Access-Control-Allow-Methods", "GET, POST, PATCH, DELETE, OPTIONS")
+                .with_header("Access-Control-Allow-Headers", "Authorization, Content-Type")
+                .with_header("Access-Control-Max-Age", "3600")
+        } else if request.path.trim_end_matches('/') == "/public/status" {
+            return public_status(request, &status);
+        } else if let (Some(page), "/public") = (&settings.status_page, request.path.trim_end_matches('/')) {
+            return public_page(request, page, &status);
+        } else if let Some(response) = settings
+            .dashboard
+            .as_ref()
+            .and_then(|d| dashboard::handle(d, request, &instances))
+        {
+            response
+        } else {
+            let (name, path) = split_server(request.path.trim_end_matches('/'));
+            match caller(&settings, request) {
+                Ok(token) => match (admit(token, request, path, &limiter), instances.find(name)) {
+                    (Err(response), _) => response,
+                    (Ok(()), Err(e)) => Response::error(404, &e),
+                    (Ok(()), Ok(server)) => {
+                        let caller = token.map(|t| t.name.as_str());
+                        match path {
+                            "/servers" if name.is_none() => servers(request, &instances),
+                            path if path == "/queue" || path.starts_with("/queue/") => {
+                                queued(request, path, caller, &server.interpreter.queue)
+                            }
+                            "/alerts/ack" if name.is_none() => acknowledge(request, caller, || instances.acknowledge_all()),
+                            "/alerts/ack" => {
+                                acknowledge(request, caller, || server.escalations.as_ref().map(Escalations::acknowledge_all))
+                            }
+                            _ => server.within(|| handle(request, path, caller, &server, &jobs)),
+                        }
+                    }
+                },
+                Err(()) => {
+                    warn!("API: rejected {} {} from {} (no valid token)", request.method, request.path, request.peer);
+                    audit_refusal(request, &request.peer.ip().to_string(), "no valid token");
+                    Response::error(401, "unauthorized").with_header("WWW-Authenticate", "Bearer")
+                }
+            }
+        };
+        with_cors(&settings, request, response)
+    });
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    match http::serve(&config.bind, tls, handler) {
+        Ok(()) => info!("API: listening on {}://{}", scheme, config.bind),
+        Err(e) => warn!("API: could not listen on {}: {}", config.bind, e),
+    }
+}
+
+fn is_loopback(bind: &str) -> bool {
+    bind.starts_with("localhost:") || bind.parse::<std::net::SocketAddr>().is_ok_and(|a| a.ip().is_loopback())
+}
+
+/// The token the request carries, None when no tokens are configured, and
+/// Err when they are and it carries none of them.
+fn caller<'a>(config: &'a ApiConfig, request: &Request) -> Result<Option<&'a ApiTokenConfig>, ()> {
+    if config.tokens.is_empty() {
+        return Ok(None);
+    }
+    let presented = request
+        .header("Authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or(request.query("token"))
+        .ok_or(())?;
+    config
+        .tokens
+        .iter()
+        .find(|t| constant_time_eq(t.token.as_bytes(), presented.trim().as_bytes()))
+        .map(Some)
+        .ok_or(())
+}
+
+/// `/servers/<name>/<route>` as the named server and its route; any other
+/// path is the first server's.
+fn split_server(path: &str) -> (Option<&str>, &str) {
+    let Some(rest) = path.strip_prefix("/servers/") else {
+        return (None, path);
+    };
+    match rest.find('/') {
+        Some(at) => (Some(&rest[..at]), &rest[at..]),
+        None => (Some(rest), ""),
+    }
+}
+
+/// Checks the token's scope for the route and its rate limit; without tokens
+/// anything goes.
+fn admit(token: Option<&ApiTokenConfig>, request: &Request, path: &str, limiter: &RateLimiter) -> Result<(), Response> {
+    let Some(token) = token else {
+        return Ok(());
+    };
+    let needed = required_scope(&request.method, path);
+    if token.scope < needed {
+        warn!("API: refused {} {} for {} (needs the {} scope)", request.method, request.path, token.name, needed.as_str());
+        audit_refusal(request, &token.name, &format!("lacks the {} scope", needed.as_str()));
+        return Err(Response::error(403, &format!("this token lacks the {} scope", needed.as_str())));
+    }
+    if let Some(limit) = token.requests_per_minute {
+        limiter.check(&token.name, limit).map_err(|retry_after| {
+            warn!("API: rate limited {} {} for {}", request.method, request.path, token.name);
+            audit_refusal(request, &token.name, "rate limited");
+            Response::error(429, "too many requests").with_header("Retry-After", &retry_after.to_string())
+        })?;
+    }
+    Ok(())
+}
+
+/// Reads are not actions, so only refused changes are worth recording.
+fn audit_refusal(request: &Request, who: &str, reason: &str) {
+    if request.method != "GET" && request.method != "OPTIONS" {
+        audit::record("api", who, &format!("{} {}", request.method, request.path), &format!("refused: {}", reason));
+    }
+}
+
+fn required_scope(method: &str, path: &str) -> Scope {
+    match (method, path) {
+        ("GET", _) => Scope::Read,
+        (_, "/command") => Scope::Admin,
+        _ => Scope::Control,
+    }
+}
+
+fn with_cors(config: &ApiConfig, request: &Request, response: Response) -> Response {
+    let Some(origin) = request.header("Origin") else {
+        return response;
+    };
+    if config.cors_origins.iter().any(|o| o == "*") {
+        response.with_header("Access-Control-Allow-Origin", "*")
+    } else if config.cors_origins.iter().any(|o| o.trim_end_matches('/') == origin) {
+        response.with_header("Access-Control-Allow-Origin", origin).with_header("Vary", "Origin")
+    } else {
+        response
+    }
+}
+
+/// One server's routes; `path` is without any `/servers/<name>` prefix.
+fn handle(request: &Request, path: &str, caller: Option<&str>, server: &Handle, jobs: &BackupJobs) -> Response {
+    let status = &server.interpreter.status;
+    if let Some(id) = path.strip_prefix("/backups/") {
+        if request.method != "GET" {
+            return Response::error(405, "method not allowed");
+        }
+        return match id.parse().ok().and_then(|id| jobs.get(id)) {
+            Some(job) => Response::json(200, &job),
+            None => Response::error(404, "no such backup job"),
+        };
+    }
+    let mut job_id = None;
+    let command = match (request.method.as_str(), path) {
+        ("GET", "/status") => {
+            let status = status.lock().map(|s| s.clone()).unwrap_or_default();
+            return Response::json(200, &status_json(&status));
+        }
+        ("GET", "/schedule") => {
+            let week = status.lock().map(|s| s.week.clone()).unwrap_or_default();
+            return Response::json(200, &schedule_json(&week));
+        }
+        ("PATCH", "/schedule") => match request.json().map_err(|e| e.to_string()).and_then(|body| schedule_changes(&body)) {
+            Ok(changes) => Command::SetHours(changes),
+            Err(e) => return Response::error(400, &e),
+        },
+        ("GET", "/logs") => {
+            let lines = match request.query("lines").map(str::parse::<usize>) {
+                None => 100,
+                Some(Ok(lines)) if lines > 0 => lines,
+                _ => return Response::error(400, "lines must be a positive number"),
+            };
+            let status = status.lock().map(|s| s.clone()).unwrap_or_default();
+            return Response::json(200, &json!({ "lines": status.tail(lines) }));
+        }
+        ("GET", "/history") => {
+            let count = match request.query("count").map(str::parse::<usize>) {
+                None => 100,
+                Some(Ok(count)) if count > 0 => count,
+                _ => return Response::error(400, "count must be a positive number"),
+            };
+            let entries = lifecycle::entries(count, request.query("filter").filter(|f| !f.is_empty()));
+            return Response::json(200, &json!({ "events": entries }));
+        }
+        ("GET", "/metrics") => {
+            let status = status.lock().map(|s| s.clone()).unwrap_or_default();
+            return Response::text(&prometheus::render(&status));
+        }
+        ("GET", "/ws/logs") => return log_stream(request, &server.feed),
+        ("POST", "/start") => Command::Start,
+        ("POST", "/stop") => Command::Stop,
+        ("POST", "/restart") => Command::Restart,
+        ("POST", "/watchdog/reset") => Command::WatchdogReset,
+        ("POST", "/backups") => {
+            let job = jobs.create();
+            job_id = Some(job.id);
+            Command::Backup(Some(job))
+        }
+        ("POST", "/extend") => match request.json().map(|body| body["minutes"].as_u64()) {
+            Ok(Some(minutes)) if minutes > 0 => Command::Extend(minutes),
+            _ => return Response::error(400, "expected {\"minutes\": <positive number>}"),
+        },
+        ("POST", "/command") => match request.json().map(|body| body["command"].as_str().map(str::to_string)) {
+            Ok(Some(line)) if !line.trim().is_empty() => Command::Console(line.trim().to_string()),
+            _ => return Response::error(400, "expected {\"command\": \"<server command>\"}"),
+        },
+        (_, "/status" | "/schedule" | "/logs" | "/history" | "/metrics" | "/ws/logs" | "/start" | "/stop" | "/restart" | "/watchdog/reset" | "/backups" | "/extend" | "/command") => {
+            return Response::error(405, "method not allowed")
+        }
+        _ => return Response::error(404, "not found"),
+    };
+    match caller {
+        Some(name) => info!("API: {} {} by {} from {}", request.method, request.path, name, request.peer),
+        None => info!("API: {} {} from {}", request.method, request.path, request.peer),
+    }
+    let who = caller.map_or_else(|| request.peer.ip().to_string(), str::to_string);
+    let sent = server.commands.send(command).is_ok();
+    audit::record("api", &who, &format!("{} {}", request.method, request.path), if sent { "queued" } else { "golem shutting down" });
+    if !sent {
+        return Response::error(500, "the golem is shutting down");
+    }
+    match job_id {
+        Some(id) => Response::json(202, &json!({ "queued": "backups", "id": id, "url": format!("/backups/{}", id) }))
+            .with_header("Location", &format!("/backups/{}", id)),
+        None => Response::json(202, &json!({ "queued": path.trim_start_matches('/') })),
+    }
+}
+
+/// `GET /servers`: every server's name and status.
+fn servers(request: &Request, instances: &Instances) -> Response {
+    if request.method != "GET" {
+        return Response::error(405, "method not allowed");
+    }
+    let servers: Vec<Value> = instances.all().iter().map(server_json).collect();
+    Response::json(200, &json!({ "servers": servers }))
+}
+
+/// The server's status, with its name and display name when it has them.
+pub fn server_json(server: &Handle) -> Value {
+    let mut json = status_json(&server.status());
+    json["name"] = json!(server.name);
+    json["display_name"] = json!(server.display_name);
+    json
+}
+
+/// `POST /alerts/ack`: stops every alert awaiting acknowledgement from
+/// escalating further, as Enter in the console does. `acknowledge` does it,
+/// returning how many, or None without `[escalation]`.
+fn acknowledge(request: &Request, caller: Option<&str>, acknowledge: impl FnOnce() -> Option<usize>) -> Response {
+    if request.method != "POST" {
+        return Response::error(405, "method not allowed");
+    }
+    let Some(count) = acknowledge() else {
+        return Response::error(404, "no [escalation] configured");
+    };
+    let who = caller.map_or_else(|| request.peer.ip().to_string(), str::to_string);
+    info!("API: {} alert(s) acknowledged by {} from {}", count, who, request.peer);
+    audit::record("api", &who, &format!("POST {}", request.path), &format!("{} acknowledged", count));
+    Response::json(200, &json!({ "acknowledged": count }))
+}
+
+/// `GET /queue`, `POST /queue` (`{"action": "stop", "when": "empty"}`) and
+/// `DELETE /queue/<id>`.
+fn queued(request: &Request, path: &str, caller: Option<&str>, queue: &ActionQueue) -> Response {
+    let who = caller.map_or_else(|| request.peer.ip().to_string(), str::to_string);
+    match (request.method.as_str(), path.strip_prefix("/queue/")) {
+        ("GET", None) => Response::json(200, &queue.json()),
+        ("POST", None) => {
+            let body = match request.json() {
+                Ok(body) => body,
+                Err(e) => return Response::error(400, &e),
+            };
+            let Some(action) = body["action"].as_str().and_then(Action::parse) else {
+                return Response::error(400, "expected {\"action\": \"stop\" | \"restart\" | \"backup\", \"when\": \"empty\" | \"idle\"}");
+            };
+            let when = match body["when"].as_str() {
+                None => action.default_when(),
+                Some(when) => match When::parse(when) {
+                    Some(when) => when,
+                    None => return Response::error(400, "when must be \"empty\" or \"idle\""),
+                },
+            };
+            let id = queue.add(action, when, &who);
+            audit::record("api", &who, &format!("queue {} when {}", action.as_str(), when.as_str()), &format!("queued as #{}", id));
+            Response::json(202, &json!({ "id": id, "action": action.as_str(), "when": when.as_str() }))
+        }
+        ("DELETE", Some(id)) => {
+            let Ok(id) = id.parse::<u64>() else {
+                return Response::error(404, "no such queued action");
+            };
+            let cancelled = queue.cancel(id);
+            audit::record("api", &who, &format!("queue cancel #{}", id), if cancelled { "cancelled" } else { "not queued" });
+            if cancelled {
+                Response::json(200, &json!({ "cancelled": id }))
+            } else {
+                Response::error(404, "no such queued action")
+            }
+        }
+        _ => Response::error(405, "method not allowed"),
+    }
+}
+
+/// Each day's hours, and whether they are its own or the daily ones.
+fn schedule_json(week: &[(Weekday, Hours, bool)]) -> Value {
+    let days: Vec<Value> = week
+        .iter()
+        .map(|(day, hours, own)| json!({ "day": schedule::day_name(*day), "hours": hours.to_string(), "own": own }))
+        .collect();
+    json!({ "days": days })
+}
+
+/// `{"friday": "18:00-24:00", "sunday": "closed", "monday": null}`, where
+/// null (or "default") puts a day back on the daily hours.
+fn schedule_changes(body: &Value) -> Result<Vec<(Weekday, Option<Hours>)>, String> {
+    let days = body.as_object().filter(|days| !days.is_empty()).ok_or("expected {\"<day>\": \"18:00-24:00\"}")?;
+    days.iter()
+        .map(|(day, hours)| match hours {
+            Value::Null => schedule::parse_change(day, "default"),
+            Value::String(hours) => schedule::parse_change(day, hours),
+            _ => Err(format!("{}: expected hours like \"18:00-24:00\", \"closed\" or null", day)),
+        })
+        .collect()
+}
+
+/// Just enough for a community website to show whether the server is up;
+/// open to all, from any origin.
+fn public_status(request: &Request, status: &SharedStatus) -> Response {
+    if request.method != "GET" {
+        return Response::error(405, "method not allowed");
+    }
+    let status = status.lock().map(|s| s.clone()).unwrap_or_default();
+    let body = json!({
+        "online": status.online,
+        "players": status.players,
+        "opens_at": status.opens_at.map(|t| t.to_rfc3339()),
+        "closes_at": status.closes_at.map(|t| t.to_rfc3339()),
+    });
+    Response::json(200, &body)
+        .with_header("Access-Control-Allow-Origin", "*")
+        .with_header("Cache-Control", "max-age=10")
+}
+
+/// Shared with players, so it may be cached by browsers and proxies alike.
+fn public_page(request: &Request, config: &StatusPageConfig, status: &SharedStatus) -> Response {
+    if request.method != "GET" {
+        return Response::error(405, "method not allowed");
+    }
+    let status = status.lock().map(|s| s.clone()).unwrap_or_default();
+    Response::html(&status_page::render(config, &status)).with_header("Cache-Control", "public, max-age=30")
+}
+
+/// Upgrades to a WebSocket carrying the live log, narrowed by `?filter=<regex>`.
+pub fn log_stream(request: &Request, feed: &LogFeed) -> Response {
+    let filter = match feed::filter(request.query("filter")) {
+        Ok(filter) => filter,
+        Err(e) => return Response::error(400, &format!("bad filter: {}", e)),
+    };
+    debug!("API: log stream opened by {}", request.peer);
+    let feed = feed.clone();
+    Response::websocket(request, move |socket| feed::stream(&feed, filter, socket))
+}
+
+pub fn status_json(status: &Status) -> Value {
+    json!({
+        "online": status.online,
+        "online_since": status.online_since.map(|t| t.to_rfc3339()),
+        "pid": status.pid.filter(|_| status.online),
+        "uptime_secs": status.online_since.filter(|_| status.online).map(|t| (Local::now() - t).num_seconds()),
+        "players": status.players,
+        "player_names": status.player_names,
+        "tps": status.tps.filter(|_| status.online),
+        "rss_bytes": status.rss_bytes.filter(|_| status.online),
+        "last_backup": status.last_backup.as_ref().map(|r| r.created.to_rfc3339()),
+        "closes_at": status.closes_at.map(|t| t.to_rfc3339()),
+        "opens_at": status.opens_at.map(|t| t.to_rfc3339()),
+        "schedule_paused": status.schedule_paused,
+    })
+}
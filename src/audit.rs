@@ -0,0 +1,85 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Value};
+use tracing::warn;
+
+/// One JSON object per line, only ever appended to.
+const AUDIT_FILE: &str = "audit.jsonl";
+
+/// Keeps lines from different interfaces' threads whole.
+static WRITING: Mutex<()> = Mutex::new(());
+
+/// Notes who asked for what through which interface, and what came of it,
+/// e.g. `record("discord", "alice", "/restart", "confirmed")`.
+pub fn record(source: &str, who: &str, action: &str, result: &str) {
+    let entry = json!({
+        "time": Local::now().to_rfc3339(),
+        "source": source,
+        "who": who,
+        "action": action,
+        "result": result,
+    });
+    let _guard = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(AUDIT_FILE)
+        .and_then(|mut file| writeln!(file, "{}", entry));
+    if let Err(e) = written {
+        warn!("Audit: could not write {}: {}", AUDIT_FILE, e);
+    }
+}
+
+/// `audit list [<count>] [<filter>]`, given the words after `list`; the last
+/// 20 entries by default.
+pub fn list(args: &[&str]) -> Vec<String> {
+    let (count, filter) = match args.split_first() {
+        Some((count, filter)) if count.parse::<usize>().is_ok() => (count.parse().unwrap_or_default(), filter),
+        _ => (20, args),
+    };
+    let filter = filter.join(" ");
+    list_lines(count, Some(filter.as_str()).filter(|f| !f.is_empty()))
+}
+
+/// The last `count` entries, oldest first, narrowed to those mentioning
+/// `filter` (a source, name, action or result) if given.
+fn list_lines(count: usize, filter: Option<&str>) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(AUDIT_FILE) else {
+        return vec!["Nothing recorded yet.".to_string()];
+    };
+    let filter = filter.map(str::to_lowercase);
+    let entries: Vec<Value> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|entry| {
+            let Some(filter) = &filter else {
+                return true;
+            };
+            ["source", "who", "action", "result"]
+                .iter()
+                .any(|field| entry[field].as_str().is_some_and(|v| v.to_lowercase().contains(filter)))
+        })
+        .collect();
+    if entries.is_empty() {
+        return vec!["No matching entries.".to_string()];
+    }
+    let text = |entry: &Value, field: &str| entry[field].as_str().unwrap_or_default().to_string();
+    entries[entries.len().saturating_sub(count)..]
+        .iter()
+        .map(|entry| {
+            let time = DateTime::parse_from_rfc3339(&text(entry, "time"))
+                .map_or(text(entry, "time"), |t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+            format!(
+                "{}  {:<9} {:<16} {:<24} {}",
+                time,
+                text(entry, "source"),
+                text(entry, "who"),
+                text(entry, "action"),
+                text(entry, "result")
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::digest::format_duration;
+use crate::instance;
+use crate::messages::Messages;
+
+const AVAILABILITY_FILE: &str = "availability.json";
+/// Days of history kept in the file.
+const KEEP_DAYS: u64 = 90;
+/// A longer gap between two checks means the golem itself was not running
+/// (or the machine slept), which says nothing about the server.
+const MAX_GAP: Duration = Duration::from_secs(120);
+
+/// Seconds counted on one calendar day.
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+struct Day {
+    /// The server was running, scheduled or not.
+    up_secs: u64,
+    /// The schedule (or a manual start) wanted the server running.
+    scheduled_secs: u64,
+    /// Both at once.
+    up_scheduled_secs: u64,
+}
+
+impl Day {
+    fn add(&mut self, other: &Day) {
+        self.up_secs += other.up_secs;
+        self.scheduled_secs += other.scheduled_secs;
+        self.up_scheduled_secs += other.up_scheduled_secs;
+    }
+
+    fn down_in_window(&self) -> Duration {
+        Duration::from_secs(self.scheduled_secs.saturating_sub(self.up_scheduled_secs))
+    }
+
+    /// Share of the scheduled time the server was up, e.g. "99.2%"; "-" when
+    /// nothing was scheduled.
+    fn percent(&self) -> String {
+        if self.scheduled_secs == 0 {
+            return "-".to_string();
+        }
+        format!("{:.1}%", self.up_scheduled_secs as f64 * 100.0 / self.scheduled_secs as f64)
+    }
+}
+
+#[derive(Default)]
+struct Tracker {
+    days: BTreeMap<NaiveDate, Day>,
+    checked_at: Option<DateTime<Local>>,
+    saved_at: Option<Instant>,
+}
+
+/// Uptime and downtime inside the running window per day, kept in
+/// availability.json. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct Availability {
+    inner: Arc<Mutex<Tracker>>,
+}
+
+impl Availability {
+    /// Picks up the history in availability.json; a missing file means none.
+    pub fn load() -> Availability {
+        let days = match fs::read_to_string(instance::path(AVAILABILITY_FILE)) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring {}: {}", AVAILABILITY_FILE, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Availability { inner: Arc::new(Mutex::new(Tracker { days, ..Tracker::default() })) }
+    }
+
+    /// Counts the time since the last call as up or down, inside the window or
+    /// not, and saves about once a minute.
+    pub fn record(&self, now: DateTime<Local>, online: bool, scheduled: bool) {
+        let mut tracker = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = tracker.checked_at.and_then(|t| (now - t).to_std().ok());
+        tracker.checked_at = Some(now);
+        let Some(elapsed) = elapsed.filter(|e| *e <= MAX_GAP) else {
+            return;
+        };
+        let secs = elapsed.as_secs();
+        let day = tracker.days.entry(now.date_naive()).or_default();
+        if online {
+            day.up_secs += secs;
+        }
+        if scheduled {
+            day.scheduled_secs += secs;
+            if online {
+                day.up_scheduled_secs += secs;
+            }
+        }
+        if tracker.saved_at.is_none_or(|t| t.elapsed() >= Duration::from_secs(60)) {
+            tracker.saved_at = Some(Instant::now());
+            if let Some(oldest) = now.date_naive().checked_sub_days(Days::new(KEEP_DAYS)) {
+                tracker.days.retain(|date, _| *date > oldest);
+            }
+            let written = serde_json::to_string_pretty(&tracker.days)
+                .map_err(std::io::Error::other)
+                .and_then(|json| fs::write(instance::path(AVAILABILITY_FILE), json));
+            if let Err(e) = written {
+                warn!("Could not save {}: {}", AVAILABILITY_FILE, e);
+            }
+        }
+    }
+
+    /// The last `days` days, today included.
+    fn total(&self, today: NaiveDate, days: u64) -> Day {
+        let tracker = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let first = today.checked_sub_days(Days::new(days - 1)).unwrap_or(today);
+        let mut total = Day::default();
+        for day in tracker.days.range(first..=today).map(|(_, day)| day) {
+            total.add(day);
+        }
+        total
+    }
+
+    /// How long the server ran over the last `days` days, today included.
+    pub fn uptime(&self, days: u64) -> Duration {
+        Duration::from_secs(self.total(Local::now().date_naive(), days).up_secs)
+    }
+
+    /// The answer to `stats`: today, yesterday, the last 7 and 30 days.
+    pub fn lines(&self) -> Vec<String> {
+        let today = Local::now().date_naive();
+        let mut lines = vec![format!("{:<13} {:>9} {:>15} {:>13}", "", "Uptime", "Down in window", "Availability")];
+        let yesterday = today.pred_opt().unwrap_or(today);
+        let periods = [
+            ("Today", self.total(today, 1)),
+            ("Yesterday", self.total(yesterday, 1)),
+            ("Last 7 days", self.total(today, 7)),
+            ("Last 30 days", self.total(today, 30)),
+        ];
+        for (name, day) in periods {
+            lines.push(format!(
+                "{:<13} {:>9} {:>15} {:>13}",
+                name,
+                format_duration(Duration::from_secs(day.up_secs)),
+                format_duration(day.down_in_window()),
+                day.percent()
+            ));
+        }
+        lines
+    }
+
+    /// Embed fields for the digest: today's availability and the week's.
+    pub fn fields(&self, messages: &Messages) -> Vec<(String, String)> {
+        let today = Local::now().date_naive();
+        let day = self.total(today, 1);
+        let week = self.total(today, 7);
+        let value = |day: &Day| {
+            messages.get(
+                "digest_availability_value",
+                &[("percent", day.percent()), ("down", format_duration(day.down_in_window()))],
+            )
+        };
+        vec![
+            (messages.get("digest_availability", &[]), value(&day)),
+            (messages.get("digest_availability_week", &[]), value(&week)),
+        ]
+    }
+}
@@ -0,0 +1,209 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::exclude::Excludes;
+use super::throttle::Throttle;
+use crate::metrics::{self, format_bytes};
+
+/// A file to archive and the name it gets inside the archive.
+#[derive(Clone)]
+pub struct Entry {
+    pub source: PathBuf,
+    pub name: String,
+    pub size: u64,
+}
+
+/// Checksum of one backed-up file, as read from the world folder.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileHash {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Passes data through while hashing it.
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            hasher: Sha256::new(),
+            bytes: 0,
+        }
+    }
+
+    pub fn finish(self, name: &str) -> FileHash {
+        FileHash {
+            name: name.to_string(),
+            size: self.bytes,
+            sha256: hex(&self.hasher.finalize()),
+        }
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.bytes += n as u64;
+        Ok(n)
+    }
+}
+
+/// Hashes everything `reader` yields.
+pub fn hash_reader(name: &str, reader: impl Read) -> io::Result<FileHash> {
+    let mut hashing = HashingReader::new(reader);
+    io::copy(&mut hashing, &mut io::sink())?;
+    Ok(hashing.finish(name))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Adds a single file, or everything below a folder, to `entries`.
+pub fn collect_path(base: &Path, path: &Path, excludes: &Excludes, entries: &mut Vec<Entry>) -> io::Result<u64> {
+    if path.is_dir() {
+        return collect_entries(base, path, excludes, entries);
+    }
+    entries.push(Entry {
+        source: path.to_path_buf(),
+        name: relative_name(base, path),
+        size: fs::metadata(path)?.len(),
+    });
+    Ok(0)
+}
+
+fn relative_name(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Lists every file below `dir`, named relative to `base` with `/` separators.
+/// Excluded folders are not descended into. Returns how many bytes were skipped.
+pub fn collect_entries(base: &Path, dir: &Path, excludes: &Excludes, entries: &mut Vec<Entry>) -> io::Result<u64> {
+    let mut skipped = 0;
+    for item in fs::read_dir(dir)? {
+        let item = item?;
+        let path = item.path();
+        let file_type = item.file_type()?;
+        let name = relative_name(base, &path);
+        if excludes.matches(&name, file_type.is_dir()) {
+            skipped += if file_type.is_dir() {
+                metrics::dir_size(&path)
+            } else {
+                item.metadata()?.len()
+            };
+            continue;
+        }
+        if file_type.is_dir() {
+            skipped += collect_entries(base, &path, excludes, entries)?;
+        } else if file_type.is_file() {
+            // Held open by a running server and useless in a backup
+            if item.file_name() == "session.lock" {
+                continue;
+            }
+            entries.push(Entry {
+                source: path,
+                name,
+                size: item.metadata()?.len(),
+            });
+        }
+    }
+    Ok(skipped)
+}
+
+/// Logs archiving progress every 10%.
+struct Progress {
+    total: u64,
+    done: u64,
+    last_reported: u64,
+}
+
+impl Progress {
+    fn new(entries: &[Entry]) -> Self {
+        Progress {
+            total: entries.iter().map(|e| e.size).sum(),
+            done: 0,
+            last_reported: 0,
+        }
+    }
+
+    fn add(&mut self, bytes: u64) {
+        self.done += bytes;
+        let percent = (self.done * 100).checked_div(self.total).unwrap_or(100);
+        if percent >= self.last_reported + 10 {
+            self.last_reported = percent - percent % 10;
+            info!(
+                "Backup: {}% ({} / {})",
+                self.last_reported,
+                format_bytes(self.done),
+                format_bytes(self.total)
+            );
+        }
+    }
+}
+
+/// Writes `entries` into a deflate zip file at `target`, returning the
+/// checksums of what was read.
+pub fn write_zip(target: &Path, entries: &[Entry], level: Option<i32>, throttle: &Throttle) -> io::Result<Vec<FileHash>> {
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(target)?));
+    let mut progress = Progress::new(entries);
+    let mut hashes = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .compression_level(level.map(i64::from))
+            .large_file(entry.size >= u32::MAX as u64);
+        zip.start_file(entry.name.as_str(), options)?;
+        let mut source = HashingReader::new(throttle.reader(File::open(&entry.source)?));
+        io::copy(&mut source, &mut zip)?;
+        hashes.push(source.finish(&entry.name));
+        progress.add(entry.size);
+    }
+    zip.finish()?;
+    Ok(hashes)
+}
+
+/// Writes `entries` into a zstd-compressed tarball at `target`. Low levels are
+/// much faster than deflate and still shrink region files well.
+pub fn write_tar_zst(
+    target: &Path,
+    entries: &[Entry],
+    level: Option<i32>,
+    throttle: &Throttle,
+) -> io::Result<Vec<FileHash>> {
+    let encoder = zstd::Encoder::new(BufWriter::new(File::create(target)?), level.unwrap_or(3))?;
+    let mut tar = tar::Builder::new(encoder);
+    let mut progress = Progress::new(entries);
+    let mut hashes = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let source = File::open(&entry.source)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&source.metadata()?);
+        let mut source = HashingReader::new(throttle.reader(source));
+        tar.append_data(&mut header, &entry.name, &mut source)?;
+        hashes.push(source.finish(&entry.name));
+        progress.add(entry.size);
+    }
+    tar.into_inner()?.finish()?.into_inner().map_err(|e| e.into_error())?;
+    Ok(hashes)
+}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::archive::FileHash;
+
+const CATALOG_FILE: &str = "catalog.json";
+const MANIFEST_DIR: &str = "manifests";
+const DIFF_DIR: &str = "diffs";
+
+/// One backup in the backup directory's catalog.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Record {
+    pub name: String,
+    pub created: DateTime<Local>,
+    pub size_bytes: u64,
+    pub duration_secs: f64,
+    pub files: usize,
+    /// None when verification is turned off.
+    pub verified: Option<bool>,
+    /// What started the backup; None for backups taken before this was tracked.
+    #[serde(default)]
+    pub trigger: Option<Trigger>,
+    /// Checksum of the archive file itself; directory backups only have the manifest.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Off-site destinations a copy was uploaded to.
+    #[serde(default)]
+    pub destinations: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Trigger {
+    /// From the command line or the golem console.
+    Manual,
+    Interval,
+    Cron,
+    Stop,
+    PreUpdate,
+    Crash,
+}
+
+impl Trigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Trigger::Manual => "manual",
+            Trigger::Interval => "interval",
+            Trigger::Cron => "cron",
+            Trigger::Stop => "stop",
+            Trigger::PreUpdate => "pre-update",
+            Trigger::Crash => "crash",
+        }
+    }
+}
+
+/// What went into a backup, for verification and later restores.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Manifest {
+    pub files: Vec<FileHash>,
+    /// The exclude patterns in effect when the backup was taken.
+    #[serde(default)]
+    pub excludes: Vec<String>,
+}
+
+pub fn load(directory: &Path) -> Vec<Record> {
+    fs::read_to_string(directory.join(CATALOG_FILE))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Adds or replaces the record with the same name.
+pub fn save_record(directory: &Path, record: Record) -> io::Result<()> {
+    let mut records = load(directory);
+    records.retain(|r| r.name != record.name);
+    records.push(record);
+    write_json(&directory.join(CATALOG_FILE), &records)
+}
+
+/// Records which destinations now hold a copy of backup `name`.
+pub fn set_destinations(directory: &Path, name: &str, destinations: Vec<String>) -> io::Result<()> {
+    let mut records = load(directory);
+    if let Some(record) = records.iter_mut().find(|r| r.name == name) {
+        record.destinations = destinations;
+    }
+    write_json(&directory.join(CATALOG_FILE), &records)
+}
+
+/// Forgets a deleted backup: its record, manifest and config diff.
+pub fn remove(directory: &Path, name: &str) -> io::Result<()> {
+    let mut records = load(directory);
+    records.retain(|r| r.name != name);
+    let _ = fs::remove_file(manifest_path(directory, name));
+    let _ = fs::remove_file(diff_path(directory, name));
+    write_json(&directory.join(CATALOG_FILE), &records)
+}
+
+pub fn save_manifest(directory: &Path, name: &str, manifest: &Manifest) -> io::Result<()> {
+    fs::create_dir_all(directory.join(MANIFEST_DIR))?;
+    write_json(&manifest_path(directory, name), manifest)
+}
+
+pub fn load_manifest(directory: &Path, name: &str) -> Option<Manifest> {
+    let text = fs::read_to_string(manifest_path(directory, name)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Keeps the config changes a backup introduced next to its manifest.
+pub fn save_diff(directory: &Path, name: &str, diff: &str) -> io::Result<()> {
+    fs::create_dir_all(directory.join(DIFF_DIR))?;
+    fs::write(diff_path(directory, name), diff)
+}
+
+pub fn diff_path(directory: &Path, name: &str) -> PathBuf {
+    directory.join(DIFF_DIR).join(format!("{}.diff", name))
+}
+
+fn manifest_path(directory: &Path, name: &str) -> PathBuf {
+    directory.join(MANIFEST_DIR).join(format!("{}.json", name))
+}
+
+// Replace atomically so a crash mid-write never loses the whole catalog
+fn write_json(path: &Path, value: &impl Serialize) -> io::Result<()> {
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(&partial, path)
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::archive::Entry;
+
+/// Latest copy of each backed-up config file, per backup scope, to diff against.
+const SNAPSHOT_DIR: &str = "config-snapshots";
+/// Files above this size are not treated as hand-edited configuration.
+const MAX_CONFIG_BYTES: u64 = 256 * 1024;
+
+/// What changed in the configuration files of a scope since its last backup.
+#[derive(Default)]
+pub struct ConfigDiff {
+    /// One short line per notable change, e.g. "server.properties: view-distance 10→6".
+    pub summary: Vec<String>,
+    /// Full line-level diff of every changed file.
+    pub detail: String,
+}
+
+fn is_config(entry: &Entry) -> bool {
+    let name = entry.name.to_lowercase();
+    entry.size <= MAX_CONFIG_BYTES
+        && [".properties", ".yml", ".yaml", ".toml", ".json", ".conf", ".cfg", ".ini", ".txt"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+}
+
+/// Compares the config files among `entries` with the snapshot kept from the
+/// previous backup of `scope`, then replaces that snapshot with the current
+/// files. Nothing is reported for the first backup of a scope.
+pub fn record(directory: &Path, scope: &str, entries: &[Entry]) -> io::Result<ConfigDiff> {
+    let snapshot_dir = directory.join(SNAPSHOT_DIR).join(scope);
+    let first = !snapshot_dir.exists();
+    let current: BTreeMap<&str, String> = entries
+        .iter()
+        .filter(|e| is_config(e))
+        .filter_map(|e| {
+            let bytes = fs::read(&e.source).ok()?;
+            Some((e.name.as_str(), String::from_utf8_lossy(&bytes).to_string()))
+        })
+        .collect();
+    let mut previous = BTreeMap::new();
+    read_snapshot(&snapshot_dir, &snapshot_dir, &mut previous);
+
+    let mut diff = ConfigDiff::default();
+    if !first {
+        for (name, text) in &current {
+            match previous.get(*name) {
+                Some(old) if old == text => {}
+                Some(old) => compare(name, old, text, &mut diff),
+                None => {
+                    diff.summary.push(format!("{}: added", name));
+                    diff.detail.push_str(&format!("+++ {} (new file)\n", name));
+                }
+            }
+        }
+        for name in previous.keys().filter(|n| !current.contains_key(n.as_str())) {
+            diff.summary.push(format!("{}: removed", name));
+            diff.detail.push_str(&format!("--- {} (removed)\n", name));
+        }
+    }
+
+    let _ = fs::remove_dir_all(&snapshot_dir);
+    for (name, text) in &current {
+        let path = snapshot_dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, text)?;
+    }
+    Ok(diff)
+}
+
+fn read_snapshot(base: &Path, dir: &Path, files: &mut BTreeMap<String, String>) {
+    let Ok(items) = fs::read_dir(dir) else {
+        return;
+    };
+    for item in items.flatten() {
+        let path = item.path();
+        if path.is_dir() {
+            read_snapshot(base, &path, files);
+        } else if let (Ok(relative), Ok(text)) = (path.strip_prefix(base), fs::read_to_string(&path)) {
+            let name = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            files.insert(name, text);
+        }
+    }
+}
+
+fn compare(name: &str, old: &str, new: &str, diff: &mut ConfigDiff) {
+    diff.detail.push_str(&format!("--- {}\n+++ {}\n", name, name));
+    let removed: Vec<&str> = old.lines().filter(|l| !new.lines().any(|n| n == *l)).collect();
+    let added: Vec<&str> = new.lines().filter(|l| !old.lines().any(|o| o == *l)).collect();
+    for line in &removed {
+        diff.detail.push_str(&format!("-{}\n", line));
+    }
+    for line in &added {
+        diff.detail.push_str(&format!("+{}\n", line));
+    }
+
+    if name.ends_with(".properties") {
+        // Key-level changes read much better than raw lines for key=value files
+        let (old, new) = (properties(old), properties(new));
+        for (key, value) in &new {
+            match old.get(key) {
+                Some(before) if before != value => diff.summary.push(format!("{}: {} {}→{}", name, key, before, value)),
+                None => diff.summary.push(format!("{}: {}={} (new)", name, key, value)),
+                _ => {}
+            }
+        }
+        for key in old.keys().filter(|k| !new.contains_key(*k)) {
+            diff.summary.push(format!("{}: {} removed", name, key));
+        }
+    } else if !added.is_empty() || !removed.is_empty() {
+        diff.summary.push(format!("{}: +{} −{} line(s)", name, added.len(), removed.len()));
+    }
+}
+
+fn properties(text: &str) -> BTreeMap<&str, &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with('!'))
+        .filter_map(|l| l.split_once('=').map(|(k, v)| (k.trim(), v.trim())))
+        .collect()
+}
@@ -0,0 +1,58 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+use super::catalog::Trigger;
+use super::throttle::Throttle;
+use super::{archive_scope, destination, BackupResult, Scope};
+use crate::config::{BackupConfig, BackupMode, Config, CrashState, RetentionConfig};
+use crate::server_props;
+
+/// Subfolder of the backup directory for crash-state snapshots.
+const CRASH_DIR: &str = "crash-states";
+const PREFIX: &str = "crash-state";
+
+/// Preserves the on-disk state right after a crash for post-mortem analysis:
+/// logs, crash reports and level.dat, or the complete worlds with
+/// `crash_state = "world"`. Only the newest `crash_state_keep` are kept.
+pub fn create_crash_state(config: &Config, backup: &BackupConfig) -> io::Result<Option<BackupResult>> {
+    if backup.crash_state == CrashState::Off {
+        return Ok(None);
+    }
+    let server_dir = config.server_dir();
+    let mut sources: Vec<PathBuf> = ["logs", "crash-reports"].iter().map(|d| server_dir.join(d)).collect();
+    for world in server_props::world_dirs(&server_dir) {
+        if backup.crash_state == CrashState::World {
+            sources.push(world);
+        } else {
+            sources.extend(["level.dat", "level.dat_old"].iter().map(|f| world.join(f)));
+        }
+    }
+    sources.retain(|p| p.exists());
+
+    let directory = Path::new(&backup.directory).join(CRASH_DIR);
+    let scope = Scope {
+        name: PREFIX.to_string(),
+        directory: directory.clone(),
+        sources,
+        extra: Vec::new(),
+        incremental: false,
+        config_diff: false,
+        trigger: Trigger::Crash,
+    };
+    let full = BackupConfig {
+        mode: BackupMode::Full,
+        ..backup.clone()
+    };
+    let result = archive_scope(config, &full, &scope, &Throttle::unlimited())?;
+
+    let retention = RetentionConfig {
+        keep_count: Some(backup.crash_state_keep.max(1)),
+        keep_days: None,
+    };
+    if let Err(e) = destination::apply_retention(&destination::Local::new(&directory), &retention, PREFIX) {
+        warn!("Backup: cleanup of old crash states failed: {}", e);
+    }
+    Ok(Some(result))
+}
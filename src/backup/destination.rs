@@ -0,0 +1,182 @@
+use std::cmp::Reverse;
+use std::io::{Read, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use tracing::{info, warn};
+
+use super::catalog;
+use super::incremental::SNAPSHOT_SUFFIX;
+use super::ftp::FtpDestination;
+use super::rclone::RcloneDestination;
+use super::s3::S3Destination;
+use super::sftp::SftpDestination;
+use crate::config::{BackupConfig, RetentionConfig};
+
+/// A backup already stored at a destination.
+pub struct RemoteBackup {
+    pub name: String,
+    pub modified: DateTime<Utc>,
+}
+
+/// Somewhere off-site that finished archives are copied to.
+pub trait Destination {
+    fn name(&self) -> String;
+    fn upload(&self, path: &Path) -> Result<(), String>;
+    fn list(&self) -> Result<Vec<RemoteBackup>, String>;
+    fn delete(&self, name: &str) -> Result<(), String>;
+}
+
+pub fn from_config(backup: &BackupConfig) -> Vec<(Box<dyn Destination>, Option<RetentionConfig>)> {
+    let mut destinations: Vec<(Box<dyn Destination>, Option<RetentionConfig>)> = Vec::new();
+    if let Some(s3) = &backup.s3 {
+        destinations.push((Box::new(S3Destination::new(s3.clone())), s3.retention.clone()));
+    }
+    if let Some(sftp) = &backup.sftp {
+        destinations.push((Box::new(SftpDestination::new(sftp.clone())), sftp.retention.clone()));
+    }
+    if let Some(ftp) = &backup.ftp {
+        destinations.push((Box::new(FtpDestination::new(ftp.clone())), ftp.retention.clone()));
+    }
+    for rclone in &backup.rclone {
+        destinations.push((Box::new(RcloneDestination::new(rclone.clone())), rclone.retention.clone()));
+    }
+    destinations
+}
+
+/// The local backup directory, so the same retention rules apply to it.
+pub struct Local {
+    directory: PathBuf,
+}
+
+impl Local {
+    pub fn new(directory: &Path) -> Self {
+        Local {
+            directory: directory.to_path_buf(),
+        }
+    }
+}
+
+impl Destination for Local {
+    fn name(&self) -> String {
+        self.directory.display().to_string()
+    }
+
+    fn upload(&self, path: &Path) -> Result<(), String> {
+        let file_name = path.file_name().ok_or("backup has no file name")?;
+        fs::copy(path, self.directory.join(file_name)).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<RemoteBackup>, String> {
+        let entries = fs::read_dir(&self.directory).map_err(|e| e.to_string())?;
+        Ok(entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                (!name.ends_with(".partial")).then(|| RemoteBackup {
+                    name,
+                    modified: modified.into(),
+                })
+            })
+            .collect())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        let path = self.directory.join(name);
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        removed.map_err(|e| e.to_string())?;
+        catalog::remove(&self.directory, name).map_err(|e| e.to_string())
+    }
+}
+
+/// Deletes backups named `<prefix>-...` beyond the newest `keep_count`, and any
+/// older than `keep_days`. The full baseline before the oldest incremental
+/// snapshot that stays is kept with it.
+pub fn apply_retention(destination: &dyn Destination, retention: &RetentionConfig, prefix: &str) -> Result<usize, String> {
+    let prefix = format!("{}-", prefix);
+    let mut backups: Vec<RemoteBackup> = destination
+        .list()?
+        .into_iter()
+        .filter(|b| b.name.starts_with(&prefix))
+        .collect();
+    backups.sort_by_key(|b| Reverse(b.modified));
+
+    let cutoff = retention
+        .keep_days
+        .map(|days| Utc::now() - ChronoDuration::days(days as i64));
+    let mut doomed: Vec<bool> = backups
+        .iter()
+        .enumerate()
+        .map(|(i, backup)| {
+            let over_count = retention.keep_count.is_some_and(|n| i >= n);
+            let too_old = cutoff.is_some_and(|c| backup.modified < c);
+            // Never delete the newest backup, whatever the policy says
+            i > 0 && (over_count || too_old)
+        })
+        .collect();
+    let snapshot = |backup: &RemoteBackup| backup.name.ends_with(SNAPSHOT_SUFFIX);
+    let oldest_kept_snapshot = (0..backups.len()).rev().find(|&i| !doomed[i] && snapshot(&backups[i]));
+    if let Some(baseline) = oldest_kept_snapshot.and_then(|i| (i + 1..backups.len()).find(|&j| !snapshot(&backups[j]))) {
+        doomed[baseline] = false;
+    }
+    let mut deleted = 0;
+    for (backup, _) in backups.iter().zip(doomed).filter(|(_, doomed)| *doomed) {
+        destination.delete(&backup.name)?;
+        info!("Backup: removed {} from {} (retention)", backup.name, destination.name());
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+const UPLOAD_ATTEMPTS: u32 = 5;
+
+/// Runs a resumable transfer, reconnecting after transient failures. Each
+/// attempt is expected to continue from whatever already reached the remote.
+pub fn with_resume(name: &str, mut attempt: impl FnMut() -> Result<(), String>) -> Result<(), String> {
+    let mut tries = 0;
+    loop {
+        tries += 1;
+        match attempt() {
+            Ok(()) => return Ok(()),
+            Err(e) if tries < UPLOAD_ATTEMPTS => {
+                let delay = Duration::from_secs(5 * 2u64.pow(tries - 1));
+                warn!("Backup: transfer to {} interrupted ({}); resuming in {}s", name, e, delay.as_secs());
+                thread::sleep(delay);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Copies everything from `reader`, sleeping as needed to stay under
+/// `limit_kbps` kilobytes per second.
+pub fn copy_limited(reader: &mut impl Read, writer: &mut impl Write, limit_kbps: Option<u64>) -> Result<u64, String> {
+    let started = Instant::now();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut copied = 0u64;
+    loop {
+        let n = reader.read(&mut buffer).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..n]).map_err(|e| e.to_string())?;
+        copied += n as u64;
+        if let Some(limit) = limit_kbps.filter(|l| *l > 0) {
+            let expected = Duration::from_secs_f64(copied as f64 / (limit * 1024) as f64);
+            if let Some(ahead) = expected.checked_sub(started.elapsed()) {
+                thread::sleep(ahead);
+            }
+        }
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(copied)
+}
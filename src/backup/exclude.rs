@@ -0,0 +1,69 @@
+/// Gitignore-style exclude patterns, matched against paths relative to the
+/// server folder (e.g. `world/region/r.0.0.mca`).
+///
+/// - `*` and `?` match within one path segment, `**` across segments.
+/// - A trailing `/` only matches folders.
+/// - A pattern without `/` matches any single segment, so `*.tmp` and `cache/`
+///   apply at every depth; one with `/` is anchored at the server folder or
+///   at a world folder, so `world/DIM-1` and `dynmap/tiles` both work.
+pub struct Excludes {
+    patterns: Vec<Pattern>,
+}
+
+struct Pattern {
+    glob: String,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl Excludes {
+    pub fn new(patterns: &[String]) -> Self {
+        Excludes {
+            patterns: patterns
+                .iter()
+                .map(|p| p.trim().replace('\\', "/"))
+                .filter(|p| !p.is_empty())
+                .map(|p| {
+                    let dir_only = p.ends_with('/');
+                    let glob = p.trim_matches('/').to_string();
+                    Pattern {
+                        anchored: glob.contains('/'),
+                        glob,
+                        dir_only,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+        self.patterns.iter().any(|p| {
+            if p.dir_only && !is_dir {
+                return false;
+            }
+            if p.anchored {
+                let in_world = path.split_once('/').map_or("", |(_, rest)| rest);
+                glob_match(p.glob.as_bytes(), path.as_bytes()) || glob_match(p.glob.as_bytes(), in_world.as_bytes())
+            } else {
+                let segment = path.rsplit('/').next().unwrap_or(path);
+                glob_match(p.glob.as_bytes(), segment.as_bytes())
+            }
+        })
+    }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => {
+            let segment_end = text.iter().position(|&c| c == b'/').unwrap_or(text.len());
+            (0..=segment_end).any(|i| glob_match(rest, &text[i..]))
+        }
+        [b'?', rest @ ..] => matches!(text, [c, tail @ ..] if *c != b'/' && glob_match(rest, tail)),
+        [c, rest @ ..] => matches!(text, [t, tail @ ..] if t == c && glob_match(rest, tail)),
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use tracing::debug;
+
+use super::destination::{self, Destination, RemoteBackup};
+use crate::config::FtpConfig;
+
+/// Plain FTP (passive mode) for NAS boxes that offer nothing else.
+pub struct FtpDestination {
+    config: FtpConfig,
+}
+
+impl FtpDestination {
+    pub fn new(config: FtpConfig) -> Self {
+        FtpDestination { config }
+    }
+
+    fn connect(&self) -> Result<Session, String> {
+        let stream = TcpStream::connect((self.config.host.as_str(), self.config.port)).map_err(|e| e.to_string())?;
+        stream.set_read_timeout(Some(Duration::from_secs(60))).map_err(|e| e.to_string())?;
+        let mut session = Session {
+            reader: BufReader::new(stream.try_clone().map_err(|e| e.to_string())?),
+            stream,
+        };
+        session.expect_reply(&[220])?;
+        session.command(&format!("USER {}", self.config.username), &[230, 331])?;
+        session.command(&format!("PASS {}", self.config.password), &[230, 202])?;
+        session.command("TYPE I", &[200])?;
+        if !self.config.remote_dir.is_empty() {
+            // Create the directory and its parents on first use; an error here
+            // means one already exists
+            let mut dir = String::new();
+            for part in self.config.remote_dir.split('/') {
+                dir.push_str(part);
+                if !part.is_empty() {
+                    let _ = session.command(&format!("MKD {}", dir), &[257]);
+                }
+                dir.push('/');
+            }
+            session.command(&format!("CWD {}", self.config.remote_dir), &[250])?;
+        }
+        Ok(session)
+    }
+}
+
+struct Session {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Session {
+    /// Reads one (possibly multi-line) reply and checks its code.
+    fn expect_reply(&mut self, ok: &[u32]) -> Result<String, String> {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let code: u32 = line.get(..3).and_then(|c| c.parse().ok()).ok_or(format!("bad FTP reply: {}", line.trim()))?;
+        let mut reply = line.clone();
+        if line.as_bytes().get(3) == Some(&b'-') {
+            let end = format!("{} ", code);
+            loop {
+                line.clear();
+                if self.reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+                    break;
+                }
+                reply.push_str(&line);
+                if line.starts_with(&end) {
+                    break;
+                }
+            }
+        }
+        if ok.contains(&code) {
+            Ok(reply.trim().to_string())
+        } else {
+            Err(format!("FTP: {}", reply.trim()))
+        }
+    }
+
+    fn command(&mut self, command: &str, ok: &[u32]) -> Result<String, String> {
+        self.stream
+            .write_all(format!("{}\r\n", command).as_bytes())
+            .map_err(|e| e.to_string())?;
+        self.expect_reply(ok)
+    }
+
+    /// Opens a passive data connection. The server's advertised address is
+    /// ignored in favour of the control connection's, which survives NAT.
+    fn passive(&mut self) -> Result<TcpStream, String> {
+        let reply = self.command("PASV", &[227])?;
+        let numbers: Vec<u16> = reply
+            .split(['(', ')'])
+            .nth(1)
+            .ok_or("bad PASV reply")?
+            .split(',')
+            .filter_map(|n| n.trim().parse().ok())
+            .collect();
+        if numbers.len() != 6 {
+            return Err(format!("bad PASV reply: {}", reply));
+        }
+        let ip = self.stream.peer_addr().map_err(|e| e.to_string())?.ip();
+        let port = numbers[4] * 256 + numbers[5];
+        TcpStream::connect_timeout(&SocketAddr::new(ip, port), Duration::from_secs(30)).map_err(|e| e.to_string())
+    }
+
+    fn size(&mut self, name: &str) -> Option<u64> {
+        let reply = self.command(&format!("SIZE {}", name), &[213]).ok()?;
+        reply.get(4..)?.trim().parse().ok()
+    }
+}
+
+impl Destination for FtpDestination {
+    fn name(&self) -> String {
+        format!("ftp://{}/{}", self.config.host, self.config.remote_dir)
+    }
+
+    fn upload(&self, path: &Path) -> Result<(), String> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or("backup has no file name")?;
+        let partial = format!("{}.partial", file_name);
+        destination::with_resume(&self.name(), || {
+            let mut session = self.connect()?;
+            let offset = session.size(&partial).unwrap_or(0);
+            let mut file = File::open(path).map_err(|e| e.to_string())?;
+            file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+            let mut data = session.passive()?;
+            if offset > 0 {
+                debug!("FTP: resuming {} at {} bytes", file_name, offset);
+                session.command(&format!("REST {}", offset), &[350])?;
+            }
+            session.command(&format!("STOR {}", partial), &[125, 150])?;
+            destination::copy_limited(&mut file, &mut data, self.config.bandwidth_limit_kbps)?;
+            drop(data);
+            session.expect_reply(&[226, 250])?;
+            session.command(&format!("RNFR {}", partial), &[350])?;
+            session.command(&format!("RNTO {}", file_name), &[250])?;
+            let _ = session.command("QUIT", &[221]);
+            Ok(())
+        })
+    }
+
+    fn list(&self) -> Result<Vec<RemoteBackup>, String> {
+        let mut session = self.connect()?;
+        let mut data = session.passive()?;
+        session.command("NLST", &[125, 150])?;
+        let mut listing = String::new();
+        data.read_to_string(&mut listing).map_err(|e| e.to_string())?;
+        drop(data);
+        session.expect_reply(&[226, 250])?;
+
+        let mut backups = Vec::new();
+        for name in listing.lines().map(|l| l.trim().rsplit('/').next().unwrap_or_default().to_string()) {
+            if name.is_empty() || name.ends_with(".partial") {
+                continue;
+            }
+            let Ok(reply) = session.command(&format!("MDTM {}", name), &[213]) else {
+                continue;
+            };
+            let stamp = reply.get(4..18).unwrap_or_default();
+            if let Ok(modified) = NaiveDateTime::parse_from_str(stamp, "%Y%m%d%H%M%S") {
+                backups.push(RemoteBackup {
+                    name,
+                    modified: modified.and_utc(),
+                });
+            }
+        }
+        let _ = session.command("QUIT", &[221]);
+        Ok(backups)
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        let mut session = self.connect()?;
+        session.command(&format!("DELE {}", name), &[250])?;
+        let _ = session.command("QUIT", &[221]);
+        Ok(())
+    }
+}
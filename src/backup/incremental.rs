@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::archive::{self, Entry, FileHash, HashingReader};
+use super::throttle::Throttle;
+
+pub const SNAPSHOT_SUFFIX: &str = "-snapshot";
+
+/// Counts of how a snapshot was assembled.
+pub struct SnapshotStats {
+    pub linked: usize,
+    pub copied: usize,
+    pub copied_bytes: u64,
+    pub hashes: Vec<FileHash>,
+    /// Names of the files that were freshly copied rather than linked.
+    pub copied_names: Vec<String>,
+}
+
+/// The newest backup directory in `directory` (a snapshot or a plain copy),
+/// by timestamped name.
+pub fn latest_snapshot(directory: &Path) -> Option<PathBuf> {
+    let mut snapshots: Vec<PathBuf> = fs::read_dir(directory)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() && p.file_name().is_some_and(|n| n.to_string_lossy().starts_with("world-")))
+        .filter(|p| !p.to_string_lossy().ends_with(".partial"))
+        .collect();
+    snapshots.sort();
+    snapshots.pop()
+}
+
+/// How many snapshots were taken since the last full archive.
+pub fn snapshots_since_full(directory: &Path) -> usize {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return 0;
+    };
+    let mut names: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with("world-")))
+        .filter(|p| !p.to_string_lossy().ends_with(".partial"))
+        .collect();
+    names.sort();
+    names.iter().rev().take_while(|p| is_snapshot(p)).count()
+}
+
+fn is_snapshot(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|n| n.to_string_lossy().ends_with(SNAPSHOT_SUFFIX))
+}
+
+/// Builds a complete directory snapshot at `target`. Files unchanged since the
+/// `previous` snapshot (same size and modification time) are hardlinked to it,
+/// so only changed region files cost disk space and copy time. Linked files
+/// take their checksum from `previous_hashes` when it has one.
+pub fn write_snapshot(
+    target: &Path,
+    entries: &[Entry],
+    previous: Option<&Path>,
+    previous_hashes: &HashMap<String, FileHash>,
+    throttle: &Throttle,
+) -> io::Result<SnapshotStats> {
+    let mut stats = SnapshotStats {
+        linked: 0,
+        copied: 0,
+        copied_bytes: 0,
+        hashes: Vec::with_capacity(entries.len()),
+        copied_names: Vec::new(),
+    };
+    for entry in entries {
+        let destination = target.join(&entry.name);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let source_modified = fs::metadata(&entry.source)?.modified()?;
+        if let Some(previous) = previous {
+            let old = previous.join(&entry.name);
+            let unchanged = fs::metadata(&old)
+                .map(|m| m.len() == entry.size && m.modified().ok() == Some(source_modified))
+                .unwrap_or(false);
+            if unchanged && fs::hard_link(&old, &destination).is_ok() {
+                let hash = match previous_hashes.get(&entry.name) {
+                    Some(hash) => hash.clone(),
+                    None => archive::hash_reader(&entry.name, throttle.reader(File::open(&old)?))?,
+                };
+                stats.hashes.push(hash);
+                stats.linked += 1;
+                continue;
+            }
+        }
+
+        let mut source = HashingReader::new(throttle.reader(File::open(&entry.source)?));
+        io::copy(&mut source, &mut File::create(&destination)?)?;
+        stats.hashes.push(source.finish(&entry.name));
+        stats.copied_names.push(entry.name.clone());
+        // Keep the source mtime so the next snapshot can tell the file is unchanged
+        File::options()
+            .write(true)
+            .open(&destination)?
+            .set_modified(source_modified)?;
+        stats.copied += 1;
+        stats.copied_bytes += entry.size;
+    }
+    Ok(stats)
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::catalog::{self, Record};
+use crate::config::BackupConfig;
+use crate::metrics::format_bytes;
+
+/// Every cataloged backup: the world's, each set's, pre-update backups and
+/// crash states (the backup directory and its direct subfolders), oldest first.
+fn all(backup: &BackupConfig) -> Vec<(PathBuf, Record)> {
+    let root = PathBuf::from(&backup.directory);
+    let mut directories = vec![root.clone()];
+    if let Ok(entries) = fs::read_dir(&root) {
+        directories.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()));
+    }
+    let mut records: Vec<(PathBuf, Record)> = directories
+        .into_iter()
+        .flat_map(|dir| catalog::load(&dir).into_iter().map(move |r| (dir.clone(), r)))
+        .collect();
+    records.sort_by_key(|(_, r)| r.created);
+    records
+}
+
+/// The newest cataloged backup of any kind.
+pub fn last_backup(backup: &BackupConfig) -> Option<Record> {
+    all(backup).pop().map(|(_, r)| r)
+}
+
+fn verified(record: &Record) -> &'static str {
+    match record.verified {
+        Some(true) => "verified",
+        Some(false) => "VERIFY FAILED",
+        None => "not verified",
+    }
+}
+
+/// One line per backup, numbered for `backups show <id>`.
+pub fn list_lines(backup: &BackupConfig) -> Vec<String> {
+    all(backup)
+        .iter()
+        .enumerate()
+        .map(|(i, (_, r))| {
+            let copies = if r.destinations.is_empty() { "local".to_string() } else { format!("local, {}", r.destinations.join(", ")) };
+            format!(
+                "{:>3}  {}  {}  {:>10}  {:<10}  {}  [{}]",
+                i + 1,
+                r.created.format("%Y-%m-%d %H:%M"),
+                r.name,
+                format_bytes(r.size_bytes),
+                r.trigger.map_or("-", |t| t.as_str()),
+                verified(r),
+                copies
+            )
+        })
+        .collect()
+}
+
+/// Details of one backup, by its number in the list or its name.
+pub fn show_lines(backup: &BackupConfig, id: &str) -> Option<Vec<String>> {
+    let records = all(backup);
+    let (directory, record) = match id.parse::<usize>() {
+        Ok(n) => records.get(n.checked_sub(1)?)?,
+        Err(_) => records.iter().find(|(_, r)| r.name == id)?,
+    };
+    let mut lines = vec![
+        format!("Name:         {}", record.name),
+        format!("Location:     {}", directory.join(&record.name).display()),
+        format!("Created:      {}", record.created.format("%Y-%m-%d %H:%M:%S")),
+        format!("Trigger:      {}", record.trigger.map_or("unknown", |t| t.as_str())),
+        format!("Size:         {}", format_bytes(record.size_bytes)),
+        format!("Duration:     {:.1}s", record.duration_secs),
+        format!("Files:        {}", record.files),
+        format!("Verification: {}", verified(record)),
+        format!("SHA-256:      {}", record.sha256.as_deref().unwrap_or("-")),
+        format!(
+            "Destinations: {}",
+            if record.destinations.is_empty() { "local only".to_string() } else { record.destinations.join(", ") }
+        ),
+    ];
+    if let Some(manifest) = catalog::load_manifest(directory, &record.name) {
+        if !manifest.excludes.is_empty() {
+            lines.push(format!("Excludes:     {}", manifest.excludes.join(", ")));
+        }
+    }
+    let diff = catalog::diff_path(directory, &record.name);
+    if diff.exists() {
+        lines.push(format!("Config diff:  {}", diff.display()));
+    }
+    if !directory.join(&record.name).exists() {
+        lines.push("(the backup itself is missing from disk)".to_string());
+    }
+    Some(lines)
+}
@@ -0,0 +1,485 @@
+mod archive;
+mod catalog;
+mod config_diff;
+mod crash_state;
+mod destination;
+mod exclude;
+mod ftp;
+mod incremental;
+mod listing;
+mod rclone;
+mod restore;
+mod s3;
+mod sftp;
+mod stats;
+mod throttle;
+mod update;
+mod verify;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use tracing::{debug, error, info, info_span, warn};
+
+use crate::config::{ArchiveFormat, BackupConfig, BackupMode, BackupSetConfig, Config};
+use crate::messages::Messages;
+use crate::metrics::{self, format_bytes};
+use crate::server::Server;
+use crate::server_props;
+
+pub use catalog::{Record, Trigger};
+pub use crash_state::create_crash_state;
+pub use listing::{last_backup, list_lines, show_lines};
+pub use restore::{available, restore};
+pub use stats::{job_lines, job_stats, record_outcome, JobStats};
+pub use update::{create_pre_update, jar_changed, mark_good, rollback};
+
+/// Config changes listed in a notification; the rest are in the stored diff.
+const MAX_LISTED_CHANGES: usize = 10;
+
+pub struct BackupResult {
+    /// An archive file, or a directory for incremental snapshots.
+    pub path: PathBuf,
+    /// Bytes written: archive size, or newly copied data for a snapshot.
+    pub size_bytes: u64,
+    pub duration: Duration,
+    /// Destination name and outcome of each off-site upload.
+    pub uploads: Vec<(String, Result<(), String>)>,
+    /// Outcome of reading the backup back against its manifest, if enabled.
+    pub verification: Option<Result<(), String>>,
+    /// Size of the previous backup of the same kind, for spotting sudden growth.
+    pub previous_size_bytes: Option<u64>,
+    /// Notable config file changes since the previous backup of the same set.
+    pub config_changes: Vec<String>,
+}
+
+impl BackupResult {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Size, duration, destinations and free space, for the completion notification.
+    pub fn fields(&self, messages: &Messages) -> Vec<(String, String)> {
+        let mut size = format_bytes(self.size_bytes);
+        if let Some(previous) = self.previous_size_bytes.filter(|&p| p > 0) {
+            let change = (self.size_bytes as f64 / previous as f64 - 1.0) * 100.0;
+            size = messages.get("backup_size_change", &[("size", size), ("change", format!("{:+.0}%", change))]);
+        }
+        let secs = self.duration.as_secs();
+        let duration = if secs < 120 {
+            format!("{:.1}s", self.duration.as_secs_f32())
+        } else {
+            format!("{}m {:02}s", secs / 60, secs % 60)
+        };
+        let mut destinations = vec![messages.get("backup_local", &[])];
+        for (name, outcome) in &self.uploads {
+            destinations.push(match outcome {
+                Ok(()) => format!("{} ✓", name),
+                Err(e) => format!("{} ✗ {}", name, e),
+            });
+        }
+
+        let mut fields = vec![
+            (messages.get("backup_size", &[]), size),
+            (messages.get("backup_duration", &[]), duration),
+            (messages.get("backup_destinations", &[]), destinations.join("\n")),
+        ];
+        if let Some(verification) = &self.verification {
+            let key = if verification.is_ok() { "backup_verified_yes" } else { "backup_verified_no" };
+            fields.push((messages.get("backup_verified", &[]), messages.get(key, &[])));
+        }
+        if !self.config_changes.is_empty() {
+            let mut changes: Vec<String> = self.config_changes.iter().take(MAX_LISTED_CHANGES).cloned().collect();
+            if self.config_changes.len() > MAX_LISTED_CHANGES {
+                let more = (self.config_changes.len() - MAX_LISTED_CHANGES).to_string();
+                changes.push(messages.get("backup_config_more", &[("count", more)]));
+            }
+            fields.push((messages.get("backup_config_changes", &[]), changes.join("\n")));
+        }
+        if let Some(free) = self.path.parent().and_then(metrics::free_space) {
+            fields.push((messages.get("backup_free_space", &[]), format_bytes(free)));
+        }
+        fields
+    }
+}
+
+/// Backs up the world folders into the backup directory: a timestamped archive
+/// (or plain copy), or in incremental mode a hardlinked snapshot with periodic
+/// full baselines. Finished archives are then copied to any configured
+/// destinations.
+pub fn create(config: &Config, backup: &BackupConfig, trigger: Trigger) -> io::Result<BackupResult> {
+    let mut result = archive_world(config, backup, false, trigger)?;
+    prune_world(backup);
+    upload(backup, "world", &mut result);
+    Ok(result)
+}
+
+/// Applies the local `retention` to the world backups in the backup directory.
+fn prune_world(backup: &BackupConfig) {
+    if let Some(retention) = &backup.retention {
+        let local = destination::Local::new(Path::new(&backup.directory));
+        if let Err(e) = destination::apply_retention(&local, retention, "world") {
+            warn!("Backup: retention cleanup of {} failed: {}", backup.directory, e);
+        }
+    }
+}
+
+/// What one backup covers and where it is kept.
+struct Scope {
+    /// Prefix of the backup names: "world", or the name of a backup set.
+    name: String,
+    directory: PathBuf,
+    sources: Vec<PathBuf>,
+    /// Files from outside the server folder, already named.
+    extra: Vec<archive::Entry>,
+    incremental: bool,
+    /// Diff the config files in this scope against its previous backup.
+    config_diff: bool,
+    trigger: Trigger,
+}
+
+/// A `hot` backup runs alongside players, so it honours the hot read limit and
+/// can run on a low-priority thread of its own.
+fn archive_world(config: &Config, backup: &BackupConfig, hot: bool, trigger: Trigger) -> io::Result<BackupResult> {
+    let span = info_span!("backup", trigger = trigger.as_str(), hot);
+    let _entered = span.enter();
+    let server_dir = config.server_dir();
+    let worlds = server_props::world_dirs(&server_dir);
+    if worlds.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no world folder found in {}", server_dir.display()),
+        ));
+    }
+    let scope = Scope {
+        name: "world".to_string(),
+        directory: PathBuf::from(&backup.directory),
+        sources: worlds,
+        extra: Vec::new(),
+        incremental: backup.mode == BackupMode::Incremental,
+        config_diff: false,
+        trigger,
+    };
+    if !hot {
+        return archive_scope(config, backup, &scope, &throttle::Throttle::unlimited());
+    }
+    let run = || archive_scope(config, backup, &scope, &throttle::Throttle::new(backup.hot_read_limit_kbps));
+    if !backup.hot_low_priority {
+        return run();
+    }
+    thread::scope(|s| {
+        s.spawn(|| {
+            let _entered = span.enter();
+            throttle::lower_priority();
+            run()
+        })
+        .join()
+        .unwrap_or_else(|_| Err(io::Error::other("backup thread panicked")))
+    })
+}
+
+/// Backs up one named set of extra files and folders, e.g. configs or plugins,
+/// into its own subfolder of the backup directory, then uploads and prunes.
+pub fn create_set(
+    config: &Config,
+    backup: &BackupConfig,
+    set: &BackupSetConfig,
+    trigger: Trigger,
+) -> io::Result<BackupResult> {
+    let _span = info_span!("backup", set = %set.name, trigger = trigger.as_str()).entered();
+    let server_dir = config.server_dir();
+    let sources: Vec<PathBuf> = set
+        .paths
+        .iter()
+        .map(|p| server_dir.join(p))
+        .filter(|p| p.exists())
+        .collect();
+    if sources.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("none of the paths of backup set {} exist", set.name),
+        ));
+    }
+    let scope = Scope {
+        name: set.name.clone(),
+        directory: Path::new(&backup.directory).join(&set.name),
+        sources,
+        extra: Vec::new(),
+        incremental: false,
+        config_diff: true,
+        trigger,
+    };
+    let mut result = archive_scope(config, backup, &scope, &throttle::Throttle::unlimited())?;
+    if let Some(retention) = &set.retention {
+        let local = destination::Local::new(&scope.directory);
+        if let Err(e) = destination::apply_retention(&local, retention, &scope.name) {
+            warn!("Backup: retention cleanup of {} failed: {}", scope.directory.display(), e);
+        }
+    }
+    upload(backup, &scope.name, &mut result);
+    Ok(result)
+}
+
+fn archive_scope(
+    config: &Config,
+    backup: &BackupConfig,
+    scope: &Scope,
+    throttle: &throttle::Throttle,
+) -> io::Result<BackupResult> {
+    let started = Instant::now();
+    let server_dir = config.server_dir();
+    let directory = &scope.directory;
+    let excludes = exclude::Excludes::new(&backup.exclude);
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for source in &scope.sources {
+        skipped += archive::collect_path(&server_dir, source, &excludes, &mut entries)?;
+    }
+    entries.extend(scope.extra.iter().cloned());
+    if !backup.exclude.is_empty() {
+        debug!(
+            "Backup: excluding {} ({} skipped)",
+            backup.exclude.join(", "),
+            format_bytes(skipped)
+        );
+    }
+
+    fs::create_dir_all(directory)?;
+    let stamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
+    let sources = scope
+        .sources
+        .iter()
+        .map(|w| w.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let incremental =
+        scope.incremental && incremental::snapshots_since_full(directory) < backup.full_every.saturating_sub(1);
+    // Snapshots hardlink unchanged files, so only full copies are measured
+    if !incremental {
+        let needed: u64 = entries.iter().map(|e| e.size).sum();
+        if let Some(free) = metrics::free_space(directory).filter(|free| needed > *free) {
+            return Err(io::Error::new(
+                io::ErrorKind::StorageFull,
+                format!(
+                    "not enough space in {}: the backup needs up to {}, {} is free",
+                    directory.display(),
+                    format_bytes(needed),
+                    format_bytes(free)
+                ),
+            ));
+        }
+    }
+    let (target, size_bytes, hashes, only_verify) = if incremental {
+        let target = directory.join(format!("{}-{}{}", scope.name, stamp, incremental::SNAPSHOT_SUFFIX));
+        let partial = directory.join(format!("{}-{}.partial", scope.name, stamp));
+        let previous = incremental::latest_snapshot(directory);
+        let previous_hashes: HashMap<String, archive::FileHash> = previous
+            .as_ref()
+            .and_then(|p| catalog::load_manifest(directory, &p.file_name()?.to_string_lossy()))
+            .map(|m| m.files.into_iter().map(|h| (h.name.clone(), h)).collect())
+            .unwrap_or_default();
+        info!(
+            "Backup: snapshotting {} file(s) from {} into {}",
+            entries.len(),
+            sources,
+            target.display()
+        );
+        let stats = match incremental::write_snapshot(&partial, &entries, previous.as_deref(), &previous_hashes, throttle) {
+            Ok(stats) => stats,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&partial);
+                return Err(e);
+            }
+        };
+        fs::rename(&partial, &target)?;
+        debug!(
+            "Backup: {} file(s) unchanged and linked, {} copied ({})",
+            stats.linked,
+            stats.copied,
+            format_bytes(stats.copied_bytes)
+        );
+        (target, stats.copied_bytes, stats.hashes, Some(stats.copied_names))
+    } else {
+        let name = format!("{}-{}{}", scope.name, stamp, backup.format.extension());
+        let target = directory.join(&name);
+        // Write under a temporary name so a half-written archive is never mistaken for a backup
+        let partial = directory.join(format!("{}.partial", name));
+        info!(
+            "Backup: archiving {} file(s) from {} into {}",
+            entries.len(),
+            sources,
+            target.display()
+        );
+        let written = match backup.format {
+            ArchiveFormat::Zip => archive::write_zip(&partial, &entries, backup.compression_level, throttle),
+            ArchiveFormat::TarZst => archive::write_tar_zst(&partial, &entries, backup.compression_level, throttle),
+            ArchiveFormat::None => {
+                incremental::write_snapshot(&partial, &entries, None, &HashMap::new(), throttle).map(|stats| stats.hashes)
+            }
+        };
+        let hashes = match written {
+            Ok(hashes) => hashes,
+            Err(e) => {
+                let _ = if partial.is_dir() {
+                    fs::remove_dir_all(&partial)
+                } else {
+                    fs::remove_file(&partial)
+                };
+                return Err(e);
+            }
+        };
+        fs::rename(&partial, &target)?;
+        let size = if target.is_dir() {
+            metrics::dir_size(&target)
+        } else {
+            fs::metadata(&target)?.len()
+        };
+        (target, size, hashes, None)
+    };
+
+    let verification = backup
+        .verify
+        .then(|| verify::verify(&target, &hashes, only_verify.as_deref()));
+    if let Some(Err(e)) = &verification {
+        error!("Backup: verification FAILED: {}", e);
+    }
+
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let snapshot = name.ends_with(incremental::SNAPSHOT_SUFFIX);
+    let previous_size_bytes = catalog::load(directory)
+        .iter()
+        .rev()
+        .find(|r| r.name.ends_with(incremental::SNAPSHOT_SUFFIX) == snapshot)
+        .map(|r| r.size_bytes);
+    let mut config_changes = Vec::new();
+    if scope.config_diff {
+        match config_diff::record(directory, &scope.name, &entries) {
+            Ok(diff) => {
+                if !diff.detail.is_empty() {
+                    if let Err(e) = catalog::save_diff(directory, &name, &diff.detail) {
+                        warn!("Backup: could not write config diff: {}", e);
+                    }
+                }
+                for change in &diff.summary {
+                    info!("Backup: config change: {}", change);
+                }
+                config_changes = diff.summary;
+            }
+            Err(e) => warn!("Backup: could not diff config files: {}", e),
+        }
+    }
+    let result = BackupResult {
+        size_bytes,
+        path: target,
+        duration: started.elapsed(),
+        uploads: Vec::new(),
+        verification,
+        previous_size_bytes,
+        config_changes,
+    };
+
+    let files = hashes.len();
+    if let Err(e) = catalog::save_manifest(directory, &name, &catalog::Manifest {
+        files: hashes,
+        excludes: backup.exclude.clone(),
+    }) {
+        warn!("Backup: could not write manifest: {}", e);
+    }
+    let record = catalog::Record {
+        name,
+        created: Local::now(),
+        size_bytes: result.size_bytes,
+        duration_secs: result.duration.as_secs_f64(),
+        files,
+        verified: result.verification.as_ref().map(|v| v.is_ok()),
+        trigger: Some(scope.trigger),
+        sha256: fs::File::open(&result.path)
+            .ok()
+            .filter(|_| result.path.is_file())
+            .and_then(|file| archive::hash_reader("", file).ok())
+            .map(|hash| hash.sha256),
+        destinations: Vec::new(),
+    };
+    if let Err(e) = catalog::save_record(directory, record) {
+        warn!("Backup: could not update catalog: {}", e);
+    }
+    info!(
+        "Backup: finished {} ({}) in {:.1}s",
+        result.file_name(),
+        format_bytes(result.size_bytes),
+        result.duration.as_secs_f32()
+    );
+    Ok(result)
+}
+
+/// Backs up a running server: pauses autosave, flushes all chunks, archives,
+/// then re-enables saving whatever the outcome.
+pub fn create_hot(
+    config: &Config,
+    backup: &BackupConfig,
+    server: &mut Server,
+    trigger: Trigger,
+) -> io::Result<BackupResult> {
+    server.send_command("save-off");
+    if !server.save_all(Duration::from_secs(120)) {
+        warn!("Backup: save-all was not confirmed; archiving anyway.");
+    }
+    let result = archive_world(config, backup, true, trigger);
+    server.send_command("save-on");
+    // Uploading can take a while; the server is already saving normally again
+    let mut result = result?;
+    prune_world(backup);
+    upload(backup, "world", &mut result);
+    Ok(result)
+}
+
+fn upload(backup: &BackupConfig, prefix: &str, result: &mut BackupResult) {
+    let destinations = destination::from_config(backup);
+    if destinations.is_empty() {
+        return;
+    }
+    if result.path.is_dir() {
+        info!("Backup: directory backups stay local; only archives are uploaded.");
+        return;
+    }
+    for (destination, retention) in destinations {
+        let name = destination.name();
+        info!("Backup: uploading {} to {}", result.file_name(), name);
+        let outcome = destination.upload(&result.path);
+        match &outcome {
+            Ok(()) => {
+                info!("Backup: uploaded to {}", name);
+                if let Some(retention) = retention {
+                    if let Err(e) = destination::apply_retention(destination.as_ref(), &retention, prefix) {
+                        warn!("Backup: retention cleanup on {} failed: {}", name, e);
+                    }
+                }
+            }
+            Err(e) => warn!("Backup: upload to {} failed: {}", name, e),
+        }
+        result.uploads.push((name, outcome));
+    }
+    let uploaded = result
+        .uploads
+        .iter()
+        .filter(|(_, outcome)| outcome.is_ok())
+        .map(|(name, _)| name.clone())
+        .collect();
+    if let Some(directory) = result.path.parent() {
+        if let Err(e) = catalog::set_destinations(directory, &result.file_name(), uploaded) {
+            warn!("Backup: could not update catalog: {}", e);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+use chrono::DateTime;
+
+use super::destination::{Destination, RemoteBackup};
+use crate::config::RcloneConfig;
+
+/// Any cloud storage rclone knows about (Google Drive, OneDrive, Dropbox, ...),
+/// through a remote the user has already set up with `rclone config`.
+pub struct RcloneDestination {
+    config: RcloneConfig,
+}
+
+impl RcloneDestination {
+    pub fn new(config: RcloneConfig) -> Self {
+        RcloneDestination { config }
+    }
+
+    fn remote_path(&self, name: &str) -> String {
+        format!("{}/{}", self.config.remote.trim_end_matches('/'), name)
+    }
+
+    fn run(&self, args: &[&str]) -> Result<Output, String> {
+        let output = Command::new(&self.config.binary)
+            .args(args)
+            .args(&self.config.extra_args)
+            .output()
+            .map_err(|e| format!("could not run {}: {}", self.config.binary, e))?;
+        if output.status.success() {
+            return Ok(output);
+        }
+        // rclone logs the useful part last; keep the message short enough for Discord
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().filter(|l| !l.trim().is_empty()).collect();
+        let start = tail.len().saturating_sub(3);
+        Err(format!("rclone {} ({}): {}", args[0], output.status, tail[start..].join(" / ")))
+    }
+}
+
+impl Destination for RcloneDestination {
+    fn name(&self) -> String {
+        self.config.remote.clone()
+    }
+
+    fn upload(&self, path: &Path) -> Result<(), String> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or("backup has no file name")?;
+        let source = path.to_string_lossy();
+        self.run(&["copyto", &source, &self.remote_path(&file_name)])?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<RemoteBackup>, String> {
+        let output = self.run(&["lsjson", "--files-only", &self.config.remote])?;
+        let entries: Vec<serde_json::Value> =
+            serde_json::from_slice(&output.stdout).map_err(|e| format!("unexpected rclone output: {}", e))?;
+        Ok(entries
+            .iter()
+            .filter_map(|entry| {
+                let modified = DateTime::parse_from_rfc3339(entry["ModTime"].as_str()?).ok()?;
+                Some(RemoteBackup {
+                    name: entry["Name"].as_str()?.to_string(),
+                    modified: modified.to_utc(),
+                })
+            })
+            .collect())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        self.run(&["deletefile", &self.remote_path(name)])?;
+        Ok(())
+    }
+}
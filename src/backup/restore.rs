@@ -0,0 +1,124 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use tracing::{error, info, info_span, warn};
+
+use super::catalog;
+use crate::config::{BackupConfig, Config};
+use crate::server_props;
+
+pub struct RestoreResult {
+    pub name: String,
+    /// Where the replaced world folders were moved.
+    pub moved_aside: Vec<PathBuf>,
+}
+
+/// Names of the world backups in `directory`, oldest first.
+pub fn available(directory: &Path) -> Vec<String> {
+    available_with_prefix(directory, "world")
+}
+
+pub fn available_with_prefix(directory: &Path, prefix: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}-", prefix);
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| n.starts_with(&prefix) && !n.ends_with(".partial"))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Replaces the world folders with the contents of backup `name`. The current
+/// folders are renamed aside rather than deleted, and put back if extraction fails.
+/// The server must not be running.
+pub fn restore(config: &Config, backup: &BackupConfig, name: &str) -> io::Result<RestoreResult> {
+    if !available(Path::new(&backup.directory)).iter().any(|n| n == name) {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("no backup named {}", name)));
+    }
+    restore_from(config, Path::new(&backup.directory), name)
+}
+
+/// Restores backup `name` from `directory`; any files besides the worlds that
+/// it contains (configs, jars) overwrite the current ones.
+pub fn restore_from(config: &Config, directory: &Path, name: &str) -> io::Result<RestoreResult> {
+    let _span = info_span!("restore", backup = name).entered();
+    let source = directory.join(name);
+    if !source.exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("no backup named {}", name)));
+    }
+    if let Some(record) = catalog::load(directory).iter().find(|r| r.name == name) {
+        if record.verified == Some(false) {
+            warn!("Restore: warning, {} failed verification when it was taken.", name);
+        }
+    }
+
+    let server_dir = config.server_dir();
+    let stamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
+    let mut moved_aside = Vec::new();
+    for world in server_props::world_dirs(&server_dir) {
+        let aside = PathBuf::from(format!("{}.before-restore-{}", world.display(), stamp));
+        info!("Restore: moving {} to {}", world.display(), aside.display());
+        if let Err(e) = fs::rename(&world, &aside) {
+            error!("Restore: could not move {} aside ({}); putting the others back.", world.display(), e);
+            for (world, aside) in &moved_aside {
+                let _ = fs::rename(aside, world);
+            }
+            return Err(e);
+        }
+        moved_aside.push((world, aside));
+    }
+
+    info!("Restore: extracting {} into {}", name, server_dir.display());
+    if let Err(e) = extract(&source, &server_dir) {
+        error!("Restore: extraction failed ({}); putting the previous world back.", e);
+        for world in server_props::world_dirs(&server_dir) {
+            let _ = fs::remove_dir_all(&world);
+        }
+        for (world, aside) in &moved_aside {
+            let _ = fs::rename(aside, world);
+        }
+        return Err(e);
+    }
+
+    info!("Restore: {} restored.", name);
+    Ok(RestoreResult {
+        name: name.to_string(),
+        moved_aside: moved_aside.into_iter().map(|(_, aside)| aside).collect(),
+    })
+}
+
+fn extract(source: &Path, server_dir: &Path) -> io::Result<()> {
+    let name = source.to_string_lossy();
+    if source.is_dir() {
+        // Copy, never link: the server would otherwise write into the backup's files
+        copy_dir(source, server_dir)
+    } else if name.ends_with(".zip") {
+        zip::ZipArchive::new(File::open(source)?)?
+            .extract(server_dir)
+            .map_err(io::Error::from)
+    } else if name.ends_with(".tar.zst") {
+        tar::Archive::new(zstd::Decoder::new(File::open(source)?)?).unpack(server_dir)
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown backup format: {}", name)))
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for item in fs::read_dir(from)? {
+        let item = item?;
+        let target = to.join(item.file_name());
+        if item.file_type()?.is_dir() {
+            copy_dir(&item.path(), &target)?;
+        } else {
+            fs::copy(item.path(), &target)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use super::archive::hex;
+use super::destination::{Destination, RemoteBackup};
+use crate::config::S3Config;
+
+/// S3-compatible object storage (AWS, Backblaze B2, MinIO, ...), using
+/// path-style URLs and SigV4 request signing.
+pub struct S3Destination {
+    client: Client,
+    config: S3Config,
+}
+
+impl S3Destination {
+    pub fn new(config: S3Config) -> Self {
+        S3Destination {
+            client: Client::builder()
+                .timeout(Duration::from_secs(600))
+                .build()
+                .expect("Failed to build HTTP client"),
+            config,
+        }
+    }
+
+    fn key_for(&self, file_name: &str) -> String {
+        format!("{}{}", self.config.prefix, file_name)
+    }
+
+    fn url(&self, key: &str, query: &[(&str, &str)]) -> Result<Url, String> {
+        let mut url = Url::parse(&self.config.endpoint).map_err(|e| e.to_string())?;
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| "invalid endpoint".to_string())?;
+            segments.pop_if_empty().push(&self.config.bucket);
+            if !key.is_empty() {
+                segments.extend(key.split('/'));
+            }
+        }
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        Ok(url)
+    }
+
+    /// Sends a signed request and returns the response body on success.
+    fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<(reqwest::header::HeaderMap, String), String> {
+        let url = self.url(key, query)?;
+        let now = Utc::now();
+        let payload_hash = hex(&Sha256::digest(&body));
+        let authorization = self.authorization(&method, &url, &payload_hash, now);
+
+        let response = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+            .header("x-amz-content-sha256", &payload_hash)
+            .header("Authorization", authorization)
+            .body(body)
+            .send()
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let text = response.text().unwrap_or_default();
+        if !status.is_success() {
+            return Err(format!("HTTP {} {}", status, text.trim()));
+        }
+        Ok((headers, text))
+    }
+
+    fn authorization(&self, method: &Method, url: &Url, payload_hash: &str, now: DateTime<Utc>) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+            .collect();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method.as_str(),
+            url.path(),
+            canonical_query,
+            host,
+            payload_hash,
+            amz_date,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut key = hmac(format!("AWS4{}", self.config.secret_key).as_bytes(), date.as_bytes());
+        for part in [self.config.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.config.access_key, scope, signature
+        )
+    }
+
+    /// Bytes per multipart part, and the largest archive sent in one PUT. S3
+    /// refuses parts under 5 MB, other than the last.
+    fn part_size(&self) -> u64 {
+        self.config.part_size_mb.max(5) * 1024 * 1024
+    }
+
+    fn upload_multipart(&self, key: &str, file: &mut File) -> Result<(), String> {
+        let (_, body) = self.send(Method::POST, key, &[("uploads", "")], Vec::new())?;
+        let upload_id = xml_values(&body, "UploadId")
+            .into_iter()
+            .next()
+            .ok_or("no UploadId in response")?;
+
+        let part_size = self.part_size() as usize;
+        let mut parts = Vec::new();
+        let result = (|| {
+            for number in 1.. {
+                let mut chunk = Vec::with_capacity(part_size);
+                file.by_ref()
+                    .take(part_size as u64)
+                    .read_to_end(&mut chunk)
+                    .map_err(|e| e.to_string())?;
+                if chunk.is_empty() {
+                    break;
+                }
+                let number_str = number.to_string();
+                let query = [("partNumber", number_str.as_str()), ("uploadId", upload_id.as_str())];
+                let (headers, _) = self.send(Method::PUT, key, &query, chunk)?;
+                let etag = headers
+                    .get("etag")
+                    .and_then(|v| v.to_str().ok())
+                    .ok_or("no ETag for uploaded part")?
+                    .to_string();
+                debug!("S3: uploaded part {} of {}", number, key);
+                parts.push((number, etag));
+            }
+            let complete = format!(
+                "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+                parts
+                    .iter()
+                    .map(|(n, etag)| format!("<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>", n, etag))
+                    .collect::<String>()
+            );
+            self.send(Method::POST, key, &[("uploadId", upload_id.as_str())], complete.into_bytes())?;
+            Ok(())
+        })();
+
+        if result.is_err() {
+            // Don't leave billable orphaned parts behind
+            let _ = self.send(Method::DELETE, key, &[("uploadId", upload_id.as_str())], Vec::new());
+        }
+        result
+    }
+}
+
+impl Destination for S3Destination {
+    fn name(&self) -> String {
+        format!("s3://{}/{}", self.config.bucket, self.config.prefix)
+    }
+
+    fn upload(&self, path: &Path) -> Result<(), String> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or("backup has no file name")?;
+        let key = self.key_for(&file_name);
+        let mut file = File::open(path).map_err(|e| e.to_string())?;
+        let size = file.metadata().map_err(|e| e.to_string())?.len();
+
+        if size <= self.part_size() {
+            let mut body = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut body).map_err(|e| e.to_string())?;
+            self.send(Method::PUT, &key, &[], body)?;
+            Ok(())
+        } else {
+            self.upload_multipart(&key, &mut file)
+        }
+    }
+
+    fn list(&self) -> Result<Vec<RemoteBackup>, String> {
+        let mut backups = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", self.config.prefix.as_str())];
+            if let Some(t) = &token {
+                query.push(("continuation-token", t.as_str()));
+            }
+            let (_, body) = self.send(Method::GET, "", &query, Vec::new())?;
+            let keys = xml_values(&body, "Key");
+            let dates = xml_values(&body, "LastModified");
+            for (key, date) in keys.into_iter().zip(dates) {
+                let Ok(modified) = DateTime::parse_from_rfc3339(&date) else {
+                    continue;
+                };
+                backups.push(RemoteBackup {
+                    name: key.strip_prefix(&self.config.prefix).unwrap_or(&key).to_string(),
+                    modified: modified.with_timezone(&Utc),
+                });
+            }
+            token = xml_values(&body, "NextContinuationToken").into_iter().next();
+            if token.is_none() {
+                return Ok(backups);
+            }
+        }
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        self.send(Method::DELETE, &self.key_for(name), &[], Vec::new())?;
+        Ok(())
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// SigV4 encoding: everything but unreserved characters is percent-encoded
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Text of every `<tag>...</tag>` in an S3 XML response.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else { break };
+        values.push(
+            rest[..end]
+                .replace("&quot;", "\"")
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
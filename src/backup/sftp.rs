@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::DateTime;
+use ssh2::{OpenFlags, OpenType, Session, Sftp};
+use tracing::debug;
+
+use super::destination::{self, Destination, RemoteBackup};
+use crate::config::SftpConfig;
+
+pub struct SftpDestination {
+    config: SftpConfig,
+}
+
+impl SftpDestination {
+    pub fn new(config: SftpConfig) -> Self {
+        SftpDestination { config }
+    }
+
+    fn connect(&self) -> Result<Sftp, String> {
+        let tcp = TcpStream::connect((self.config.host.as_str(), self.config.port)).map_err(|e| e.to_string())?;
+        tcp.set_read_timeout(Some(Duration::from_secs(60))).map_err(|e| e.to_string())?;
+        let mut session = Session::new().map_err(|e| e.to_string())?;
+        session.set_tcp_stream(tcp);
+        session.handshake().map_err(|e| e.to_string())?;
+        match (&self.config.private_key, &self.config.password) {
+            (Some(key), passphrase) => session
+                .userauth_pubkey_file(&self.config.username, None, Path::new(key), passphrase.as_deref())
+                .map_err(|e| e.to_string())?,
+            (None, Some(password)) => session
+                .userauth_password(&self.config.username, password)
+                .map_err(|e| e.to_string())?,
+            (None, None) => return Err("sftp needs a password or private_key".to_string()),
+        }
+        let sftp = session.sftp().map_err(|e| e.to_string())?;
+        // The folder and its parents, e.g. a server's inside the shared one
+        let dir = Path::new(&self.config.remote_dir);
+        let missing: Vec<&Path> = dir
+            .ancestors()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .take_while(|dir| sftp.stat(dir).is_err())
+            .collect();
+        for dir in missing.into_iter().rev() {
+            sftp.mkdir(dir, 0o755).map_err(|e| e.to_string())?;
+        }
+        Ok(sftp)
+    }
+
+    fn remote(&self, name: &str) -> PathBuf {
+        Path::new(&self.config.remote_dir).join(name)
+    }
+}
+
+impl Destination for SftpDestination {
+    fn name(&self) -> String {
+        format!("sftp://{}/{}", self.config.host, self.config.remote_dir)
+    }
+
+    fn upload(&self, path: &Path) -> Result<(), String> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or("backup has no file name")?;
+        let partial = self.remote(&format!("{}.partial", file_name));
+        destination::with_resume(&self.name(), || {
+            let sftp = self.connect()?;
+            let offset = sftp.stat(&partial).ok().and_then(|s| s.size).unwrap_or(0);
+            let mut file = File::open(path).map_err(|e| e.to_string())?;
+            file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+            let mut remote = sftp
+                .open_mode(&partial, OpenFlags::WRITE | OpenFlags::CREATE, 0o644, OpenType::File)
+                .map_err(|e| e.to_string())?;
+            if offset > 0 {
+                debug!("SFTP: resuming {} at {} bytes", file_name, offset);
+                remote.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+            }
+            destination::copy_limited(&mut file, &mut remote, self.config.bandwidth_limit_kbps)?;
+            drop(remote);
+            sftp.rename(&partial, &self.remote(&file_name), None)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn list(&self) -> Result<Vec<RemoteBackup>, String> {
+        let sftp = self.connect()?;
+        let entries = sftp
+            .readdir(Path::new(&self.config.remote_dir))
+            .map_err(|e| e.to_string())?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|(path, stat)| {
+                let name = path.file_name()?.to_string_lossy().to_string();
+                let modified = DateTime::from_timestamp(stat.mtime? as i64, 0)?;
+                (!name.ends_with(".partial")).then_some(RemoteBackup { name, modified })
+            })
+            .collect())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        self.connect()?.unlink(&self.remote(name)).map_err(|e| e.to_string())
+    }
+}
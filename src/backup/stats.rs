@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use chrono::{DateTime, Local};
+
+use super::BackupResult;
+use crate::history;
+use crate::metrics::format_bytes;
+
+/// Every finished backup goes to `stats/backups.csv` as
+/// `<time>,<job>,<ok|failed>,<seconds>,<bytes>`, the job being "world" or a
+/// set's name.
+const SERIES: &str = "backups";
+
+pub fn record_outcome(job: &str, outcome: &io::Result<BackupResult>) {
+    let value = match outcome {
+        Ok(result) => format!("{},ok,{:.3},{}", job, result.duration.as_secs_f64(), result.size_bytes),
+        Err(_) => format!("{},failed,,", job),
+    };
+    history::append(SERIES, Local::now(), &value);
+}
+
+/// All-time figures for one job.
+#[derive(Clone, Debug, Default)]
+pub struct JobStats {
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Whether the latest run succeeded.
+    pub last_ok: bool,
+    pub last_success: Option<DateTime<Local>>,
+    /// Of the latest successful run.
+    pub duration_secs: Option<f64>,
+    pub size_bytes: Option<u64>,
+}
+
+impl JobStats {
+    /// Bytes written per second in the latest successful run.
+    pub fn throughput(&self) -> Option<f64> {
+        let (duration, size) = (self.duration_secs?, self.size_bytes?);
+        (duration > 0.0).then(|| size as f64 / duration)
+    }
+}
+
+/// The figures per job, by name.
+pub fn job_stats() -> BTreeMap<String, JobStats> {
+    let mut jobs: BTreeMap<String, JobStats> = BTreeMap::new();
+    let since = DateTime::from_timestamp(0, 0).unwrap_or_default().with_timezone(&Local);
+    for (time, value) in history::read_since(SERIES, since) {
+        let mut parts = value.rsplitn(4, ',');
+        let (Some(size), Some(duration), Some(outcome), Some(job)) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        let stats = jobs.entry(job.to_string()).or_default();
+        stats.last_ok = outcome == "ok";
+        if stats.last_ok {
+            stats.succeeded += 1;
+            stats.last_success = Some(time);
+            stats.duration_secs = duration.parse().ok();
+            stats.size_bytes = size.parse().ok();
+        } else {
+            stats.failed += 1;
+        }
+    }
+    jobs
+}
+
+/// `stats backups`: runs, failures and the latest run of each job.
+pub fn job_lines() -> Vec<String> {
+    let jobs = job_stats();
+    if jobs.is_empty() {
+        return vec!["No backups recorded yet.".to_string()];
+    }
+    let mut lines = vec![format!(
+        "{:<16} {:>5} {:>7} {:>16} {:>10} {:>10} {:>10}",
+        "Job", "Runs", "Failed", "Last success", "Took", "Size", "Speed"
+    )];
+    for (job, stats) in jobs {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        lines.push(format!(
+            "{:<16} {:>5} {:>7} {:>16} {:>10} {:>10} {:>10}",
+            job,
+            stats.succeeded + stats.failed,
+            stats.failed,
+            or_dash(stats.last_success.map(|t| t.format("%Y-%m-%d %H:%M").to_string())),
+            or_dash(stats.duration_secs.map(|s| format!("{:.1} s", s))),
+            or_dash(stats.size_bytes.map(format_bytes)),
+            or_dash(stats.throughput().map(|b| format!("{}/s", format_bytes(b as u64)))),
+        ));
+    }
+    lines
+}
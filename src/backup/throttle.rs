@@ -0,0 +1,90 @@
+use std::cell::Cell;
+use std::io::{self, Read};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A read-speed cap shared by every file of one backup, so the server's disk
+/// keeps headroom during a hot backup.
+pub struct Throttle {
+    limit_kbps: Option<u64>,
+    started: Instant,
+    read: Cell<u64>,
+}
+
+impl Throttle {
+    pub fn new(limit_kbps: Option<u64>) -> Self {
+        Throttle {
+            limit_kbps: limit_kbps.filter(|l| *l > 0),
+            started: Instant::now(),
+            read: Cell::new(0),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Throttle::new(None)
+    }
+
+    pub fn reader<R: Read>(&self, inner: R) -> ThrottledReader<'_, R> {
+        ThrottledReader { inner, throttle: self }
+    }
+
+    fn consume(&self, bytes: usize) {
+        let Some(limit) = self.limit_kbps else {
+            return;
+        };
+        self.read.set(self.read.get() + bytes as u64);
+        let expected = Duration::from_secs_f64(self.read.get() as f64 / (limit * 1024) as f64);
+        if let Some(ahead) = expected.checked_sub(self.started.elapsed()) {
+            thread::sleep(ahead);
+        }
+    }
+}
+
+pub struct ThrottledReader<'a, R> {
+    inner: R,
+    throttle: &'a Throttle,
+}
+
+impl<R: Read> Read for ThrottledReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.throttle.consume(n);
+        Ok(n)
+    }
+}
+
+/// Drops the calling thread to idle CPU and I/O priority. Meant for a thread
+/// that exits when its work is done, since the priority can't always be
+/// raised back without privileges.
+pub fn lower_priority() {
+    if cfg!(target_os = "windows") {
+        windows_background_mode();
+    } else if let Some(tid) = linux_thread_id() {
+        // Both apply to a single thread when given its id
+        let _ = std::process::Command::new("renice").args(["-n", "19", "-p", &tid]).output();
+        let _ = std::process::Command::new("ionice").args(["-c", "3", "-p", &tid]).output();
+    }
+}
+
+/// The kernel thread id, from /proc/thread-self -> "<pid>/task/<tid>".
+fn linux_thread_id() -> Option<String> {
+    let link = std::fs::read_link("/proc/thread-self").ok()?;
+    Some(link.file_name()?.to_string_lossy().to_string())
+}
+
+#[cfg(windows)]
+fn windows_background_mode() {
+    const THREAD_MODE_BACKGROUND_BEGIN: i32 = 0x0001_0000;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> isize;
+        fn SetThreadPriority(thread: isize, priority: i32) -> i32;
+    }
+    // Background mode lowers CPU, I/O and memory priority of this thread
+    unsafe {
+        SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN);
+    }
+}
+
+#[cfg(not(windows))]
+fn windows_background_mode() {}
@@ -0,0 +1,140 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use super::archive::{self, Entry};
+use super::catalog::Trigger;
+use super::restore::{self, RestoreResult};
+use super::throttle::Throttle;
+use super::{archive_scope, BackupResult, Scope};
+use crate::config::{BackupConfig, BackupMode, Config};
+use crate::server_props;
+
+/// Subfolder of the backup directory for pre-update backups.
+const UPDATE_DIR: &str = "pre-update";
+/// Copies of the jars the server last started successfully with.
+const LAST_GOOD_DIR: &str = "last-good";
+
+fn update_dir(backup: &BackupConfig) -> PathBuf {
+    Path::new(&backup.directory).join(UPDATE_DIR)
+}
+
+fn jars(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut jars: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e.eq_ignore_ascii_case("jar")))
+        .collect();
+    jars.sort();
+    jars
+}
+
+fn hashes(dir: &Path) -> Vec<(String, String)> {
+    jars(dir)
+        .iter()
+        .filter_map(|jar| {
+            let name = jar.file_name()?.to_string_lossy().to_string();
+            let hash = archive::hash_reader(&name, File::open(jar).ok()?).ok()?;
+            Some((name, hash.sha256))
+        })
+        .collect()
+}
+
+/// Whether the server jars differ from the ones of the last successful start,
+/// i.e. the server was updated (by an updater or a manual jar swap). False
+/// until a first successful start has been recorded.
+pub fn jar_changed(config: &Config, backup: &BackupConfig) -> bool {
+    let last_good = update_dir(backup).join(LAST_GOOD_DIR);
+    let previous = hashes(&last_good);
+    !previous.is_empty() && previous != hashes(&config.server_dir())
+}
+
+/// Remembers the current jars as known-good, once the server reached "Done".
+pub fn mark_good(config: &Config, backup: &BackupConfig) -> io::Result<()> {
+    let server_dir = config.server_dir();
+    let last_good = update_dir(backup).join(LAST_GOOD_DIR);
+    if hashes(&last_good) == hashes(&server_dir) {
+        return Ok(());
+    }
+    let _ = fs::remove_dir_all(&last_good);
+    fs::create_dir_all(&last_good)?;
+    for jar in jars(&server_dir) {
+        if let Some(name) = jar.file_name() {
+            fs::copy(&jar, last_good.join(name))?;
+        }
+    }
+    info!("Update: recorded the current server jar(s) as known-good");
+    Ok(())
+}
+
+/// Full backup of the worlds, top-level configs and the last known-good jars,
+/// taken before an updated server starts for the first time.
+pub fn create_pre_update(config: &Config, backup: &BackupConfig) -> io::Result<BackupResult> {
+    let server_dir = config.server_dir();
+    let mut sources = server_props::world_dirs(&server_dir);
+    sources.extend(config_files(&server_dir));
+    let last_good = update_dir(backup).join(LAST_GOOD_DIR);
+    let extra = jars(&last_good)
+        .into_iter()
+        .filter_map(|jar| {
+            Some(Entry {
+                name: jar.file_name()?.to_string_lossy().to_string(),
+                size: fs::metadata(&jar).ok()?.len(),
+                source: jar,
+            })
+        })
+        .collect();
+
+    let scope = Scope {
+        name: UPDATE_DIR.to_string(),
+        directory: update_dir(backup),
+        sources,
+        extra,
+        incremental: false,
+        config_diff: false,
+        trigger: Trigger::PreUpdate,
+    };
+    // Always a self-contained archive, whatever the regular backup mode is
+    let full = BackupConfig {
+        mode: BackupMode::Full,
+        ..backup.clone()
+    };
+    archive_scope(config, &full, &scope, &Throttle::unlimited())
+}
+
+/// Top-level configuration files and the `config` folder used by mod loaders.
+/// The golem's own config.toml is left out so a rollback never reverts it.
+fn config_files(server_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(server_dir) else {
+        return Vec::new();
+    };
+    let own_config = fs::canonicalize("config.toml").ok();
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| own_config.is_none() || fs::canonicalize(p).ok() != own_config)
+        .filter(|p| {
+            let name = p.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+            if p.is_dir() {
+                return name == "config";
+            }
+            name == "server.properties"
+                || [".yml", ".yaml", ".json", ".toml"].iter().any(|ext| name.ends_with(ext))
+        })
+        .collect()
+}
+
+/// Puts back the world, configs and jars from the newest pre-update backup.
+pub fn rollback(config: &Config, backup: &BackupConfig) -> io::Result<RestoreResult> {
+    let directory = update_dir(backup);
+    let latest = restore::available_with_prefix(&directory, UPDATE_DIR)
+        .pop()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no pre-update backup to roll back to"))?;
+    info!("Update: rolling back to {}", latest);
+    restore::restore_from(config, &directory, &latest)
+}
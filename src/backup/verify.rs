@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use tracing::info;
+
+use super::archive::{self, FileHash};
+
+/// Re-reads a finished backup and compares every file against the checksums
+/// taken while it was written. For directory backups, `only` limits the check
+/// to freshly copied files; hardlinked ones were verified with their original.
+pub fn verify(path: &Path, expected: &[FileHash], only: Option<&[String]>) -> Result<(), String> {
+    let mut expected: HashMap<&str, &FileHash> = expected.iter().map(|h| (h.name.as_str(), h)).collect();
+    if let Some(only) = only {
+        expected.retain(|name, _| only.iter().any(|o| o == name));
+    }
+
+    let actual = read_hashes(path, &expected).map_err(|e| format!("could not read back {}: {}", path.display(), e))?;
+    let mut mismatched = Vec::new();
+    let mut missing = Vec::new();
+    for (name, hash) in &expected {
+        match actual.get(*name) {
+            Some(found) if found == &hash.sha256 => {}
+            Some(_) => mismatched.push(*name),
+            None => missing.push(*name),
+        }
+    }
+    if mismatched.is_empty() && missing.is_empty() {
+        info!("Backup: verified {} file(s)", expected.len());
+        return Ok(());
+    }
+    mismatched.sort();
+    missing.sort();
+    let mut problems = Vec::new();
+    if let Some(first) = mismatched.first() {
+        problems.push(format!("{} file(s) differ (e.g. {})", mismatched.len(), first));
+    }
+    if let Some(first) = missing.first() {
+        problems.push(format!("{} file(s) missing (e.g. {})", missing.len(), first));
+    }
+    Err(problems.join(", "))
+}
+
+fn read_hashes(path: &Path, expected: &HashMap<&str, &FileHash>) -> io::Result<HashMap<String, String>> {
+    let mut actual = HashMap::new();
+    let name = path.to_string_lossy();
+    if path.is_dir() {
+        for file in expected.keys() {
+            if let Ok(source) = File::open(path.join(file)) {
+                actual.insert(file.to_string(), archive::hash_reader(file, source)?.sha256);
+            }
+        }
+    } else if name.ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(File::open(path)?)?;
+        for i in 0..zip.len() {
+            let entry = zip.by_index(i)?;
+            let entry_name = entry.name().to_string();
+            actual.insert(entry_name.clone(), archive::hash_reader(&entry_name, entry)?.sha256);
+        }
+    } else if name.ends_with(".tar.zst") {
+        let mut tar = tar::Archive::new(zstd::Decoder::new(File::open(path)?)?);
+        for entry in tar.entries()? {
+            let entry = entry?;
+            let entry_name = entry.path()?.to_string_lossy().to_string();
+            actual.insert(entry_name.clone(), archive::hash_reader(&entry_name, entry)?.sha256);
+        }
+    }
+    Ok(actual)
+}
@@ -0,0 +1,269 @@
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process;
+
+use crate::audit;
+use crate::backup::{self, Trigger};
+use crate::config::{load_config, Config};
+use crate::instance;
+use crate::ipc;
+use crate::lifecycle;
+use crate::messages::Messages;
+use crate::monitor;
+use crate::notify::{self, Event, EventKind};
+
+/// Handles a subcommand given on the command line. Returns false when the
+/// golem should run normally instead.
+pub fn run(args: &[String]) -> bool {
+    match args.first().map(String::as_str) {
+        None => false,
+        Some("test-notify") => {
+            test_notify();
+            true
+        }
+        Some("backup") => {
+            let (config, _, rest) = load_for_server(&args[1..]);
+            backup_now(&config, rest.first().map(String::as_str));
+            true
+        }
+        Some("restore") => {
+            let (config, server, rest) = load_for_server(&args[1..]);
+            restore(&config, server.as_deref(), rest.first().map(String::as_str));
+            true
+        }
+        Some("backups") => {
+            let (config, _, rest) = load_for_server(&args[1..]);
+            backups(&config, &rest);
+            true
+        }
+        Some("ctl") => {
+            ctl(&args[1..]);
+            true
+        }
+        Some("audit") => {
+            let rest: Vec<&str> = args[1..].iter().map(String::as_str).skip_while(|a| *a == "list").collect();
+            for line in audit::list(&rest) {
+                println!("{}", line);
+            }
+            true
+        }
+        Some("history") => {
+            let (_, _, rest) = load_for_server(&args[1..]);
+            let rest: Vec<&str> = rest.iter().map(String::as_str).collect();
+            for line in lifecycle::list(&rest) {
+                println!("{}", line);
+            }
+            true
+        }
+        Some("monitor") => {
+            monitor::run();
+            true
+        }
+        Some(other) => {
+            eprintln!("Unknown command: {}", other);
+            eprintln!("Usage: rusty-golem [test-notify | backup [<set>] | backups [list | show <id>] | restore [<name>] | audit list [<count>] [<filter>] | history [<count>] [<filter>] | ctl <command> | monitor]");
+            eprintln!("With [[servers]], backup, backups, restore and history need --server <name>.");
+            process::exit(2);
+        }
+    }
+}
+
+/// The config, the server's display name, and the arguments other than
+/// `--server <name>`. With `[[servers]]` that names the server to work with,
+/// and this process takes its settings and state files; without one it is
+/// not clear which is meant, so that is an error.
+fn load_for_server(args: &[String]) -> (Config, Option<String>, Vec<String>) {
+    let config = load_config();
+    let mut name = None;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg != "--server" {
+            rest.push(arg.clone());
+            continue;
+        }
+        let Some(given) = args.next() else {
+            eprintln!("--server needs a server name.");
+            process::exit(2);
+        };
+        name = Some(given.clone());
+    }
+    let names = config.servers.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>().join(", ");
+    match name {
+        None if config.servers.is_empty() => (config, None, rest),
+        Some(_) if config.servers.is_empty() => {
+            eprintln!("--server: config.toml has no [[servers]].");
+            process::exit(2);
+        }
+        None => {
+            eprintln!("config.toml has several servers; say which with --server <name> ({}).", names);
+            process::exit(2);
+        }
+        Some(name) => match config.servers.iter().find(|entry| entry.name == name) {
+            Some(entry) => {
+                instance::enter(&entry.name, false);
+                (config.for_server(entry), Some(entry.display_name().to_string()), rest)
+            }
+            None => {
+                eprintln!("No server named {} in config.toml ({}).", name, names);
+                process::exit(2);
+            }
+        },
+    }
+}
+
+/// Sends one message through every configured backend, synchronously and
+/// without retries or filtering, and reports each result.
+fn test_notify() {
+    let config = load_config();
+    let messages = Messages::from_config(&config);
+    let event = Event::new(EventKind::TestNotification, messages.get("test_notification", &[]));
+
+    let mut failures = 0;
+    for backend in notify::build_backends(&config, None) {
+        match backend.notify(&event) {
+            Ok(()) => println!("{}: OK", backend.name()),
+            Err(e) => {
+                failures += 1;
+                println!("{}: FAILED - {}", backend.name(), e);
+            }
+        }
+    }
+
+    if failures > 0 {
+        println!("{} backend(s) failed.", failures);
+        process::exit(1);
+    }
+    println!("All backends delivered the test message.");
+}
+
+/// One-off backup of the world and every backup set, or of just the named
+/// set ("world" for the world alone). Safe while the server is stopped; while
+/// it runs, the archive may catch region files mid-write.
+fn backup_now(config: &Config, only: Option<&str>) {
+    let Some(backup_config) = &config.backup else {
+        eprintln!("No [backup] section in config.toml.");
+        process::exit(2);
+    };
+    if only.is_some_and(|name| name != "world" && !backup_config.sets.iter().any(|s| s.name == name)) {
+        eprintln!("No backup set named {}.", only.unwrap_or_default());
+        process::exit(2);
+    }
+
+    let mut outcomes = Vec::new();
+    if only.is_none_or(|name| name == "world") {
+        outcomes.push(backup::create(config, backup_config, Trigger::Manual));
+    }
+    for set in backup_config.sets.iter().filter(|s| only.is_none_or(|name| name == s.name)) {
+        outcomes.push(backup::create_set(config, backup_config, set, Trigger::Manual));
+    }
+
+    let mut failed = false;
+    for outcome in outcomes {
+        match outcome {
+            Ok(result) => failed |= result.uploads.iter().any(|(_, outcome)| outcome.is_err()),
+            Err(e) => {
+                eprintln!("Backup failed: {}", e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Browses the backup catalog: `backups list` (the default) or `backups show <id>`,
+/// where the id is a number from the list or a backup name.
+fn backups(config: &Config, args: &[String]) {
+    let Some(backup_config) = &config.backup else {
+        eprintln!("No [backup] section in config.toml.");
+        process::exit(2);
+    };
+    let lines = match (args.first().map(String::as_str), args.get(1)) {
+        (None | Some("list"), _) => backup::list_lines(backup_config),
+        (Some("show"), Some(id)) => backup::show_lines(backup_config, id).unwrap_or_else(|| {
+            eprintln!("No backup {}. Run `rusty-golem backups list` to see them.", id);
+            process::exit(1);
+        }),
+        _ => {
+            eprintln!("Usage: rusty-golem backups [list | show <id>]");
+            process::exit(2);
+        }
+    };
+    if lines.is_empty() {
+        println!("No backups in the catalog yet.");
+    }
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+/// Hands a console command, e.g. `ctl stop` or `ctl extend 30`, to the golem
+/// already running in this folder.
+fn ctl(args: &[String]) {
+    if args.is_empty() {
+        eprintln!("Usage: rusty-golem ctl <command>, e.g. `rusty-golem ctl help`");
+        process::exit(2);
+    }
+    let config = load_config();
+    if config.control_socket.is_empty() {
+        eprintln!("control_socket is turned off in config.toml.");
+        process::exit(2);
+    }
+    if let Err(e) = ipc::send(&config.control_socket, &args.join(" ")) {
+        eprintln!("Could not reach the golem on {}: {}", config.control_socket, e);
+        process::exit(1);
+    }
+}
+
+/// Offline restore, for when the golem itself is not running. Without a name,
+/// lists the available backups. The announcement names the server, if one
+/// of several.
+fn restore(config: &Config, server: Option<&str>, name: Option<&str>) {
+    let Some(backup_config) = &config.backup else {
+        eprintln!("No [backup] section in config.toml.");
+        process::exit(2);
+    };
+    let Some(name) = name else {
+        for name in backup::available(Path::new(&backup_config.directory)) {
+            println!("  {}", name);
+        }
+        return;
+    };
+
+    print!(
+        "Make sure the Minecraft server is not running. The current world will be moved aside and replaced by {}. Type `yes` to confirm: ",
+        name
+    );
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    let _ = io::stdin().lock().read_line(&mut answer);
+    if answer.trim() != "yes" {
+        println!("Restore cancelled.");
+        return;
+    }
+
+    let messages = Messages::from_config(config);
+    let event = match backup::restore(config, backup_config, name) {
+        Ok(result) => {
+            let message = messages.get("backup_restored", &[("file", result.name)]);
+            let message = match server {
+                Some(server) => format!("[{}] {}", server, message),
+                None => message,
+            };
+            Event::new(EventKind::BackupRestored, message)
+        }
+        Err(e) => {
+            eprintln!("Restore failed: {}", e);
+            process::exit(1);
+        }
+    };
+    for backend in notify::build_backends(config, None) {
+        if backend.min_level() <= event.severity {
+            if let Err(e) = backend.notify(&event) {
+                println!("{}: could not announce the restore - {}", backend.name(), e);
+            }
+        }
+    }
+}
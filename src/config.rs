@@ -0,0 +1,1434 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveTime;
+use serde::Deserialize;
+
+use crate::cron;
+use crate::notify::Severity;
+
+/// Read from the working folder at startup, and rewritten by the dashboard's
+/// settings page.
+pub const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Config {
+    /// The server's start script; with `[[servers]]`, set there instead.
+    #[serde(default)]
+    pub server_bat_path: String,
+    /// Server root (world, logs, crash-reports). Defaults to the folder of `server_bat_path`.
+    pub server_dir: Option<String>,
+    pub start_time: String,
+    pub end_time: String,
+    /// How long a manual start outside the running window keeps the server up.
+    #[serde(default = "default_manual_session_minutes")]
+    pub manual_session_minutes: u64,
+    /// Minutes before a scheduled stop at which players are warned.
+    #[serde(default = "default_stop_warning_minutes")]
+    pub stop_warning_minutes: Vec<i64>,
+    /// Several servers supervised side by side, each with its own settings;
+    /// empty for the single server described by the settings above.
+    #[serde(default)]
+    pub servers: Vec<ServerEntry>,
+    #[serde(default)]
+    pub stagger: StaggerConfig,
+    pub discord_webhook_url: String,
+    /// Minimum severity posted to `discord_webhook_url`.
+    #[serde(default)]
+    pub discord_min_level: Severity,
+    /// Additional Discord channels, each with its own minimum severity.
+    #[serde(default)]
+    pub discord_webhooks: Vec<DiscordWebhookConfig>,
+    pub discord_bot: Option<DiscordBotConfig>,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    pub ntfy: Option<NtfyConfig>,
+    pub pushover: Option<PushoverConfig>,
+    pub windows_toast: Option<ToastConfig>,
+    pub windows_event_log: Option<EventLogConfig>,
+    pub line: Option<LineConfig>,
+    pub matrix: Option<MatrixConfig>,
+    pub alarm: Option<AlarmConfig>,
+    /// Events below this severity are not sent to any backend.
+    #[serde(default)]
+    pub notification_level: Severity,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    /// Built-in message catalog: "en" or "ja".
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Per-key overrides of the catalog messages, using `{{placeholder}}` syntax.
+    #[serde(default)]
+    pub messages: HashMap<String, String>,
+    pub status_message: Option<StatusMessageConfig>,
+    /// Console command whose answer gives the TPS shown in status, e.g. "tps"
+    /// on Paper/Spigot or "tick query" on vanilla 1.20.3+. Asked once a minute.
+    pub tps_command: Option<String>,
+    /// TPS below which a `lag` warning goes out, with the host's load.
+    pub lag_tps: Option<f64>,
+    /// Named sequences of console commands, run with `alias <name>` or `/alias`.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, Vec<String>>,
+    pub escalation: Option<EscalationConfig>,
+    pub backup: Option<BackupConfig>,
+    pub api: Option<ApiConfig>,
+    pub grpc: Option<GrpcConfig>,
+    pub mqtt: Option<MqttConfig>,
+    pub influxdb: Option<InfluxConfig>,
+    /// Unix socket or Windows named pipe for `rusty-golem ctl`; empty turns it off.
+    #[serde(default = "default_control_socket")]
+    pub control_socket: String,
+    pub log_file: Option<LogFileConfig>,
+    /// A JSON status file rewritten every few seconds, e.g. "status.json".
+    pub status_file: Option<String>,
+    #[serde(default)]
+    pub disk_space: DiskSpaceConfig,
+    #[serde(default)]
+    pub startup_trend: StartupTrendConfig,
+    #[serde(default)]
+    pub world_growth: WorldGrowthConfig,
+    #[serde(default)]
+    pub log_anomaly: LogAnomalyConfig,
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+    pub heartbeat: Option<HeartbeatConfig>,
+    pub probe: Option<ProbeConfig>,
+    pub memory_trend: Option<MemoryTrendConfig>,
+    /// Cron expression for the weekly report (hours run, playtime leaderboard,
+    /// crashes, average TPS); empty turns it off.
+    #[serde(default = "default_weekly_report")]
+    pub weekly_report: String,
+}
+
+/// One of several servers supervised side by side, under `[[servers]]`.
+/// What it leaves out comes from the top of the file.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ServerEntry {
+    /// Names the folders for its state (`servers/<name>`) and its backups.
+    pub name: String,
+    /// Put in front of each of its notifications, e.g. "[Survival] ...";
+    /// defaults to `name`.
+    pub display_name: Option<String>,
+    pub server_bat_path: String,
+    pub server_dir: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub stop_warning_minutes: Option<Vec<i64>>,
+    /// Replace the ones at the top of the file for this server, e.g. to post
+    /// to a channel of its own.
+    pub discord_webhook_url: Option<String>,
+    pub discord_webhooks: Option<Vec<DiscordWebhookConfig>>,
+    pub webhooks: Option<Vec<WebhookConfig>>,
+}
+
+impl ServerEntry {
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// How the servers of `[[servers]]` take turns starting, under `[stagger]`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StaggerConfig {
+    /// Start the next server once the one before has logged "Done"...
+    pub wait_for_done: bool,
+    /// ...or this many seconds after it started, whichever comes first; 0
+    /// starts them all at once.
+    pub seconds: u64,
+}
+
+impl Default for StaggerConfig {
+    fn default() -> Self {
+        StaggerConfig { wait_for_done: true, seconds: 300 }
+    }
+}
+
+fn default_stop_warning_minutes() -> Vec<i64> {
+    vec![10, 5, 1]
+}
+
+fn default_weekly_report() -> String {
+    // Sunday, 21:00
+    "0 21 * * 0".to_string()
+}
+
+/// The HTTP control API.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiConfig {
+    /// Address and port to listen on; keep it on localhost or the LAN.
+    #[serde(default = "default_api_bind")]
+    pub bind: String,
+    /// Bearer tokens accepted by the API; with none, anyone who can reach
+    /// `bind` may use it.
+    #[serde(default)]
+    pub tokens: Vec<ApiTokenConfig>,
+    /// Serve HTTPS with this certificate instead of plain HTTP.
+    pub tls: Option<TlsConfig>,
+    /// Origins whose pages may call the API from a browser, e.g.
+    /// "https://example.com", or "*" for any.
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+    /// A web dashboard at `/`, with start/stop/backup buttons.
+    pub dashboard: Option<DashboardConfig>,
+    /// A page for players at `/public`, open to all.
+    pub status_page: Option<StatusPageConfig>,
+}
+
+/// The gRPC control interface; only in builds with `--features grpc`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct GrpcConfig {
+    #[serde(default = "default_grpc_bind")]
+    pub bind: String,
+    /// Accepted as `authorization: Bearer <token>` metadata; with none, anyone
+    /// who can reach `bind` may use it.
+    #[serde(default)]
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub tokens: Vec<ApiTokenConfig>,
+}
+
+fn default_grpc_bind() -> String {
+    "127.0.0.1:50051".to_string()
+}
+
+/// An MQTT broker to publish state to and take commands from, e.g. for Home Assistant.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    #[serde(default = "default_mqtt_name")]
+    pub client_id: String,
+    /// State goes to `<prefix>/state` and `<prefix>/players`, commands come
+    /// in on `<prefix>/command`.
+    #[serde(default = "default_mqtt_name")]
+    pub topic_prefix: String,
+    /// Announce the entities for Home Assistant's MQTT discovery.
+    #[serde(default = "default_true")]
+    pub discovery: bool,
+    #[serde(default = "default_discovery_prefix")]
+    pub discovery_prefix: String,
+}
+
+/// A line-protocol write endpoint the status is pushed to, e.g. InfluxDB's.
+#[derive(Deserialize, Debug, Clone)]
+pub struct InfluxConfig {
+    /// The full write URL, database or bucket included.
+    pub url: String,
+    /// Sent as `Authorization: Token <token>` (InfluxDB 2).
+    pub token: Option<String>,
+    #[serde(default = "default_influx_measurement")]
+    pub measurement: String,
+    /// Added to every point, e.g. `server = "survival"`.
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    #[serde(default = "default_influx_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+fn default_influx_measurement() -> String {
+    "rusty_golem".to_string()
+}
+
+fn default_influx_interval_seconds() -> u64 {
+    60
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_name() -> String {
+    "rusty-golem".to_string()
+}
+
+fn default_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiTokenConfig {
+    /// Who holds the token, for the log.
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub scope: Scope,
+    /// Requests beyond this in a minute are refused with 429.
+    pub requests_per_minute: Option<u32>,
+}
+
+/// What a token may do; each includes the ones before it.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Status, schedule, logs and metrics.
+    #[serde(alias = "read-only")]
+    Read,
+    /// Start, stop, restart, extend, backups and the schedule.
+    Control,
+    /// Everything, including server console commands.
+    #[default]
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Control => "control",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+/// PEM files; the certificate file may hold the whole chain.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+/// Credentials for the dashboard: a bearer token, basic auth, or both.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DashboardConfig {
+    pub token: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StatusPageConfig {
+    #[serde(default = "default_status_page_name")]
+    pub name: String,
+    /// What players type into Minecraft, shown under the name.
+    pub address: Option<String>,
+    /// CSS colors: the "Online" badge and the page background.
+    #[serde(default = "default_status_page_accent")]
+    pub accent: String,
+    #[serde(default = "default_status_page_background")]
+    pub background: String,
+}
+
+fn default_status_page_name() -> String {
+    "Minecraft server".to_string()
+}
+
+fn default_status_page_accent() -> String {
+    "#57f287".to_string()
+}
+
+fn default_status_page_background() -> String {
+    "#1e1f22".to_string()
+}
+
+fn default_control_socket() -> String {
+    if cfg!(windows) { r"\\.\pipe\rusty-golem" } else { "rusty-golem.sock" }.to_string()
+}
+
+fn default_api_bind() -> String {
+    "127.0.0.1:8765".to_string()
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BackupConfig {
+    /// Where timestamped archives are written.
+    pub directory: String,
+    /// When to back up around a scheduled stop: "before", "after" or "off".
+    #[serde(default)]
+    pub on_stop: StopBackup,
+    /// Take a hot backup (save-off / save-all / save-on) this often while running.
+    pub hot_interval_minutes: Option<u64>,
+    /// Cap on how fast hot backups read the world, in KB/s, to avoid lag spikes.
+    pub hot_read_limit_kbps: Option<u64>,
+    /// Copy hot backups on a thread at idle CPU and I/O priority.
+    #[serde(default)]
+    pub hot_low_priority: bool,
+    /// Cron expressions ("minute hour day month weekday") for world backups,
+    /// hot while the server runs and cold while it is down.
+    #[serde(default)]
+    pub schedule: Vec<String>,
+    #[serde(default)]
+    pub mode: BackupMode,
+    /// In incremental mode, every Nth backup is a full archive baseline.
+    #[serde(default = "default_full_every")]
+    pub full_every: usize,
+    /// Which world backups to keep in `directory`.
+    pub retention: Option<RetentionConfig>,
+    #[serde(default)]
+    pub format: ArchiveFormat,
+    /// Format-specific: 0-9 for zip (default 6), 1-22 for tar.zst (default 3).
+    pub compression_level: Option<i32>,
+    /// Glob patterns of files and folders to leave out, e.g. "*.tmp" or "cache/".
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Read each new backup back and compare it with checksums of the source files.
+    #[serde(default = "default_true")]
+    pub verify: bool,
+    /// Off-site copy of each finished archive.
+    pub s3: Option<S3Config>,
+    pub sftp: Option<SftpConfig>,
+    pub ftp: Option<FtpConfig>,
+    #[serde(default)]
+    pub rclone: Vec<RcloneConfig>,
+    /// Back up world, configs and the old jar when the server jar changes.
+    #[serde(default = "default_true")]
+    pub pre_update: bool,
+    /// Restore that backup by itself if the updated server doesn't reach "Done".
+    #[serde(default)]
+    pub auto_rollback: bool,
+    #[serde(default = "default_update_start_timeout_minutes")]
+    pub update_start_timeout_minutes: u64,
+    /// What to preserve when the server crashes, before it is restarted.
+    #[serde(default)]
+    pub crash_state: CrashState,
+    /// How many crash-state snapshots to keep.
+    #[serde(default = "default_crash_state_keep")]
+    pub crash_state_keep: usize,
+    /// Extra named backups of files outside the world, e.g. configs and plugins.
+    #[serde(default)]
+    pub sets: Vec<BackupSetConfig>,
+}
+
+fn default_update_start_timeout_minutes() -> u64 {
+    10
+}
+
+fn default_crash_state_keep() -> usize {
+    5
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BackupSetConfig {
+    pub name: String,
+    /// Files and folders relative to the server folder.
+    pub paths: Vec<String>,
+    /// Own schedule; without one the set is backed up alongside every world backup.
+    pub interval_minutes: Option<u64>,
+    /// Cron expressions for this set, instead of or besides an interval.
+    #[serde(default)]
+    pub schedule: Vec<String>,
+    /// Which of this set's local backups to keep.
+    pub retention: Option<RetentionConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct S3Config {
+    /// e.g. "https://s3.eu-west-1.amazonaws.com" or "http://nas:9000" for MinIO.
+    pub endpoint: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Prepended to object keys, e.g. "minecraft/".
+    #[serde(default)]
+    pub prefix: String,
+    /// Archives larger than this are sent as a multipart upload in parts of this size.
+    #[serde(default = "default_part_size_mb")]
+    pub part_size_mb: u64,
+    pub retention: Option<RetentionConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SftpConfig {
+    pub host: String,
+    #[serde(default = "default_sftp_port")]
+    pub port: u16,
+    pub username: String,
+    /// Login password, or the key's passphrase when `private_key` is set.
+    pub password: Option<String>,
+    pub private_key: Option<String>,
+    pub remote_dir: String,
+    /// Per-transfer cap in KB/s.
+    pub bandwidth_limit_kbps: Option<u64>,
+    pub retention: Option<RetentionConfig>,
+}
+
+fn default_sftp_port() -> u16 {
+    22
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FtpConfig {
+    pub host: String,
+    #[serde(default = "default_ftp_port")]
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub remote_dir: String,
+    /// Per-transfer cap in KB/s.
+    pub bandwidth_limit_kbps: Option<u64>,
+    pub retention: Option<RetentionConfig>,
+}
+
+fn default_ftp_port() -> u16 {
+    21
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RcloneConfig {
+    /// An rclone remote and folder, e.g. "gdrive:Minecraft/backups".
+    pub remote: String,
+    /// Path to the rclone executable if it isn't on PATH.
+    #[serde(default = "default_rclone_binary")]
+    pub binary: String,
+    /// Appended to every rclone invocation, e.g. ["--bwlimit", "2M"].
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    pub retention: Option<RetentionConfig>,
+}
+
+fn default_rclone_binary() -> String {
+    "rclone".to_string()
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_part_size_mb() -> u64 {
+    64
+}
+
+/// Which old backups to delete at a destination after each upload.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RetentionConfig {
+    pub keep_count: Option<usize>,
+    pub keep_days: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupMode {
+    /// A complete archive every time.
+    #[default]
+    Full,
+    /// Directory snapshots that hardlink unchanged files to the previous one.
+    Incremental,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    /// A plain directory copy, for deduplicating filesystems.
+    #[serde(rename = "none")]
+    None,
+}
+
+impl ArchiveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => ".zip",
+            ArchiveFormat::TarZst => ".tar.zst",
+            ArchiveFormat::None => "",
+        }
+    }
+}
+
+fn default_full_every() -> usize {
+    12
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StopBackup {
+    /// After `save-all` with saving paused, while the server is still up.
+    #[default]
+    Before,
+    /// Once the server has exited and the files are quiescent.
+    After,
+    Off,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CrashState {
+    #[default]
+    Off,
+    /// Logs, crash reports and each world's level.dat.
+    Minimal,
+    /// The minimal set plus the complete world folders.
+    World,
+}
+
+/// Re-sends unacknowledged alerts along a chain of backends.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EscalationConfig {
+    /// Minutes to wait for an acknowledgement before each escalation step.
+    #[serde(default = "default_ack_timeout_minutes")]
+    pub ack_timeout_minutes: u64,
+    /// Backend names to escalate to, in order, per severity.
+    #[serde(default)]
+    pub chains: HashMap<Severity, Vec<String>>,
+}
+
+fn default_ack_timeout_minutes() -> u64 {
+    10
+}
+
+/// A single Discord message kept up to date by editing it in place.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StatusMessageConfig {
+    /// Defaults to `discord_webhook_url`.
+    pub webhook_url: Option<String>,
+    #[serde(default = "default_status_interval_minutes")]
+    pub interval_minutes: u64,
+}
+
+fn default_status_interval_minutes() -> u64 {
+    5
+}
+
+/// The golem's own messages, without the server's output, kept in a file that
+/// is rotated by size.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LogFileConfig {
+    #[serde(default = "default_log_file_path")]
+    pub path: String,
+    /// Size at which the file is renamed to `<path>.1` and a new one begun.
+    #[serde(default = "default_log_file_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Rotated files kept besides the current one.
+    #[serde(default = "default_log_file_keep")]
+    pub keep: usize,
+}
+
+/// A URL pinged while all is well, for an outside monitor that alerts when
+/// the pings stop.
+#[derive(Deserialize, Debug, Clone)]
+pub struct HeartbeatConfig {
+    pub url: String,
+    /// At least 10.
+    #[serde(default = "default_heartbeat_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+fn default_heartbeat_interval_seconds() -> u64 {
+    60
+}
+
+/// Status pings to the server port, as a player's client would send.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProbeConfig {
+    /// host:port; defaults to server-ip (or 127.0.0.1) and server-port from
+    /// server.properties.
+    pub address: Option<String>,
+    #[serde(default = "default_probe_interval_seconds")]
+    pub interval_seconds: u64,
+    /// Slower answers count as failures.
+    #[serde(default = "default_probe_max_latency_ms")]
+    pub max_latency_ms: u64,
+    /// Share of the last `window` pings that may fail before a warning.
+    #[serde(default = "default_probe_max_failure_percent")]
+    pub max_failure_percent: u64,
+    #[serde(default = "default_probe_window")]
+    pub window: usize,
+    /// Once a minute while pings go unanswered the console is asked too; this
+    /// many such minutes in a row with neither answering count as a freeze,
+    /// and the server is killed and started again. 0 only warns.
+    #[serde(default = "default_probe_freeze_checks")]
+    pub freeze_checks: u32,
+}
+
+fn default_probe_freeze_checks() -> u32 {
+    3
+}
+
+fn default_probe_interval_seconds() -> u64 {
+    60
+}
+
+fn default_probe_max_latency_ms() -> u64 {
+    500
+}
+
+fn default_probe_max_failure_percent() -> u64 {
+    50
+}
+
+fn default_probe_window() -> usize {
+    10
+}
+
+/// Free-space floors under `[disk_space]`; 0 turns a warning off.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DiskSpaceConfig {
+    /// The drive holding the server folder and its worlds.
+    pub world_min_free_mb: u64,
+    /// The drive holding the backup directory.
+    pub backup_min_free_mb: u64,
+}
+
+impl Default for DiskSpaceConfig {
+    fn default() -> Self {
+        DiskSpaceConfig { world_min_free_mb: 2048, backup_min_free_mb: 2048 }
+    }
+}
+
+/// Warnings when the server's memory is set to outgrow its limit before the
+/// session ends, under `[memory_trend]`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MemoryTrendConfig {
+    /// How many minutes of samples the trend is fitted over.
+    #[serde(default = "default_memory_window_minutes")]
+    pub window_minutes: u64,
+    /// The ceiling to project against; the host's RAM when not set.
+    pub limit_mb: Option<u64>,
+    /// Also queue a restart for when nobody is on.
+    #[serde(default)]
+    pub restart: bool,
+}
+
+fn default_memory_window_minutes() -> u64 {
+    180
+}
+
+/// How much the worlds may grow from one daily measurement to the next,
+/// under `[world_growth]`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WorldGrowthConfig {
+    /// 0 turns the warning off; the size is still recorded.
+    pub max_daily_growth_mb: u64,
+}
+
+impl Default for WorldGrowthConfig {
+    fn default() -> Self {
+        WorldGrowthConfig { max_daily_growth_mb: 5120 }
+    }
+}
+
+/// When a minute's WARN and ERROR lines count as a spike, under
+/// `[log_anomaly]`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LogAnomalyConfig {
+    /// How many of the preceding minutes make the baseline.
+    pub baseline_minutes: usize,
+    /// Standard deviations above the baseline's mean.
+    pub deviations: f64,
+    /// Fewer lines than this are never a spike; 0 counts every minute that
+    /// stands out.
+    pub min_lines: u32,
+}
+
+impl Default for LogAnomalyConfig {
+    fn default() -> Self {
+        LogAnomalyConfig { baseline_minutes: 60, deviations: 4.0, min_lines: 20 }
+    }
+}
+
+/// The golem's own heartbeat and how `rusty-golem monitor` judges it, under
+/// `[monitor]`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Rewritten on every pass of the main loop, about every ten seconds.
+    pub heartbeat_file: String,
+    /// A beat older than this means the golem hung or died.
+    pub stale_seconds: u64,
+    /// Time a freshly started golem has to write its first beat.
+    pub grace_seconds: u64,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        MonitorConfig { heartbeat_file: "golem.heartbeat".to_string(), stale_seconds: 120, grace_seconds: 120 }
+    }
+}
+
+/// How many times a server that keeps going down is started again, under
+/// `[watchdog]`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    /// Starts allowed within the window before the watchdog steps in.
+    pub max_attempts: u32,
+    /// The rolling window, in minutes.
+    pub window_minutes: u64,
+    /// Seconds to wait before the restart after the first, second, ... crash
+    /// in a row; the last one applies to every crash after it.
+    pub delays_seconds: Vec<u64>,
+    /// A pause after the server's process ended before it is started again,
+    /// on top of the delay above, for hosts where files stay locked for a
+    /// while.
+    pub restart_delay_seconds: u64,
+    /// Automatic restarts allowed per calendar day, whatever the window says;
+    /// past it the watchdog stops until re-armed by hand. 0 = no limit.
+    pub daily_restart_limit: u32,
+    /// Minutes the server has to stay up for the watchdog to be re-armed:
+    /// the crash count starts over and a spent cooldown is available again.
+    pub stable_minutes: u64,
+    pub on_exhausted: Exhausted,
+    /// With `on_exhausted = "give_up"` (or a failed fallback): minutes until
+    /// one more round of attempts before giving up for good; 0 gives up at
+    /// once.
+    pub cooldown_minutes: u64,
+    /// With `on_exhausted = "retry"`: minutes until another round of attempts.
+    pub retry_minutes: u64,
+    /// With `on_exhausted = "fallback"`: run, and waited for, before another
+    /// round of attempts; if it fails the watchdog gives up.
+    pub fallback_script: Option<String>,
+    /// What to do when the server exits with code 0 by itself, e.g. after
+    /// `stop` was typed in its console. Not counted as a crash.
+    pub on_clean_exit: CleanExit,
+    pub policies: PolicyConfig,
+    pub safe_mode: Option<SafeModeConfig>,
+    pub out_of_memory: Option<OutOfMemoryConfig>,
+}
+
+/// A reduced launch profile for starts after repeated crashes, under
+/// `[watchdog.safe_mode]`. The next start without enough crashes behind it
+/// puts everything back.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SafeModeConfig {
+    /// Crashes in a row before the next start is in safe mode; below
+    /// `max_attempts`, or the watchdog steps in first.
+    #[serde(default = "default_safe_mode_after_crashes")]
+    pub after_crashes: usize,
+    /// server.properties values for safe mode, e.g. `view-distance = 6`.
+    #[serde(default)]
+    pub properties: BTreeMap<String, toml::Value>,
+    /// Datapacks (file or folder names in the world's `datapacks`) moved aside.
+    #[serde(default)]
+    pub disable_datapacks: Vec<String>,
+    /// Started instead of `server_bat_path`, e.g. with other JVM flags.
+    pub start_script: Option<String>,
+}
+
+fn default_safe_mode_after_crashes() -> usize {
+    2
+}
+
+/// More heap after the server ran out of memory, under
+/// `[watchdog.out_of_memory]`: every `-Xmx` in `server_bat_path` goes up by
+/// `step_mb` with each such crash, to at most `max_heap_mb`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OutOfMemoryConfig {
+    #[serde(default = "default_heap_step_mb")]
+    pub step_mb: u64,
+    /// Leave room for the operating system and whatever else runs there.
+    pub max_heap_mb: u64,
+}
+
+fn default_heap_step_mb() -> u64 {
+    1024
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        WatchdogConfig {
+            max_attempts: 3,
+            window_minutes: 5,
+            delays_seconds: vec![15, 60, 300, 900],
+            restart_delay_seconds: 0,
+            daily_restart_limit: 0,
+            stable_minutes: 10,
+            on_exhausted: Exhausted::GiveUp,
+            cooldown_minutes: 30,
+            retry_minutes: 60,
+            fallback_script: None,
+            on_clean_exit: CleanExit::LeaveStopped,
+            policies: PolicyConfig::default(),
+            safe_mode: None,
+            out_of_memory: None,
+        }
+    }
+}
+
+/// What happens after the server stops cleanly without being asked to.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanExit {
+    /// Start it again right away.
+    Restart,
+    /// Leave it off until the next running window.
+    #[default]
+    LeaveStopped,
+    /// Leave it off and ask on Discord (with the bot's slash commands set up)
+    /// whether to start it again.
+    Ask,
+}
+
+/// What follows each way the server can go down, under `[watchdog.policies]`.
+/// Every restart still counts toward the attempts and the daily limit.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PolicyConfig {
+    pub crash: Policy,
+    /// Killed after the server stopped answering.
+    pub freeze: Policy,
+    /// The start script could not be run at all.
+    pub start_failure: Policy,
+    pub out_of_memory: Policy,
+    /// Replaces `on_clean_exit` when set.
+    pub clean_exit: Option<Policy>,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        PolicyConfig {
+            crash: Policy::Backoff,
+            freeze: Policy::Backoff,
+            start_failure: Policy::Backoff,
+            out_of_memory: Policy::Restart,
+            clean_exit: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Policy {
+    /// Start it again after `restart_delay_seconds` only.
+    Restart,
+    /// Start it again after the delay for the crashes in a row so far.
+    Backoff,
+    /// As backoff, with the next start in safe mode.
+    SafeMode,
+    /// Leave it down until it is started by hand or the next window.
+    Halt,
+}
+
+/// What the watchdog does once the attempts are used up.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Exhausted {
+    /// Leave the server down, after one more try once `cooldown_minutes` have
+    /// passed.
+    #[default]
+    GiveUp,
+    /// Try again after `retry_minutes`.
+    Retry,
+    /// Run `fallback_script`, then try again.
+    Fallback,
+}
+
+/// When a boot counts as slow, under `[startup_trend]`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StartupTrendConfig {
+    /// How many of the latest boots the median is taken over.
+    pub baseline_boots: usize,
+    /// How much longer than that median a boot may take; 0 turns the
+    /// warning off.
+    pub max_growth_percent: u64,
+}
+
+impl Default for StartupTrendConfig {
+    fn default() -> Self {
+        StartupTrendConfig { baseline_boots: 10, max_growth_percent: 50 }
+    }
+}
+
+fn default_log_file_path() -> String {
+    "golem.log".to_string()
+}
+
+fn default_log_file_max_size_mb() -> u64 {
+    10
+}
+
+fn default_log_file_keep() -> usize {
+    5
+}
+
+impl Config {
+    pub fn server_dir(&self) -> PathBuf {
+        match &self.server_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => Path::new(&self.server_bat_path)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| PathBuf::from(".")),
+        }
+    }
+
+    /// The config one server of `[[servers]]` is supervised with: the top of
+    /// the file with the server's own settings in place, and its backups in
+    /// a folder of their own.
+    pub fn for_server(&self, entry: &ServerEntry) -> Config {
+        let mut config = self.clone();
+        config.server_bat_path = entry.server_bat_path.clone();
+        config.server_dir = entry.server_dir.clone();
+        if let Some(start_time) = &entry.start_time {
+            config.start_time = start_time.clone();
+        }
+        if let Some(end_time) = &entry.end_time {
+            config.end_time = end_time.clone();
+        }
+        if let Some(minutes) = &entry.stop_warning_minutes {
+            config.stop_warning_minutes = minutes.clone();
+        }
+        if let Some(url) = &entry.discord_webhook_url {
+            config.discord_webhook_url = url.clone();
+        }
+        if let Some(webhooks) = &entry.discord_webhooks {
+            config.discord_webhooks = webhooks.clone();
+        }
+        if let Some(webhooks) = &entry.webhooks {
+            config.webhooks = webhooks.clone();
+        }
+        // Each server's archives in a folder of its own, locally and off-site,
+        // so retention counts only that server's
+        if let Some(backup) = config.backup.as_mut() {
+            backup.directory = Path::new(&backup.directory).join(&entry.name).to_string_lossy().to_string();
+            if let Some(s3) = backup.s3.as_mut() {
+                s3.prefix = format!("{}/", subfolder(&s3.prefix, &entry.name));
+            }
+            if let Some(sftp) = backup.sftp.as_mut() {
+                sftp.remote_dir = subfolder(&sftp.remote_dir, &entry.name);
+            }
+            if let Some(ftp) = backup.ftp.as_mut() {
+                ftp.remote_dir = subfolder(&ftp.remote_dir, &entry.name);
+            }
+            for rclone in &mut backup.rclone {
+                rclone.remote = subfolder(&rclone.remote, &entry.name);
+            }
+        }
+        config
+    }
+
+    /// `start_time` and `end_time`.
+    pub fn daily_hours(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let time = |name: &str, value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("Invalid {} format: {} (expected hh:mm)", name, value))
+        };
+        Ok((time("start_time", &self.start_time)?, time("end_time", &self.end_time)?))
+    }
+}
+
+/// `name` inside a remote folder, an rclone remote ("gdrive:") or an object
+/// key prefix ("minecraft/").
+fn subfolder(dir: &str, name: &str) -> String {
+    if dir.is_empty() || dir.ends_with('/') || dir.ends_with(':') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn default_manual_session_minutes() -> u64 {
+    60
+}
+
+fn default_language() -> String {
+    "en".to_string()
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct NotificationsConfig {
+    /// How many times a failed delivery is attempted before it is dropped and logged.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Identical events within this many seconds are collapsed into one summary.
+    #[serde(default = "default_dedup_window_secs")]
+    pub dedup_window_secs: u64,
+    /// Global cap on delivered messages per minute (0 disables the cap).
+    #[serde(default = "default_max_per_minute")]
+    pub max_per_minute: u32,
+    /// Attach the console tail and new crash reports to crash notifications.
+    #[serde(default = "default_true")]
+    pub attach_crash_logs: bool,
+    #[serde(default = "default_crash_log_lines")]
+    pub crash_log_lines: usize,
+    /// Add RAM, uptime, player count and world size to start/stop/crash messages.
+    #[serde(default = "default_true")]
+    pub include_metrics: bool,
+    #[serde(default)]
+    pub events: EventSwitches,
+}
+
+/// Per-event on/off switches under `[notifications.events]`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct EventSwitches {
+    pub golem_start: bool,
+    pub golem_unresponsive: bool,
+    pub start: bool,
+    pub start_failed: bool,
+    pub stop: bool,
+    pub crash: bool,
+    /// Crashes for want of memory, sent instead of `crash`.
+    pub out_of_memory: bool,
+    pub freeze: bool,
+    pub warning: bool,
+    pub watchdog: bool,
+    pub digest: bool,
+    pub player_join: bool,
+    pub player_leave: bool,
+    pub backup: bool,
+    pub backup_failed: bool,
+    pub restore: bool,
+    pub update_failed: bool,
+    pub schedule: bool,
+    pub disk_space: bool,
+    pub lag: bool,
+    pub probe: bool,
+    pub weekly_report: bool,
+    pub startup_slow: bool,
+    pub memory_growth: bool,
+    pub world_growth: bool,
+    pub log_anomaly: bool,
+}
+
+impl Default for EventSwitches {
+    fn default() -> Self {
+        EventSwitches {
+            golem_start: true,
+            golem_unresponsive: true,
+            start: true,
+            start_failed: true,
+            stop: true,
+            crash: true,
+            out_of_memory: true,
+            freeze: true,
+            warning: true,
+            watchdog: true,
+            digest: true,
+            // Chatty on busy servers, so opt-in
+            player_join: false,
+            player_leave: false,
+            backup: true,
+            backup_failed: true,
+            restore: true,
+            update_failed: true,
+            schedule: true,
+            disk_space: true,
+            lag: true,
+            probe: true,
+            weekly_report: true,
+            startup_slow: true,
+            memory_growth: true,
+            world_growth: true,
+            log_anomaly: true,
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            max_attempts: default_max_attempts(),
+            dedup_window_secs: default_dedup_window_secs(),
+            max_per_minute: default_max_per_minute(),
+            attach_crash_logs: true,
+            crash_log_lines: default_crash_log_lines(),
+            include_metrics: true,
+            events: EventSwitches::default(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_dedup_window_secs() -> u64 {
+    300
+}
+
+fn default_max_per_minute() -> u32 {
+    20
+}
+
+fn default_crash_log_lines() -> usize {
+    100
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DiscordWebhookConfig {
+    /// Name used to refer to this channel, e.g. in escalation chains.
+    pub name: Option<String>,
+    pub url: String,
+    #[serde(default)]
+    pub min_level: Severity,
+}
+
+/// Posting as a bot user instead of (or in addition to) the webhook.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DiscordBotConfig {
+    pub token: String,
+    /// Channel or thread the events are posted to.
+    pub channel_id: String,
+    #[serde(default)]
+    pub min_level: Severity,
+    /// Slash commands for controlling the server; needs the bot to stay connected.
+    pub commands: Option<BotCommandsConfig>,
+    pub chat_bridge: Option<ChatBridgeConfig>,
+}
+
+/// Game chat posted to a Discord channel, and that channel's messages shown in game.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChatBridgeConfig {
+    pub channel_id: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BotCommandsConfig {
+    /// Register the commands on this Discord server only, where they show up at once.
+    pub guild_id: Option<String>,
+    /// Who may start, stop, restart and extend; `/status` is open to everyone.
+    #[serde(default)]
+    pub admin_role_ids: Vec<String>,
+    #[serde(default)]
+    pub admin_user_ids: Vec<String>,
+    /// Who may run server console commands with `/cmd`; admins may not unless
+    /// listed here too.
+    #[serde(default)]
+    pub console_role_ids: Vec<String>,
+    #[serde(default)]
+    pub console_user_ids: Vec<String>,
+    /// How long the Confirm button of `/stop`, `/restart` and `/restore` works.
+    #[serde(default = "default_confirm_timeout_secs")]
+    pub confirm_timeout_secs: u64,
+    /// Console commands `/cmd` refuses, by their first word.
+    #[serde(default = "default_console_blocklist")]
+    pub console_blocklist: Vec<String>,
+    /// Lets everyone vote for `/extend` shortly before the scheduled stop.
+    pub extend_vote: Option<ExtendVoteConfig>,
+}
+
+fn default_confirm_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExtendVoteConfig {
+    /// Distinct users needed.
+    #[serde(default = "default_extend_votes")]
+    pub votes: usize,
+    /// How much a successful vote adds.
+    #[serde(default = "default_extend_vote_minutes")]
+    pub minutes: u64,
+    /// Voting opens this long before the stop.
+    #[serde(default = "default_extend_vote_window_minutes")]
+    pub window_minutes: i64,
+}
+
+fn default_extend_votes() -> usize {
+    3
+}
+
+fn default_extend_vote_minutes() -> u64 {
+    30
+}
+
+fn default_extend_vote_window_minutes() -> i64 {
+    5
+}
+
+fn default_console_blocklist() -> Vec<String> {
+    ["op", "deop", "stop"].map(String::from).to_vec()
+}
+
+/// A generic HTTP endpoint that receives every event as a JSON document.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebhookConfig {
+    /// Name used to refer to this endpoint; defaults to the URL.
+    pub name: Option<String>,
+    pub url: String,
+    #[serde(default = "default_webhook_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub min_level: Severity,
+    /// JSON body template. Either a TOML table or a string containing JSON.
+    /// String values may contain `{{event}}`, `{{severity}}`, `{{message}}`, `{{text}}`
+    /// (message plus details) and `{{timestamp}}`.
+    pub body: Option<serde_json::Value>,
+}
+
+impl WebhookConfig {
+    /// The body template, parsed first when it is written as a JSON string.
+    pub fn body_template(&self) -> Result<serde_json::Value, String> {
+        let body = match &self.body {
+            Some(serde_json::Value::String(raw)) => serde_json::from_str(raw)
+                .map_err(|e| format!("Invalid JSON body template for {}: {}", self.url, e))?,
+            Some(value) => value.clone(),
+            None => serde_json::json!({
+                "event": "{{event}}",
+                "severity": "{{severity}}",
+                "message": "{{message}}",
+                "text": "{{text}}",
+                "timestamp": "{{timestamp}}"
+            }),
+        };
+        Ok(body)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct NtfyConfig {
+    /// Full topic URL, e.g. `https://ntfy.sh/my-golem-alerts`.
+    pub topic_url: String,
+    /// Access token for protected topics.
+    pub token: Option<String>,
+    #[serde(default = "default_push_min_level")]
+    pub min_level: Severity,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PushoverConfig {
+    /// Application API token.
+    pub token: String,
+    /// User or group key.
+    pub user: String,
+    #[serde(default = "default_push_min_level")]
+    pub min_level: Severity,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LineConfig {
+    /// Long-lived channel access token of the Messaging API channel.
+    pub channel_access_token: String,
+    /// User, group or room ID to push to.
+    pub target_id: String,
+    #[serde(default)]
+    pub min_level: Severity,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MatrixConfig {
+    /// e.g. `https://matrix.example.org`
+    pub homeserver_url: String,
+    pub access_token: String,
+    /// Room ID (`!abc123:example.org`), not an alias.
+    pub room_id: String,
+    #[serde(default)]
+    pub min_level: Severity,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ToastConfig {
+    /// AppUserModelID shown as the toast's source; defaults to PowerShell's.
+    pub app_id: Option<String>,
+    #[serde(default = "default_push_min_level")]
+    pub min_level: Severity,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EventLogConfig {
+    /// The event source entries are written under; registered on first use,
+    /// which needs the golem to run elevated once.
+    #[serde(default = "default_event_log_source")]
+    pub source: String,
+    #[serde(default = "default_event_log_name")]
+    pub log: String,
+    #[serde(default = "default_push_min_level")]
+    pub min_level: Severity,
+}
+
+fn default_event_log_source() -> String {
+    "Rusty-Golem".to_string()
+}
+
+fn default_event_log_name() -> String {
+    "Application".to_string()
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlarmConfig {
+    /// WAV file to play; without one the console bell is rung instead.
+    pub sound_file: Option<String>,
+    #[serde(default = "default_alarm_repeat")]
+    pub repeat: u32,
+    #[serde(default = "default_alarm_min_level")]
+    pub min_level: Severity,
+}
+
+fn default_alarm_repeat() -> u32 {
+    5
+}
+
+fn default_alarm_min_level() -> Severity {
+    Severity::Critical
+}
+
+// Phones should only buzz for things worth looking at
+fn default_push_min_level() -> Severity {
+    Severity::Warn
+}
+
+fn default_webhook_method() -> String {
+    "POST".to_string()
+}
+
+pub fn load_config() -> Config {
+    let content = fs::read_to_string(CONFIG_FILE).expect("Failed to read config.toml");
+    parse_config(&content).unwrap_or_else(|e| panic!("Failed to parse config.toml: {}", e))
+}
+
+/// Parses and checks a config the way startup does, so an edit that would
+/// keep the golem from starting again is refused up front.
+pub fn parse_config(text: &str) -> Result<Config, String> {
+    let config: Config = toml::from_str(text).map_err(|e| e.to_string())?;
+    config.daily_hours()?;
+    if config.servers.is_empty() && config.server_bat_path.is_empty() {
+        return Err("server_bat_path is missing (or [[servers]], for several servers)".to_string());
+    }
+    let mut names = HashSet::new();
+    for entry in &config.servers {
+        let name_ok = !entry.name.is_empty() && entry.name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !name_ok {
+            return Err(format!("[[servers]] name \"{}\": use letters, digits, - and _ only", entry.name));
+        }
+        if !names.insert(&entry.name) {
+            return Err(format!("[[servers]] name \"{}\" is used twice", entry.name));
+        }
+        config.for_server(entry).daily_hours().map_err(|e| format!("[[servers]] {}: {}", entry.name, e))?;
+    }
+    let webhooks = config.webhooks.iter().chain(config.servers.iter().flat_map(|entry| entry.webhooks.iter().flatten()));
+    for webhook in webhooks {
+        webhook.body_template()?;
+    }
+    let schedules = config
+        .backup
+        .iter()
+        .flat_map(|b| b.schedule.iter().chain(b.sets.iter().flat_map(|s| &s.schedule)));
+    for expression in schedules {
+        cron::Schedule::parse(expression).map_err(|e| format!("Invalid backup schedule: {}", e))?;
+    }
+    if !config.weekly_report.is_empty() {
+        cron::Schedule::parse(&config.weekly_report).map_err(|e| format!("Invalid weekly_report: {}", e))?;
+    }
+    if config.watchdog.on_exhausted == Exhausted::Fallback && config.watchdog.fallback_script.is_none() {
+        return Err("watchdog.on_exhausted = \"fallback\" needs a fallback_script".to_string());
+    }
+    let policies = &config.watchdog.policies;
+    let policies = [policies.crash, policies.freeze, policies.start_failure, policies.out_of_memory];
+    let safe_mode_policy = policies.contains(&Policy::SafeMode) || config.watchdog.policies.clean_exit == Some(Policy::SafeMode);
+    if safe_mode_policy && config.watchdog.safe_mode.is_none() {
+        return Err("a \"safe_mode\" policy in [watchdog.policies] needs [watchdog.safe_mode]".to_string());
+    }
+    let bot_commands = config.discord_bot.as_ref().is_some_and(|bot| bot.commands.is_some());
+    if config.watchdog.on_clean_exit == CleanExit::Ask && !bot_commands {
+        return Err("watchdog.on_clean_exit = \"ask\" needs [discord_bot.commands]".to_string());
+    }
+    Ok(config)
+}
+
+/// Replaces config.toml in one step, so a crash halfway through cannot leave
+/// a truncated file behind.
+pub fn save_config(text: &str) -> io::Result<()> {
+    let temporary = format!("{}.tmp", CONFIG_FILE);
+    fs::write(&temporary, text)?;
+    fs::rename(&temporary, CONFIG_FILE)
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::thread;
+
+use crate::audit;
+use crate::availability::Availability;
+use crate::queue::{Action, ActionQueue, When};
+use crate::backup;
+use crate::config::BackupConfig;
+use crate::control::{Command, SharedStatus};
+use crate::history;
+use crate::instance::{Handle, Instances};
+use crate::lifecycle;
+use crate::messages::Messages;
+use crate::schedule;
+use crate::startup;
+use crate::world_growth;
+
+const HELP: &str = "Commands: status, start, stop, restart, watchdog reset, extend <minutes>, backup now, pause-schedule, \
+resume-schedule, schedule [set <day> <hours>|closed|default], alias [<name>], backups [<id>], restore [<name>], rollback, \
+audit list [<count>] [<filter>], history [<count>] [<filter>], stats [players|tps|world [--week|--month]|startup|backups], \
+queue [<stop|restart|backup> [empty|idle] | cancel <id>]. Anything else goes to the server console; \
+prefix it with `/` when it clashes with one of these, e.g. `/stop`. With several servers, `servers` lists them and \
+a server's name in front picks one, e.g. `creative stop`; without one a command is for the first.";
+
+/// The golem's command set, shared by its own console and the control socket.
+#[derive(Clone)]
+pub struct Interpreter {
+    pub aliases: BTreeMap<String, Vec<String>>,
+    pub availability: Availability,
+    pub backup: Option<BackupConfig>,
+    pub messages: Messages,
+    pub queue: ActionQueue,
+    pub status: SharedStatus,
+}
+
+impl Interpreter {
+    /// Works out one command line: the lines to answer with and the command,
+    /// if any, for the main loop. `status`, `start`, `stop`, `restart`, `watchdog reset`,
+    /// `extend <minutes>`, `backup now`, `pause-schedule`/`resume-schedule` and
+    /// `schedule set <day> <hours>` control the golem, `alias <name>` runs one
+    /// of the config's command sequences, `backups [<id>]` browses the catalog, and `restore
+    /// <name>` and `rollback` queue a restore without asking (the console asks
+    /// first). Any other line goes to the server console as typed, or with a
+    /// leading `/` removed, which is how to reach server commands sharing a
+    /// name with ours.
+    pub fn interpret(&self, line: &str) -> (Vec<String>, Option<Command>) {
+        let line = line.trim();
+        if let Some(server_command) = line.strip_prefix('/') {
+            return (Vec::new(), Some(Command::Console(server_command.to_string())));
+        }
+        let mut words = line.split_whitespace();
+        let command = match (words.next(), words.next()) {
+            (None, _) => None,
+            (Some("status"), _) => {
+                let status = self.status.lock().map(|s| s.clone()).unwrap_or_default();
+                let lines = status
+                    .fields(&self.messages)
+                    .into_iter()
+                    .map(|(name, value)| format!("{:<12} {}", format!("{}:", name), value))
+                    .collect();
+                return (lines, None);
+            }
+            (Some("start"), _) => Some(Command::Start),
+            (Some("stop"), _) => Some(Command::Stop),
+            (Some("restart"), _) => Some(Command::Restart),
+            (Some("watchdog"), Some("reset")) => Some(Command::WatchdogReset),
+            (Some("watchdog"), _) => return (vec!["Usage: watchdog reset".to_string()], None),
+            (Some("extend"), minutes) => match minutes.map(str::parse::<u64>) {
+                Some(Ok(minutes)) if minutes > 0 => Some(Command::Extend(minutes)),
+                _ => return (vec!["Usage: extend <minutes>".to_string()], None),
+            },
+            (Some("backup"), Some("now")) => Some(Command::Backup(None)),
+            (Some("backup"), _) => return (vec!["Type `backup now` to back up the world.".to_string()], None),
+            (Some("pause-schedule"), _) => Some(Command::PauseSchedule),
+            (Some("resume-schedule"), _) => Some(Command::ResumeSchedule),
+            (Some("schedule"), None) => {
+                let week = self.status.lock().map(|s| s.week.clone()).unwrap_or_default();
+                return (schedule::week_lines(&week), None);
+            }
+            (Some("schedule"), Some("set")) => match (words.next(), words.next()) {
+                (Some(day), Some(hours)) => match schedule::parse_change(day, hours) {
+                    Ok(change) => Some(Command::SetHours(vec![change])),
+                    Err(e) => return (vec![e], None),
+                },
+                _ => return (vec!["Usage: schedule set <day> <18:00-24:00|closed|default>".to_string()], None),
+            },
+            (Some("schedule"), _) => return (vec!["Type `schedule` or `schedule set <day> <hours>`.".to_string()], None),
+            (Some("help"), _) => return (vec![HELP.to_string()], None),
+            (Some("audit"), None | Some("list")) => return (audit::list(&words.collect::<Vec<_>>()), None),
+            (Some("audit"), _) => return (vec!["Usage: audit list [<count>] [<filter>]".to_string()], None),
+            (Some("history"), first) => {
+                let args: Vec<&str> = first.into_iter().chain(words).collect();
+                return (lifecycle::list(&args), None);
+            }
+            (Some("stats"), None) => return (self.availability.lines(), None),
+            (Some("stats"), Some("players")) => return (history::player_lines(&words.collect::<Vec<_>>()), None),
+            (Some("stats"), Some("tps")) => return (history::tps_lines(&words.collect::<Vec<_>>()), None),
+            (Some("stats"), Some("world")) => return (world_growth::lines(&words.collect::<Vec<_>>()), None),
+            (Some("stats"), Some("startup")) => return (startup::lines(), None),
+            (Some("stats"), Some("backups")) => return (backup::job_lines(), None),
+            (Some("stats"), _) => return (vec!["Usage: stats [players|tps|world [--week|--month]|startup|backups]".to_string()], None),
+            (Some("queue"), None) => return (self.queue.lines(), None),
+            (Some("queue"), Some("cancel")) => {
+                let Some(id) = words.next().and_then(|id| id.trim_start_matches('#').parse().ok()) else {
+                    return (vec!["Usage: queue cancel <id>".to_string()], None);
+                };
+                let cancelled = self.queue.cancel(id);
+                audit::record("console", "local", &format!("queue cancel #{}", id), if cancelled { "cancelled" } else { "not queued" });
+                return (vec![if cancelled { format!("Cancelled #{}.", id) } else { format!("Nothing queued as #{}.", id) }], None);
+            }
+            (Some("queue"), Some(action)) => {
+                let Some(action) = Action::parse(action) else {
+                    return (vec!["Usage: queue <stop|restart|backup> [empty|idle], or queue cancel <id>".to_string()], None);
+                };
+                let Some(when) = words.next().map_or(Some(action.default_when()), When::parse) else {
+                    return (vec!["Say when: empty (nobody on) or idle (once the golem is free).".to_string()], None);
+                };
+                let id = self.queue.add(action, when, "console");
+                audit::record("console", "local", &format!("queue {} when {}", action.as_str(), when.as_str()), &format!("queued as #{}", id));
+                return (vec![format!("Queued as #{}: {} when {}.", id, action.as_str(), when.as_str())], None);
+            }
+            (Some("alias"), None) => {
+                let mut lines: Vec<String> =
+                    self.aliases.iter().map(|(name, commands)| format!("  {:<12} {}", name, commands.join("; "))).collect();
+                if lines.is_empty() {
+                    lines.push("No aliases; define them under [aliases] in config.toml.".to_string());
+                }
+                return (lines, None);
+            }
+            (Some("alias"), Some(name)) => match self.aliases.get(name) {
+                Some(commands) => Some(Command::ConsoleLines(commands.clone())),
+                None => return (vec![format!("No alias {}. Type `alias` to list them.", name)], None),
+            },
+            (Some("backups"), id) => {
+                let Some(backup) = &self.backup else {
+                    return (vec!["No [backup] section in config.toml.".to_string()], None);
+                };
+                let lines = match id {
+                    None => backup::list_lines(backup),
+                    Some(id) => backup::show_lines(backup, id)
+                        .unwrap_or_else(|| vec![format!("No backup {}. Type `backups` to list them.", id)]),
+                };
+                return (lines, None);
+            }
+            (Some("restore"), name) => {
+                let Some(backup) = &self.backup else {
+                    return (vec!["No [backup] section in config.toml.".to_string()], None);
+                };
+                let dir = Path::new(&backup.directory);
+                let available = backup::available(dir);
+                let Some(name) = name else {
+                    let mut lines: Vec<String> = available.iter().map(|name| format!("  {}", name)).collect();
+                    if available.is_empty() {
+                        lines.push(format!("No backups in {}.", dir.display()));
+                    }
+                    lines.push("Type `restore <name>` to restore one.".to_string());
+                    return (lines, None);
+                };
+                if !available.iter().any(|n| n == name) {
+                    return (vec![format!("No backup named {}. Type `restore` to list them.", name)], None);
+                }
+                Some(Command::Restore(name.to_string()))
+            }
+            (Some("rollback"), _) => Some(Command::Rollback),
+            (Some(_), _) => Some(Command::Console(line.to_string())),
+        };
+        (Vec::new(), command)
+    }
+}
+
+/// Works out a line for the server it names: with `[[servers]]`, a line
+/// starting with a server's name is that server's command (the name alone
+/// asks for its status), and any other is the first server's. `servers`
+/// lists them all. Returns the answer, and the command with the server to
+/// send it to.
+pub fn route(instances: &Instances, line: &str) -> (Vec<String>, Option<(Command, Handle)>) {
+    let line = line.trim();
+    if line == "servers" {
+        return (server_lines(instances), None);
+    }
+    let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let (handle, line) = match instances.get(first) {
+        Some(handle) if rest.trim().is_empty() => (handle, "status"),
+        Some(handle) => (handle, rest),
+        None => (instances.primary(), line),
+    };
+    let (answer, command) = handle.within(|| handle.interpreter.interpret(line));
+    (answer, command.map(|command| (command, handle)))
+}
+
+/// One line per server: its name, state and players.
+fn server_lines(instances: &Instances) -> Vec<String> {
+    instances
+        .all()
+        .iter()
+        .map(|handle| {
+            let status = handle.status();
+            let state = handle.interpreter.messages.get(if status.online { "status_online" } else { "status_offline" }, &[]);
+            let name = handle.name.as_deref().unwrap_or("server");
+            format!("  {:<12} {:<10} {} player(s)", name, state, status.players)
+        })
+        .collect()
+}
+
+/// Reads the golem's own console (not the Minecraft one) as a command prompt
+/// for the interpreter. An empty line acknowledges every server's outstanding
+/// alerts, and restores and rollbacks wait for a typed `yes`.
+pub fn spawn(instances: Instances) {
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        while let Some(Ok(line)) = lines.next() {
+            if line.trim().is_empty() {
+                if let Some(count) = instances.acknowledge_all().filter(|count| *count > 0) {
+                    println!("Acknowledged {} alert(s).", count);
+                }
+                continue;
+            }
+            let (answer, command) = route(&instances, &line);
+            for line in answer {
+                println!("{}", line);
+            }
+            let confirm = match command.as_ref().map(|(command, _)| command) {
+                Some(Command::Restore(name)) => Some((
+                    format!("This stops the server, moves the current world aside and restores {}.", name),
+                    "Restore",
+                )),
+                Some(Command::Rollback) => Some((
+                    "This stops the server and restores the world, configs and jar from the newest pre-update backup."
+                        .to_string(),
+                    "Rollback",
+                )),
+                _ => None,
+            };
+            if let Some((warning, what)) = confirm {
+                let server = command.as_ref().and_then(|(_, handle)| handle.name.as_ref());
+                match server {
+                    Some(name) => println!("{} ({}) Type `yes` to confirm:", warning, name),
+                    None => println!("{} Type `yes` to confirm:", warning),
+                }
+                if !matches!(lines.next(), Some(Ok(answer)) if answer.trim() == "yes") {
+                    println!("{} cancelled.", what);
+                    continue;
+                }
+                println!("{} queued.", what);
+            }
+            if let Some((command, handle)) = command {
+                if handle.commands.send(command).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
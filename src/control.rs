@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Weekday};
+
+use crate::backup::{JobStats, Record};
+use crate::config::Config;
+use crate::digest;
+use crate::jobs::BackupJob;
+use crate::messages::Messages;
+use crate::metrics::{format_bytes, Host};
+use crate::schedule::Hours;
+
+/// Requests for the main loop from the golem's console and remote interfaces.
+pub enum Command {
+    /// Stop the server if it runs, restore the named backup, and let the
+    /// schedule start it again.
+    Restore(String),
+    /// The same, from the newest pre-update backup, undoing a failed update.
+    Rollback,
+    /// Start now; outside the running window this opens a manual session.
+    Start,
+    /// Stop now and stay stopped until the window next opens.
+    Stop,
+    Restart,
+    /// Forget the crashes the watchdog counted, lift whatever it is holding
+    /// off for, and start now: the problem was fixed.
+    WatchdogReset,
+    /// Push today's stop back by this many minutes.
+    Extend(u64),
+    /// A line for the Minecraft server console, e.g. "say hello".
+    Console(String),
+    /// Several, in order: an alias.
+    ConsoleLines(Vec<String>),
+    /// The same, answered with the lines the server printed in reply, or
+    /// None when it is not running.
+    Query(String, Sender<Option<Vec<String>>>),
+    /// Back up the world (hot if the server runs) and the sets that follow it,
+    /// reporting to the API job that asked for it, if any.
+    Backup(Option<BackupJob>),
+    /// Stop following the schedule, leaving the server as it is.
+    PauseSchedule,
+    ResumeSchedule,
+    /// Give weekdays hours of their own, or with None the daily ones again.
+    SetHours(Vec<(Weekday, Option<Hours>)>),
+    /// A checked config, already saved, to go by from now on.
+    ApplyConfig(Box<Config>),
+}
+
+/// What the main loop last saw, for interfaces that answer without waiting on it.
+#[derive(Clone, Default)]
+pub struct Status {
+    pub online: bool,
+    /// Up while it should be, or down because it should be.
+    pub healthy: bool,
+    pub online_since: Option<DateTime<Local>>,
+    /// Of the server process (the start script, on Windows `cmd`).
+    pub pid: Option<u32>,
+    pub players: usize,
+    pub player_names: Vec<String>,
+    pub tps: Option<f64>,
+    /// Memory of the server process and its children.
+    pub rss_bytes: Option<u64>,
+    /// The machine the golem runs on, sampled once a minute.
+    pub host: Option<Host>,
+    /// Round trip of the last status ping to the server port, if probing.
+    pub latency: Option<Duration>,
+    pub last_backup: Option<Record>,
+    /// Runs and the latest outcome of each backup job, from the stats store.
+    pub backup_jobs: BTreeMap<String, JobStats>,
+    /// Set while the server is meant to run.
+    pub closes_at: Option<DateTime<Local>>,
+    pub opens_at: Option<DateTime<Local>>,
+    pub schedule_paused: bool,
+    /// Each day's hours, and whether they are its own.
+    pub week: Vec<(Weekday, Hours, bool)>,
+    /// The last lines of the server console, oldest first.
+    pub log_tail: Vec<String>,
+    pub counters: Counters,
+}
+
+/// Running totals since the golem started.
+#[derive(Clone, Copy, Default)]
+pub struct Counters {
+    pub starts: u64,
+    /// Starts after a crash or a restart command.
+    pub restarts: u64,
+    pub crashes: u64,
+    /// Kills after the server stopped answering.
+    pub freezes: u64,
+}
+
+pub type SharedStatus = Arc<Mutex<Status>>;
+
+impl Status {
+    /// The last `lines` console lines.
+    pub fn tail(&self, lines: usize) -> &[String] {
+        &self.log_tail[self.log_tail.len().saturating_sub(lines)..]
+    }
+
+    /// State, uptime, players, TPS, memory, latency, the host's load, the next scheduled event and the
+    /// last backup.
+    pub fn fields(&self, messages: &Messages) -> Vec<(String, String)> {
+        let (state, uptime) = match self.online_since.filter(|_| self.online) {
+            Some(since) => (
+                messages.get("status_online", &[]),
+                digest::format_duration((Local::now() - since).to_std().unwrap_or_default()),
+            ),
+            None => (messages.get("status_offline", &[]), "-".to_string()),
+        };
+        let next = match (self.closes_at, self.opens_at) {
+            _ if self.schedule_paused => messages.get("status_paused", &[]),
+            (Some(closes), _) => messages.get("status_next_stop", &[("time", closes.format("%H:%M").to_string())]),
+            (None, Some(opens)) => messages.get("status_next_start", &[("time", opens.format("%H:%M").to_string())]),
+            (None, None) => "-".to_string(),
+        };
+        let players = if self.player_names.is_empty() {
+            self.players.to_string()
+        } else {
+            format!("{} ({})", self.players, self.player_names.join(", "))
+        };
+        let online = |value: Option<String>| value.filter(|_| self.online).unwrap_or_else(|| "-".to_string());
+        let last_backup = match self.last_backup.as_ref().map(|r| r.created) {
+            Some(time) if time.date_naive() == Local::now().date_naive() => time.format("%H:%M").to_string(),
+            Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+            None => "-".to_string(),
+        };
+        vec![
+            (messages.get("status_state", &[]), state),
+            (messages.get("status_uptime", &[]), uptime),
+            (messages.get("status_players", &[]), players),
+            (messages.get("status_tps", &[]), online(self.tps.map(|tps| format!("{:.1}", tps)))),
+            (messages.get("status_ram", &[]), online(self.rss_bytes.map(format_bytes))),
+            (messages.get("status_latency", &[]), online(self.latency.map(|rtt| format!("{} ms", rtt.as_millis())))),
+            (messages.get("status_host", &[]), self.host.map_or("-".to_string(), |host| host.summary())),
+            (messages.get("status_next_event", &[]), next),
+            (messages.get("status_last_backup", &[]), last_backup),
+        ]
+    }
+}
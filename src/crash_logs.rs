@@ -0,0 +1,187 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::messages::Messages;
+use crate::notify::Attachment;
+
+// Discord rejects larger uploads on unboosted servers
+const MAX_ATTACHMENT_BYTES: u64 = 8 * 1024 * 1024;
+/// Keeps a long exception message within an embed field.
+const MAX_FIELD_CHARS: usize = 300;
+
+/// Builds the files attached to a crash notification: the console tail
+/// and every `crash-reports/crash-*.txt` written since `since`.
+pub fn collect(server_dir: &Path, since: SystemTime, tail: &[String]) -> Vec<Attachment> {
+    let mut attachments = Vec::new();
+    if !tail.is_empty() {
+        attachments.push(Attachment {
+            filename: "latest-log-excerpt.txt".to_string(),
+            content: tail.join("\n").into_bytes(),
+        });
+    }
+    for (name, path, size) in new_reports(server_dir, since) {
+        if size > MAX_ATTACHMENT_BYTES {
+            continue;
+        }
+        if let Ok(content) = fs::read(path) {
+            attachments.push(Attachment { filename: name, content });
+        }
+    }
+    attachments
+}
+
+/// A file to attach as it is, unless it is too large to upload.
+pub fn attachment(path: &Path) -> Option<Attachment> {
+    if fs::metadata(path).ok()?.len() > MAX_ATTACHMENT_BYTES {
+        return None;
+    }
+    let filename = path.file_name()?.to_string_lossy().to_string();
+    Some(Attachment { filename, content: fs::read(path).ok()? })
+}
+
+/// `crash-reports/crash-*.txt` written since `since`, oldest first, with their
+/// sizes.
+fn new_reports(server_dir: &Path, since: SystemTime) -> Vec<(String, PathBuf, u64)> {
+    let Ok(entries) = fs::read_dir(server_dir.join("crash-reports")) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<(SystemTime, String, PathBuf, u64)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !(name.starts_with("crash-") && name.ends_with(".txt")) {
+                return None;
+            }
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().ok().filter(|m| *m >= since)?;
+            Some((modified, name, entry.path(), meta.len()))
+        })
+        .collect();
+    reports.sort_by_key(|(modified, ..)| *modified);
+    reports.into_iter().map(|(_, name, path, size)| (name, path, size)).collect()
+}
+
+/// What a crash report says went wrong.
+#[derive(Default)]
+pub struct CrashReport {
+    /// The "Description:" line, e.g. "Exception in server tick loop".
+    pub description: Option<String>,
+    /// The exception line that follows it.
+    pub cause: Option<String>,
+    /// Mods or plugins the loader blames ("Suspected Mod(s):" on Forge and
+    /// Fabric), if any.
+    pub suspects: Vec<String>,
+}
+
+pub fn parse_report(text: &str) -> CrashReport {
+    let mut report = CrashReport::default();
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next() {
+        if let Some(description) = line.strip_prefix("Description:") {
+            if report.description.is_none() {
+                report.description = Some(description.trim().to_string());
+                // The exception follows after a blank line
+                while lines.peek().is_some_and(|l| l.trim().is_empty()) {
+                    lines.next();
+                }
+                report.cause = lines.next().map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+            }
+        } else if let Some((_, named)) = line.split_once("Suspected Mod:").or_else(|| line.split_once("Suspected Mods:")) {
+            let named = named.trim();
+            if !named.is_empty() && !named.eq_ignore_ascii_case("none") && !named.eq_ignore_ascii_case("unknown") {
+                report.suspects.push(named.to_string());
+            }
+            // Forge lists them below, one tab in; details sit two tabs in
+            while let Some(next) = lines.peek().filter(|l| l.starts_with('\t')) {
+                if !next.starts_with("\t\t") {
+                    report.suspects.push(next.trim().to_string());
+                }
+                lines.next();
+            }
+        }
+    }
+    report
+}
+
+/// Notification fields from the newest crash report written since `since`.
+pub fn report_fields(server_dir: &Path, since: SystemTime, messages: &Messages) -> Vec<(String, String)> {
+    let Some((_, path, _)) = new_reports(server_dir, since).pop() else {
+        return Vec::new();
+    };
+    let Ok(text) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let report = parse_report(&text);
+    let shorten = |text: String| match text.char_indices().nth(MAX_FIELD_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    };
+    let mut fields = Vec::new();
+    if let Some(description) = report.description {
+        fields.push((messages.get("crash_description", &[]), shorten(description)));
+    }
+    if let Some(cause) = report.cause {
+        fields.push((messages.get("crash_cause", &[]), shorten(cause)));
+    }
+    if !report.suspects.is_empty() {
+        fields.push((messages.get("crash_suspects", &[]), shorten(report.suspects.join("\n"))));
+    }
+    fields
+}
+
+/// How far back in the console `unrecoverable` looks.
+pub const SIGNATURE_LINES: usize = 200;
+
+/// Why a server went down that starting it again cannot fix.
+#[derive(Clone, Copy, Debug)]
+pub enum Unrecoverable {
+    /// Another process holds the server's port.
+    PortInUse,
+    /// The world was saved by a newer version than this server.
+    NewerWorld,
+    /// A mod or plugin needs one that is missing or the wrong version.
+    MissingDependency,
+    /// The server jar needs a newer Java than the one running it.
+    JavaTooOld,
+}
+
+impl Unrecoverable {
+    /// The message describing it.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Unrecoverable::PortInUse => "unrecoverable_port",
+            Unrecoverable::NewerWorld => "unrecoverable_world",
+            Unrecoverable::MissingDependency => "unrecoverable_dependency",
+            Unrecoverable::JavaTooOld => "unrecoverable_java",
+        }
+    }
+}
+
+/// Lowercase fragments of what vanilla, Paper, Forge, NeoForge and Fabric
+/// print for each.
+const SIGNATURES: &[(Unrecoverable, &str)] = &[
+    (Unrecoverable::PortInUse, "failed to bind to port"),
+    (Unrecoverable::PortInUse, "address already in use"),
+    (Unrecoverable::NewerWorld, "newer version of minecraft"),
+    (Unrecoverable::NewerWorld, "saved with a newer version"),
+    (Unrecoverable::NewerWorld, "from a newer version"),
+    (Unrecoverable::MissingDependency, "missing or unsupported mandatory dependencies"),
+    (Unrecoverable::MissingDependency, "incompatible mods found"),
+    (Unrecoverable::MissingDependency, "unknown/missing dependency"),
+    (Unrecoverable::MissingDependency, "unknown dependency"),
+    (Unrecoverable::JavaTooOld, "unsupportedclassversionerror"),
+    (Unrecoverable::JavaTooOld, "has been compiled by a more recent version of the java runtime"),
+];
+
+/// The first line of the console's last output that says the server cannot
+/// come up again as it is, and why.
+pub fn unrecoverable(lines: &[String]) -> Option<(Unrecoverable, String)> {
+    lines.iter().find_map(|line| {
+        let lower = line.to_lowercase();
+        SIGNATURES
+            .iter()
+            .find(|(_, fragment)| lower.contains(fragment))
+            .map(|(cause, _)| (*cause, line.clone()))
+    })
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Datelike, Duration, Local, Timelike};
+
+/// A standard five-field cron expression: minute, hour, day of month, month,
+/// day of week (0 or 7 = Sunday). Fields accept `*`, `a-b`, `a,b` and `/step`.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    /// Whether day of month / day of week were restricted; when both are, the
+    /// expression fires if either matches, as in classic cron.
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Schedule, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("expected 5 fields in \"{}\"", expression));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // 7 is an alias for Sunday
+        if weekdays[7] {
+            weekdays[0] = true;
+        }
+        Ok(Schedule {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    pub fn matches(&self, time: DateTime<Local>) -> bool {
+        let day = self.days[time.day() as usize];
+        let weekday = self.weekdays[time.weekday().num_days_from_sunday() as usize];
+        let day_matches = match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        };
+        self.minutes[time.minute() as usize]
+            && self.hours[time.hour() as usize]
+            && self.months[time.month() as usize]
+            && day_matches
+    }
+
+    /// Whether the schedule fired in any whole minute after `after` up to and
+    /// including `until`, so a slow loop iteration never skips a run.
+    pub fn fired_between(&self, after: DateTime<Local>, until: DateTime<Local>) -> bool {
+        let mut minute = truncate(after) + Duration::minutes(1);
+        let end = truncate(until);
+        // Don't replay a whole backlog after the machine slept for days
+        let start_limit = end - Duration::hours(24);
+        if minute < start_limit {
+            minute = start_limit;
+        }
+        while minute <= end {
+            if self.matches(minute) {
+                return true;
+            }
+            minute += Duration::minutes(1);
+        }
+        false
+    }
+}
+
+fn truncate(time: DateTime<Local>) -> DateTime<Local> {
+    time.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(time)
+}
+
+/// Parses one field into a lookup table indexed by value.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| format!("bad step in \"{}\"", part))?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(format!("step must be positive in \"{}\"", part));
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, part)?, parse_value(b, part)?)
+        } else {
+            let value = parse_value(range, part)?;
+            // "5/15" means from 5 to the end in steps of 15
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("\"{}\" is outside {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+fn parse_value(value: &str, part: &str) -> Result<u32, String> {
+    value.parse().map_err(|_| format!("bad value in \"{}\"", part))
+}
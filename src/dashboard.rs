@@ -0,0 +1,184 @@
+use std::fs;
+
+use base64::Engine;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::api::{log_stream, server_json};
+use crate::audit;
+use crate::config::{self, DashboardConfig};
+use crate::control::Command;
+use crate::http::{Request, Response};
+use crate::instance::Instances;
+
+const PAGE: &str = include_str!("dashboard.html");
+const SETTINGS_PAGE: &str = include_str!("settings.html");
+
+/// Serves the dashboard page at `/`, the config editor at
+/// `/dashboard/settings`, and their `dashboard/*` endpoints; None for any
+/// other path. Everything except the bare pages with token auth requires the
+/// configured credentials. The state, logs and actions are the first
+/// server's, or with `?server=<name>` that one's; the state lists them all.
+pub fn handle(config: &DashboardConfig, request: &Request, instances: &Instances) -> Option<Response> {
+    let path = request.path.as_str();
+    if path != "/" && !path.starts_with("/dashboard/") {
+        return None;
+    }
+    // With basic auth the browser has to be challenged for the page itself,
+    // and then sends the credentials along with every request on its own
+    match path {
+        "/" if config.username.is_none() => return Some(Response::html(PAGE)),
+        "/dashboard/settings" if config.username.is_none() => return Some(Response::html(SETTINGS_PAGE)),
+        _ => {}
+    }
+    if !authorized(config, request) {
+        warn!("Dashboard: rejected {} {} from {}", request.method, path, request.peer);
+        if request.method != "GET" {
+            audit::record("dashboard", &request.peer.ip().to_string(), &format!("{} {}", request.method, path), "unauthorized");
+        }
+        let response = Response::error(401, "unauthorized");
+        return Some(if config.username.is_some() {
+            response.with_header("WWW-Authenticate", "Basic realm=\"rusty-golem\"")
+        } else {
+            response
+        });
+    }
+
+    // Cached basic-auth credentials go along with a form another site posts
+    if request.method != "GET" && !same_origin(request) {
+        warn!("Dashboard: rejected cross-site {} {} from {}", request.method, path, request.peer);
+        audit::record("dashboard", &request.peer.ip().to_string(), &format!("{} {}", request.method, path), "cross-site");
+        return Some(Response::error(403, "cross-site request"));
+    }
+
+    let server = match instances.find(request.query("server")) {
+        Ok(server) => server,
+        Err(e) => return Some(Response::error(404, &e)),
+    };
+    let command = match (request.method.as_str(), path) {
+        ("GET", "/") => return Some(Response::html(PAGE)),
+        ("GET", "/dashboard/settings") => return Some(Response::html(SETTINGS_PAGE)),
+        ("GET", "/dashboard/config") => {
+            return Some(match fs::read_to_string(config::CONFIG_FILE) {
+                Ok(text) => Response::json(200, &json!({ "text": text })),
+                Err(e) => Response::error(500, &format!("could not read config.toml: {}", e)),
+            })
+        }
+        ("PUT", "/dashboard/config") => return Some(save_config(request, instances)),
+        ("GET", "/dashboard/state") => {
+            let mut state = server_json(&server);
+            state["log"] = json!(server.status().tail(100));
+            state["servers"] = json!(instances.all().iter().map(server_json).collect::<Vec<_>>());
+            return Some(Response::json(200, &state));
+        }
+        ("GET", "/dashboard/logs") => return Some(log_stream(request, &server.feed)),
+        ("POST", "/dashboard/start") => Command::Start,
+        ("POST", "/dashboard/stop") => Command::Stop,
+        ("POST", "/dashboard/backup") => Command::Backup(None),
+        _ => return Some(Response::error(404, "not found")),
+    };
+    let action = match &server.name {
+        Some(name) => format!("{} ({})", path.trim_start_matches("/dashboard/"), name),
+        None => path.trim_start_matches("/dashboard/").to_string(),
+    };
+    info!("Dashboard: {} from {}", action, request.peer);
+    let sent = server.commands.send(command).is_ok();
+    audit::record("dashboard", &request.peer.ip().to_string(), &action, if sent { "queued" } else { "golem shutting down" });
+    if !sent {
+        return Some(Response::error(500, "the golem is shutting down"));
+    }
+    Some(Response::json(202, &json!({ "queued": path.trim_start_matches("/dashboard/") })))
+}
+
+/// `{"text": "<config.toml>"}`: checked as at startup, then written and
+/// handed to every server's main loop. With `?check` it is only checked.
+fn save_config(request: &Request, instances: &Instances) -> Response {
+    let text = match request.json().map(|body| body["text"].as_str().map(str::to_string)) {
+        Ok(Some(text)) => text,
+        _ => return Response::error(400, "expected {\"text\": \"<config.toml>\"}"),
+    };
+    let check = request.query("check").is_some();
+    let who = request.peer.ip().to_string();
+    let parsed = config::parse_config(&text).and_then(|new_config| keeps_servers(instances, &new_config).map(|()| new_config));
+    let new_config = match parsed {
+        Ok(new_config) => new_config,
+        Err(e) => {
+            if !check {
+                audit::record("dashboard", &who, "edit config.toml", "invalid");
+            }
+            return Response::error(400, &e);
+        }
+    };
+    if check {
+        return Response::json(200, &json!({ "valid": true }));
+    }
+    if let Err(e) = config::save_config(&text) {
+        audit::record("dashboard", &who, "edit config.toml", "write failed");
+        return Response::error(500, &format!("could not write config.toml: {}", e));
+    }
+    info!("Dashboard: config.toml edited from {}", request.peer);
+    audit::record("dashboard", &who, "edit config.toml", "saved");
+    for added in new_config.servers.iter().filter(|entry| instances.get(&entry.name).is_none()) {
+        info!("Config: {} is supervised once the golem is restarted.", added.name);
+    }
+    for server in instances.all() {
+        if server.commands.send(Command::ApplyConfig(Box::new(new_config.clone()))).is_err() {
+            return Response::error(500, "the golem is shutting down");
+        }
+    }
+    Response::json(200, &json!({ "saved": true }))
+}
+
+/// Servers are supervised from startup, so an edit may add `[[servers]]`
+/// entries (for the next start) but not take away one that runs, by
+/// removing or renaming it, nor turn one server into several or back.
+fn keeps_servers(instances: &Instances, new_config: &config::Config) -> Result<(), String> {
+    let names = instances.names();
+    if names.is_empty() != new_config.servers.is_empty() {
+        return Err("switching between one server and [[servers]] needs the golem to be restarted".to_string());
+    }
+    match names.iter().find(|name| !new_config.servers.iter().any(|entry| &entry.name == *name)) {
+        Some(name) => Err(format!("[[servers]] no longer has {}, which the golem runs; restart the golem to remove or rename it", name)),
+        None => Ok(()),
+    }
+}
+
+/// Whether a request came from the dashboard's own pages: browsers say where
+/// a request comes from in `Origin` (or at least `Referer`), and it must be
+/// this host. Requests without either are from scripts, not browsers.
+fn same_origin(request: &Request) -> bool {
+    let Some(from) = request.header("Origin").or_else(|| request.header("Referer")) else {
+        return true;
+    };
+    let from_host = from.split_once("://").map(|(_, rest)| rest.split('/').next().unwrap_or_default());
+    from_host.is_some_and(|from_host| request.header("Host").is_some_and(|host| host.eq_ignore_ascii_case(from_host)))
+}
+
+fn authorized(config: &DashboardConfig, request: &Request) -> bool {
+    // The page's WebSocket cannot send headers, so it passes the token along
+    if let (Some(token), Some(given)) = (&config.token, request.query("token")) {
+        if constant_time_eq(token.as_bytes(), given.as_bytes()) {
+            return true;
+        }
+    }
+    let Some(header) = request.header("Authorization") else {
+        return false;
+    };
+    if let (Some(token), Some(given)) = (&config.token, header.strip_prefix("Bearer ")) {
+        if constant_time_eq(token.as_bytes(), given.trim().as_bytes()) {
+            return true;
+        }
+    }
+    if let (Some(username), Some(password), Some(given)) = (&config.username, &config.password, header.strip_prefix("Basic ")) {
+        let expected = format!("{}:{}", username, password);
+        if let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(given.trim()) {
+            return constant_time_eq(expected.as_bytes(), &decoded);
+        }
+    }
+    false
+}
+
+/// Compares secrets without leaking how many leading bytes matched.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+
+use crate::history;
+use crate::messages::Messages;
+use crate::metrics::format_bytes;
+
+/// Everything that happened during one running window, summarised when it closes.
+#[derive(Default)]
+pub struct DailyStats {
+    uptime: Duration,
+    starts: u32,
+    crashes: u32,
+    /// Who is on, with when they joined.
+    online: HashMap<String, DateTime<Local>>,
+    unique_players: HashSet<String>,
+    peak_players: usize,
+    backups: Vec<(String, u64)>,
+}
+
+impl DailyStats {
+    pub fn record_start(&mut self) {
+        self.starts += 1;
+    }
+
+    pub fn record_crash(&mut self) {
+        self.crashes += 1;
+        history::append("crashes", Local::now(), "1");
+    }
+
+    /// Called whenever a server process ends, for whatever reason.
+    pub fn record_stopped(&mut self, ran_for: Duration) {
+        self.uptime += ran_for;
+        let now = Local::now();
+        for (name, joined) in std::mem::take(&mut self.online) {
+            record_session(&name, joined, now);
+        }
+    }
+
+    pub fn record_backup(&mut self, file_name: &str, size_bytes: u64) {
+        self.backups.push((file_name.to_string(), size_bytes));
+    }
+
+    pub fn player_joined(&mut self, name: &str) {
+        self.online.insert(name.to_string(), Local::now());
+        self.unique_players.insert(name.to_string());
+        self.peak_players = self.peak_players.max(self.online.len());
+    }
+
+    pub fn player_left(&mut self, name: &str) {
+        if let Some(joined) = self.online.remove(name) {
+            record_session(name, joined, Local::now());
+        }
+    }
+
+    pub fn online_count(&self) -> usize {
+        self.online.len()
+    }
+
+    pub fn online_players(&self) -> Vec<String> {
+        let mut players: Vec<String> = self.online.keys().cloned().collect();
+        players.sort_by_key(|name| name.to_lowercase());
+        players
+    }
+
+    /// Embed fields for the digest notification.
+    pub fn fields(&self, messages: &Messages) -> Vec<(String, String)> {
+        let restarts = self.starts.saturating_sub(1);
+        let backups = if self.backups.is_empty() {
+            "-".to_string()
+        } else {
+            self.backups
+                .iter()
+                .map(|(name, size)| format!("{} ({})", name, format_bytes(*size)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        vec![
+            (messages.get("digest_uptime", &[]), format_duration(self.uptime)),
+            (messages.get("digest_restarts", &[]), restarts.to_string()),
+            (messages.get("digest_crashes", &[]), self.crashes.to_string()),
+            (messages.get("digest_peak_players", &[]), self.peak_players.to_string()),
+            (messages.get("digest_unique_players", &[]), self.unique_players.len().to_string()),
+            (messages.get("digest_backups", &[]), backups),
+        ]
+    }
+}
+
+/// One play session in `stats/sessions.csv`, as `<name>,<seconds>`, for
+/// the weekly report.
+fn record_session(name: &str, joined: DateTime<Local>, left: DateTime<Local>) {
+    let seconds = (left - joined).num_seconds().max(0);
+    history::append("sessions", left, &format!("{},{}", name, seconds));
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let minutes = d.as_secs() / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
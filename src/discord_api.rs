@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::Method;
+
+const API_BASE: &str = "https://discord.com/api/v10";
+
+/// Minimal Discord REST client authenticated with a bot token.
+#[derive(Clone)]
+pub struct DiscordApi {
+    client: Client,
+    token: String,
+}
+
+impl DiscordApi {
+    pub fn new(token: &str) -> Self {
+        DiscordApi {
+            client: Client::builder()
+                .timeout(Duration::from_secs(15))
+                .build()
+                .expect("Failed to build HTTP client"),
+            token: token.to_string(),
+        }
+    }
+
+    /// `path` is relative to the API root, e.g. `/channels/123/messages`.
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", API_BASE, path))
+            .header("Authorization", format!("Bot {}", self.token))
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use reqwest::Method;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::config::ChatBridgeConfig;
+use crate::control::Command;
+use crate::discord_api::DiscordApi;
+use crate::feed::LogFeed;
+use crate::server_log::{self, LogEvent};
+
+/// Longest Discord message passed into the game.
+const MAX_INGAME_CHARS: usize = 256;
+
+/// Plugin-free chat bridge: players' chat goes to the Discord channel, and
+/// the channel's messages come into the game through `tellraw`.
+pub struct ChatBridge {
+    config: ChatBridgeConfig,
+    commands: Sender<Command>,
+}
+
+impl ChatBridge {
+    /// Starts relaying game chat from the feed to the channel.
+    pub fn spawn(api: DiscordApi, config: ChatBridgeConfig, feed: &LogFeed, commands: Sender<Command>) -> Self {
+        let lines = feed.subscribe();
+        let path = format!("/channels/{}/messages", config.channel_id);
+        thread::spawn(move || {
+            for item in lines {
+                let Some(Some(LogEvent::Chat { player, message })) = item["line"].as_str().map(server_log::parse_line) else {
+                    continue;
+                };
+                // Nobody gets pinged by what is typed in game
+                let body = json!({
+                    "content": format!("**{}**: {}", player, message),
+                    "allowed_mentions": { "parse": [] },
+                });
+                match api.request(Method::POST, &path).json(&body).send() {
+                    Ok(response) if response.status().is_success() => {}
+                    Ok(response) => warn!("Chat bridge: posting to Discord failed ({}).", response.status()),
+                    Err(e) => warn!("Chat bridge: posting to Discord failed: {}", e),
+                }
+            }
+        });
+        ChatBridge { config, commands }
+    }
+
+    /// A MESSAGE_CREATE from the gateway; only people's messages in the
+    /// bridged channel are passed on.
+    pub fn incoming(&self, message: &Value) {
+        if message["channel_id"].as_str() != Some(self.config.channel_id.as_str()) || message["author"]["bot"] == true {
+            return;
+        }
+        let author = message["member"]["nick"]
+            .as_str()
+            .or(message["author"]["global_name"].as_str())
+            .or(message["author"]["username"].as_str())
+            .unwrap_or("?");
+        let mut text: String = message["content"].as_str().unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.chars().count() > MAX_INGAME_CHARS {
+            text = text.chars().take(MAX_INGAME_CHARS).collect::<String>() + "…";
+        }
+        if text.is_empty() {
+            if message["attachments"].as_array().is_none_or(|a| a.is_empty()) {
+                return;
+            }
+            text = "[attachment]".to_string();
+        }
+        let components = json!([
+            "",
+            { "text": "[Discord] ", "color": "blue" },
+            { "text": format!("<{}> ", author), "color": "aqua" },
+            { "text": text }
+        ]);
+        let _ = self.commands.send(Command::Console(format!("tellraw @a {}", components)));
+    }
+}
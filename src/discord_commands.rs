@@ -0,0 +1,792 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::blocking::RequestBuilder;
+use reqwest::Method;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::audit;
+use crate::backup;
+use crate::config::{BotCommandsConfig, DiscordBotConfig, ExtendVoteConfig, ServerEntry};
+use crate::control::Command;
+use crate::discord_api::DiscordApi;
+use crate::discord_chat::ChatBridge;
+use crate::discord_gateway;
+use crate::instance::{Handle, Instances};
+use crate::messages::Messages;
+use crate::schedule;
+
+/// Interaction types.
+const APPLICATION_COMMAND: u64 = 2;
+const MESSAGE_COMPONENT: u64 = 3;
+const AUTOCOMPLETE: u64 = 4;
+/// Interaction response type: reply with a message right away.
+const CHANNEL_MESSAGE: u8 = 4;
+/// Interaction response type: "thinking…" now, the message in a later edit.
+const DEFERRED_CHANNEL_MESSAGE: u8 = 5;
+/// Interaction response type: edit the message the button was on.
+const UPDATE_MESSAGE: u8 = 7;
+const AUTOCOMPLETE_RESULT: u8 = 8;
+/// Message flag: only the user who ran the command sees the reply.
+const EPHEMERAL: u64 = 1 << 6;
+/// Option types: `/schedule set` and `show`, `/cmd command`, `/extend minutes`.
+const SUB_COMMAND: u8 = 1;
+const STRING: u8 = 3;
+const INTEGER: u8 = 4;
+/// Discord's message length limit, less room for the code fence.
+const MAX_REPLY_CHARS: usize = 1900;
+
+/// A destructive command waiting for its Confirm button. The question is
+/// ephemeral, so only whoever ran the command can press it.
+struct Pending {
+    command: Command,
+    /// The server it is for.
+    commands: Sender<Command>,
+    /// The slash command, for the audit log.
+    action: String,
+    expires: Instant,
+    /// The reply once confirmed.
+    done: String,
+}
+
+/// Users who voted to extend, and the stop they voted against; a new stop
+/// time starts a new vote.
+#[derive(Default)]
+struct Vote {
+    voters: Vec<String>,
+    stop: Option<DateTime<Local>>,
+}
+
+struct Bot {
+    api: DiscordApi,
+    settings: BotCommandsConfig,
+    /// Offered as every command's `server` option; empty with a single server.
+    servers: Vec<String>,
+    aliases: BTreeMap<String, Vec<String>>,
+    messages: Messages,
+    instances: Instances,
+    /// By the id of the interaction that asked.
+    pending: HashMap<String, Pending>,
+    /// By server.
+    votes: HashMap<Option<String>, Vote>,
+}
+
+/// The `custom_id` prefix of the buttons `ask_to_start` posts.
+const CLEAN_EXIT: &str = "clean_exit";
+
+/// Posts the question whether to start the server again after it stopped
+/// cleanly, with buttons the bot answers; in the background, as the main
+/// loop calls it. `server` is the one in `[[servers]]` that stopped, whose
+/// name the buttons carry.
+pub fn ask_to_start(config: &DiscordBotConfig, messages: &Messages, server: Option<&ServerEntry>) {
+    let api = DiscordApi::new(&config.token);
+    let path = format!("/channels/{}/messages", config.channel_id);
+    let question = messages.get("bot_clean_exit_question", &[]);
+    let (content, suffix) = match server {
+        Some(entry) => (format!("[{}] {}", entry.display_name(), question), format!(":{}", entry.name)),
+        None => (question, String::new()),
+    };
+    let body = json!({
+        "content": content,
+        "components": [{
+            "type": 1,
+            "components": [
+                { "type": 2, "style": 3, "label": messages.get("bot_clean_exit_start", &[]), "custom_id": format!("{}:start{}", CLEAN_EXIT, suffix) },
+                { "type": 2, "style": 2, "label": messages.get("bot_clean_exit_leave", &[]), "custom_id": format!("{}:leave{}", CLEAN_EXIT, suffix) }
+            ]
+        }]
+    });
+    thread::spawn(move || match api.request(Method::POST, &path).json(&body).send() {
+        Ok(response) if response.status().is_success() => {}
+        Ok(response) => warn!("Discord: could not ask whether to start the server: HTTP {}", response.status()),
+        Err(e) => warn!("Discord: could not ask whether to start the server: {}", e),
+    });
+}
+
+/// Gateway intents the chat bridge needs; the second is privileged and has
+/// to be switched on in the Discord developer portal.
+const GUILD_MESSAGES: u64 = 1 << 9;
+const MESSAGE_CONTENT: u64 = 1 << 15;
+
+/// Connects the bot to the gateway if it has slash commands or a chat bridge
+/// configured. The commands are `/status`, `/start`, `/stop`, `/restart`,
+/// `/extend`, `/restore`, `/schedule`, `/watchdog reset`, `/logs`, `/cmd` and `/alias`, handing control commands to
+/// the main loop; `/stop`, `/restart` and `/restore` only go ahead once confirmed with
+/// a button. With `[[servers]]` (named in `servers`) each command takes a
+/// `server` option, without which `/status` shows every server and the
+/// others act on the first; the chat bridge links the first.
+pub fn spawn(config: &DiscordBotConfig, servers: Vec<String>, instances: Instances) {
+    let token = config.token.clone();
+    let api = DiscordApi::new(&config.token);
+    let primary = instances.primary();
+    let bridge = config
+        .chat_bridge
+        .clone()
+        .map(|bridge| ChatBridge::spawn(api.clone(), bridge, &primary.feed, primary.commands.clone()));
+    let mut bot = config.commands.clone().map(|settings| Bot {
+        api,
+        settings,
+        servers,
+        aliases: primary.interpreter.aliases.clone(),
+        messages: primary.interpreter.messages.clone(),
+        instances,
+        pending: HashMap::new(),
+        votes: HashMap::new(),
+    });
+    if bot.is_none() && bridge.is_none() {
+        return;
+    }
+    // Interactions arrive without any gateway intents
+    let intents = if bridge.is_some() { GUILD_MESSAGES | MESSAGE_CONTENT } else { 0 };
+    thread::spawn(move || {
+        let mut application_id = None;
+        discord_gateway::run(&token, intents, &mut |event, data| {
+            if let (Some(bridge), "MESSAGE_CREATE") = (&bridge, event) {
+                bridge.incoming(data);
+            }
+            let Some(bot) = bot.as_mut() else {
+                return;
+            };
+            match (event, data["type"].as_u64()) {
+                ("READY", _) => {
+                    let Some(id) = data["application"]["id"].as_str() else {
+                        return;
+                    };
+                    if application_id.as_deref() != Some(id) {
+                        bot.register(id);
+                        application_id = Some(id.to_string());
+                    }
+                }
+                ("INTERACTION_CREATE", Some(APPLICATION_COMMAND)) if data["data"]["name"] == "cmd" => {
+                    bot.console_command(data);
+                }
+                ("INTERACTION_CREATE", Some(APPLICATION_COMMAND)) if data["data"]["name"] == "logs" => bot.logs(data),
+                ("INTERACTION_CREATE", Some(APPLICATION_COMMAND)) => {
+                    let reply = bot.handle(data);
+                    respond(&bot.api, data, CHANNEL_MESSAGE, reply);
+                }
+                ("INTERACTION_CREATE", Some(MESSAGE_COMPONENT)) => {
+                    let reply = bot.button(data);
+                    respond(&bot.api, data, UPDATE_MESSAGE, reply);
+                }
+                ("INTERACTION_CREATE", Some(AUTOCOMPLETE)) => {
+                    let reply = bot.backup_choices(data);
+                    respond(&bot.api, data, AUTOCOMPLETE_RESULT, reply);
+                }
+                _ => {}
+            }
+        });
+    });
+}
+
+impl Bot {
+    fn register(&self, application_id: &str) {
+        let path = match &self.settings.guild_id {
+            Some(guild) => format!("/applications/{}/guilds/{}/commands", application_id, guild),
+            None => format!("/applications/{}/commands", application_id),
+        };
+        let mut definitions = json!([
+            { "name": "status", "description": "Show whether the Minecraft server is up" },
+            { "name": "start", "description": "Start the Minecraft server now" },
+            { "name": "stop", "description": "Stop the Minecraft server until it is next scheduled" },
+            { "name": "restart", "description": "Restart the Minecraft server" },
+            {
+                "name": "extend",
+                "description": "Keep the Minecraft server up longer today",
+                "options": [{
+                    "type": INTEGER,
+                    "name": "minutes",
+                    "description": "How many minutes to add (admins only; a vote adds a set amount)",
+                    "required": false,
+                    "min_value": 1,
+                    "max_value": 720
+                }]
+            },
+            {
+                "name": "restore",
+                "description": "Stop the Minecraft server and restore a backup",
+                "options": [{
+                    "type": STRING,
+                    "name": "backup",
+                    "description": "Which backup",
+                    "required": true,
+                    "autocomplete": true
+                }]
+            },
+            {
+                "name": "schedule",
+                "description": "Show or change the weekly schedule",
+                "options": [
+                    { "type": SUB_COMMAND, "name": "show", "description": "Show each day's hours" },
+                    {
+                        "type": SUB_COMMAND,
+                        "name": "set",
+                        "description": "Change one day's hours",
+                        "options": [
+                            {
+                                "type": STRING,
+                                "name": "day",
+                                "description": "Which day",
+                                "required": true,
+                                "choices": schedule::WEEK
+                                    .iter()
+                                    .map(|day| json!({ "name": schedule::day_name(*day), "value": schedule::day_name(*day) }))
+                                    .collect::<Vec<_>>()
+                            },
+                            {
+                                "type": STRING,
+                                "name": "hours",
+                                "description": "e.g. 18:00-24:00, closed, or default for the daily hours",
+                                "required": true
+                            }
+                        ]
+                    }
+                ]
+            },
+            {
+                "name": "watchdog",
+                "description": "Control the crash watchdog",
+                "options": [{ "type": SUB_COMMAND, "name": "reset", "description": "Forget the crashes counted so far and start the server now" }]
+            },
+            {
+                "name": "logs",
+                "description": "Show the last lines of the server console",
+                "options": [{
+                    "type": INTEGER,
+                    "name": "lines",
+                    "description": "How many lines (default 50)",
+                    "required": false,
+                    "min_value": 1,
+                    "max_value": 500
+                }]
+            },
+            {
+                "name": "cmd",
+                "description": "Run a command on the Minecraft server console",
+                "options": [{
+                    "type": STRING,
+                    "name": "command",
+                    "description": "The command, e.g. list or say hello",
+                    "required": true
+                }]
+            }
+        ]);
+        // Discord allows at most 25 choices
+        let choices = |names: Vec<&String>| -> Vec<Value> { names.into_iter().take(25).map(|name| json!({ "name": name, "value": name })).collect() };
+        if !self.aliases.is_empty() {
+            definitions.as_array_mut().expect("an array").push(json!({
+                "name": "alias",
+                "description": "Run one of the command sequences from the golem's config",
+                "options": [{
+                    "type": STRING,
+                    "name": "name",
+                    "description": "Which alias",
+                    "required": true,
+                    "choices": choices(self.aliases.keys().collect())
+                }]
+            }));
+        }
+        if !self.servers.is_empty() {
+            let server = json!({
+                "type": STRING,
+                "name": "server",
+                "description": "Which server (without one: every server for /status, the first for the rest)",
+                "required": false,
+                "choices": choices(self.servers.iter().collect())
+            });
+            for definition in definitions.as_array_mut().expect("an array") {
+                let options = &mut definition["options"];
+                // Subcommands carry options of their own, and then only they may
+                let subcommands = options.as_array().is_some_and(|o| o.iter().any(|o| o["type"] == SUB_COMMAND));
+                let targets: Vec<&mut Value> = if subcommands {
+                    options.as_array_mut().expect("an array").iter_mut().map(|sub| &mut sub["options"]).collect()
+                } else {
+                    vec![options]
+                };
+                for options in targets {
+                    match options.as_array_mut() {
+                        Some(options) => options.push(server.clone()),
+                        None => *options = json!([server.clone()]),
+                    }
+                }
+            }
+        }
+        // Bulk overwrite, so commands removed in a later version disappear too
+        match self.api.request(Method::PUT, &path).json(&definitions).send() {
+            Ok(response) if response.status().is_success() => info!("Discord: slash commands registered."),
+            Ok(response) => warn!("Discord: registering slash commands failed ({}).", response.status()),
+            Err(e) => warn!("Discord: registering slash commands failed: {}", e),
+        }
+    }
+
+    /// The server the command names in its `server` option, or the first.
+    fn target(&self, interaction: &Value) -> Result<Handle, Value> {
+        let name = option(interaction, "server").and_then(Value::as_str);
+        self.instances.find(name).map_err(ephemeral)
+    }
+
+    /// Works out the reply's `data` for one slash command.
+    fn handle(&mut self, interaction: &Value) -> Value {
+        let name = interaction["data"]["name"].as_str().unwrap_or_default();
+        if name == "status" {
+            let handles = match option(interaction, "server").and_then(Value::as_str) {
+                None => self.instances.all(),
+                Some(server) => match self.instances.find(Some(server)) {
+                    Ok(handle) => vec![handle],
+                    Err(e) => return ephemeral(e),
+                },
+            };
+            let embeds: Vec<Value> = handles.iter().map(|handle| self.status_embed(handle)).collect();
+            return json!({ "embeds": embeds });
+        }
+        let handle = match self.target(interaction) {
+            Ok(handle) => handle,
+            Err(reply) => return reply,
+        };
+        let subcommand = &interaction["data"]["options"][0];
+        if name == "schedule" && subcommand["name"] == "show" {
+            let lines = schedule::week_lines(&handle.status().week).join("\n");
+            let title = handle.titled(self.messages.get("schedule_title", &[]));
+            return json!({ "embeds": [{ "title": title, "description": format!("```\n{}\n```", lines) }] });
+        }
+
+        if name == "alias" {
+            return self.alias(interaction, &handle);
+        }
+
+        let admin = allowed(&self.settings.admin_role_ids, &self.settings.admin_user_ids, interaction);
+        if let (false, "extend", Some(vote)) = (admin, name, self.settings.extend_vote.clone()) {
+            return self.vote_extend(interaction, &vote, &handle);
+        }
+        if !admin {
+            audit(interaction, &format!("/{}", name), "refused: not an admin");
+            return ephemeral(self.messages.get("bot_not_allowed", &[]));
+        }
+        let messages = self.messages.clone();
+        let players = handle.status().players.to_string();
+        let (command, reply) = match name {
+            "start" => (Command::Start, messages.get("bot_starting", &[])),
+            "watchdog" => (Command::WatchdogReset, messages.get("bot_watchdog_reset", &[])),
+            "extend" => {
+                let minutes = option(interaction, "minutes").and_then(Value::as_u64).unwrap_or(30);
+                (Command::Extend(minutes), messages.get("bot_extending", &[("minutes", minutes.to_string())]))
+            }
+            "stop" => {
+                let question = messages.get("bot_confirm_stop", &[("players", players)]);
+                return self.ask(interaction, &handle, Command::Stop, question, messages.get("bot_stopping", &[]));
+            }
+            "restart" => {
+                let question = messages.get("bot_confirm_restart", &[("players", players)]);
+                return self.ask(interaction, &handle, Command::Restart, question, messages.get("bot_restarting", &[]));
+            }
+            "schedule" => {
+                let option = |name: &str| option(interaction, name).and_then(Value::as_str);
+                match schedule::parse_change(option("day").unwrap_or_default(), option("hours").unwrap_or_default()) {
+                    Ok((day, hours)) => {
+                        let hours_text = hours.map_or("default".to_string(), |h| h.to_string());
+                        let reply = messages.get("bot_schedule_setting", &[("day", schedule::day_name(day).to_string()), ("hours", hours_text)]);
+                        (Command::SetHours(vec![(day, hours)]), reply)
+                    }
+                    Err(e) => return ephemeral(e),
+                }
+            }
+            "restore" => {
+                let name = option(interaction, "backup").and_then(Value::as_str).unwrap_or_default().to_string();
+                if !available_backups(&handle).contains(&name) {
+                    return ephemeral(messages.get("bot_no_backup", &[("name", name)]));
+                }
+                let question = messages.get("bot_confirm_restore", &[("name", name.clone()), ("players", players)]);
+                let done = messages.get("bot_restoring", &[("name", name.clone())]);
+                return self.ask(interaction, &handle, Command::Restore(name), question, done);
+            }
+            _ => return ephemeral(format!("Unknown command: {}", name)),
+        };
+        info!("Discord: /{} requested by {}", name, user_name(interaction));
+        let sent = handle.commands.send(command).is_ok();
+        audit(interaction, &action(interaction, &handle), if sent { "queued" } else { "golem shutting down" });
+        if !sent {
+            return ephemeral("The golem is shutting down.".to_string());
+        }
+        ephemeral(handle.titled(reply))
+    }
+
+    fn status_embed(&self, handle: &Handle) -> Value {
+        let fields: Vec<Value> = handle
+            .status()
+            .fields(&self.messages)
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": value, "inline": true }))
+            .collect();
+        json!({ "title": handle.titled(self.messages.get("status_title", &[])), "fields": fields })
+    }
+
+    /// `/alias`: the same people as `/cmd` may run it, but the blocklist does
+    /// not apply, the sequence being the config owner's own.
+    fn alias(&self, interaction: &Value, handle: &Handle) -> Value {
+        let name = option(interaction, "name").and_then(Value::as_str).unwrap_or_default().to_string();
+        let action = with_server(&format!("/alias {}", name), handle);
+        if !allowed(&self.settings.console_role_ids, &self.settings.console_user_ids, interaction) {
+            audit(interaction, &action, "refused: not allowed");
+            return ephemeral(self.messages.get("bot_not_allowed", &[]));
+        }
+        let Some(lines) = handle.interpreter.aliases.get(&name) else {
+            return ephemeral(self.messages.get("bot_no_alias", &[("name", name)]));
+        };
+        if !handle.status().online {
+            return ephemeral(self.messages.get("bot_command_offline", &[]));
+        }
+        info!("Discord: /alias {} requested by {}", name, user_name(interaction));
+        let sent = handle.commands.send(Command::ConsoleLines(lines.clone())).is_ok();
+        audit(interaction, &action, if sent { "queued" } else { "golem shutting down" });
+        if !sent {
+            return ephemeral("The golem is shutting down.".to_string());
+        }
+        ephemeral(self.messages.get("bot_alias_running", &[("name", name), ("commands", lines.join("; "))]))
+    }
+
+    /// `/extend` from someone who is not an admin: one vote, and once enough
+    /// people have voted within the last minutes before the stop, the
+    /// extension itself. Progress is shown in the channel and in game.
+    fn vote_extend(&mut self, interaction: &Value, vote: &ExtendVoteConfig, handle: &Handle) -> Value {
+        let messages = self.messages.clone();
+        let status = handle.status();
+        let Some(stop) = status.closes_at.filter(|_| status.online) else {
+            return ephemeral(messages.get("bot_vote_no_stop", &[]));
+        };
+        if stop - Local::now() > chrono::Duration::minutes(vote.window_minutes) {
+            return ephemeral(messages.get("bot_vote_too_early", &[("minutes", vote.window_minutes.to_string())]));
+        }
+        let votes = self.votes.entry(handle.name.clone()).or_default();
+        if votes.stop != Some(stop) {
+            votes.voters.clear();
+            votes.stop = Some(stop);
+        }
+        let user = user_id(interaction).to_string();
+        let counts = |votes: usize| [("votes", votes.to_string()), ("needed", vote.votes.to_string())];
+        if votes.voters.contains(&user) {
+            return ephemeral(messages.get("bot_vote_already", &counts(votes.voters.len())));
+        }
+        votes.voters.push(user);
+        let count = votes.voters.len();
+        info!("Discord: extend vote {}/{} by {}", count, vote.votes, user_name(interaction));
+        audit(interaction, &action(interaction, handle), &format!("vote {} of {}", count, vote.votes));
+        let reply = if count >= vote.votes {
+            votes.voters.clear();
+            let _ = handle.commands.send(Command::Extend(vote.minutes));
+            messages.get("bot_vote_passed", &[("minutes", vote.minutes.to_string())])
+        } else {
+            let mut placeholders = counts(count).to_vec();
+            placeholders.push(("player", user_name(interaction)));
+            let _ = handle.commands.send(Command::Console(format!("say {}", messages.get("ingame_extend_vote", &placeholders))));
+            messages.get("bot_vote_counted", &placeholders)
+        };
+        json!({ "content": handle.titled(reply) })
+    }
+
+    /// Holds `command` for `handle`'s server back and replies with Confirm
+    /// and Cancel buttons.
+    fn ask(&mut self, interaction: &Value, handle: &Handle, command: Command, question: String, done: String) -> Value {
+        let id = interaction["id"].as_str().unwrap_or_default().to_string();
+        let seconds = self.settings.confirm_timeout_secs;
+        let now = Instant::now();
+        self.pending.retain(|_, pending| pending.expires > now);
+        self.pending.insert(
+            id.clone(),
+            Pending {
+                command,
+                commands: handle.commands.clone(),
+                action: action(interaction, handle),
+                expires: now + Duration::from_secs(seconds),
+                done: handle.titled(done),
+            },
+        );
+        audit(interaction, &self.pending[&id].action, "awaiting confirmation");
+        let hint = self.messages.get("bot_confirm_within", &[("seconds", seconds.to_string())]);
+        let confirm = self.messages.get("bot_confirm", &[]);
+        let cancel = self.messages.get("bot_cancel", &[]);
+        json!({
+            "content": format!("{}\n{}", handle.titled(question), hint),
+            "flags": EPHEMERAL,
+            "components": [{
+                "type": 1,
+                "components": [
+                    { "type": 2, "style": 4, "label": confirm, "custom_id": format!("confirm:{}", id) },
+                    { "type": 2, "style": 2, "label": cancel, "custom_id": format!("cancel:{}", id) }
+                ]
+            }]
+        })
+    }
+
+    /// A Confirm or Cancel click; the answer replaces the question and its buttons.
+    fn button(&mut self, interaction: &Value) -> Value {
+        let custom_id = interaction["data"]["custom_id"].as_str().unwrap_or_default();
+        let (action, id) = custom_id.split_once(':').unwrap_or_default();
+        if action == CLEAN_EXIT {
+            return self.clean_exit(interaction, id);
+        }
+        let content = match self.pending.remove(id) {
+            Some(pending) if pending.expires > Instant::now() && action == "confirm" => {
+                info!("Discord: confirmed by {}: {}", user_name(interaction), pending.done);
+                let sent = pending.commands.send(pending.command).is_ok();
+                audit(interaction, &pending.action, if sent { "confirmed" } else { "golem shutting down" });
+                if sent {
+                    pending.done
+                } else {
+                    "The golem is shutting down.".to_string()
+                }
+            }
+            Some(pending) if pending.expires > Instant::now() => {
+                audit(interaction, &pending.action, "cancelled");
+                self.messages.get("bot_cancelled", &[])
+            }
+            Some(pending) => {
+                audit(interaction, &pending.action, "confirmation expired");
+                self.messages.get("bot_confirm_expired", &[])
+            }
+            None => self.messages.get("bot_confirm_expired", &[]),
+        };
+        json!({ "content": content, "components": [] })
+    }
+
+    /// An answer to the question `ask_to_start` posted, `start` or `leave`
+    /// and with several servers `:<name>`. Whoever answers first settles it;
+    /// the buttons go.
+    fn clean_exit(&mut self, interaction: &Value, answer: &str) -> Value {
+        let messages = &self.messages;
+        if !allowed(&self.settings.admin_role_ids, &self.settings.admin_user_ids, interaction) {
+            return ephemeral(messages.get("bot_not_allowed", &[]));
+        }
+        let (answer, server) = answer.split_once(':').map_or((answer, None), |(answer, server)| (answer, Some(server)));
+        let handle = match self.instances.find(server) {
+            Ok(handle) => handle,
+            Err(e) => return ephemeral(e),
+        };
+        let user = user_name(interaction);
+        let content = if answer == "start" {
+            let sent = handle.commands.send(Command::Start).is_ok();
+            audit(interaction, &with_server("start after a clean exit", &handle), if sent { "sent" } else { "golem shutting down" });
+            messages.get("bot_clean_exit_started", &[("user", user)])
+        } else {
+            audit(interaction, &with_server("leave stopped after a clean exit", &handle), "ok");
+            messages.get("bot_clean_exit_left", &[("user", user)])
+        };
+        json!({ "content": handle.titled(content), "components": [] })
+    }
+
+    /// Suggestions for `/restore backup` as it is typed, newest first, from
+    /// the server chosen so far.
+    fn backup_choices(&self, interaction: &Value) -> Value {
+        let typed = option(interaction, "backup").and_then(Value::as_str).unwrap_or_default().to_lowercase();
+        let available = self.target(interaction).map(|handle| available_backups(&handle)).unwrap_or_default();
+        let choices: Vec<Value> = available
+            .into_iter()
+            .rev()
+            // Discord shows at most 25, with names of up to 100 characters
+            .filter(|name| name.len() <= 100 && name.to_lowercase().contains(&typed))
+            .take(25)
+            .map(|name| json!({ "name": name, "value": name }))
+            .collect();
+        json!({ "choices": choices })
+    }
+
+    /// `/logs`: the console's last lines, as a code block when they fit in a
+    /// message and as a file when they do not. Admins only, as the log shows
+    /// players' addresses.
+    fn logs(&self, interaction: &Value) {
+        let (api, messages) = (&self.api, &self.messages);
+        if !allowed(&self.settings.admin_role_ids, &self.settings.admin_user_ids, interaction) {
+            respond(api, interaction, CHANNEL_MESSAGE, ephemeral(messages.get("bot_not_allowed", &[])));
+            return;
+        }
+        let handle = match self.target(interaction) {
+            Ok(handle) => handle,
+            Err(reply) => return respond(api, interaction, CHANNEL_MESSAGE, reply),
+        };
+        let lines = option(interaction, "lines").and_then(Value::as_u64).unwrap_or(50) as usize;
+        info!("Discord: /logs {} requested by {}", lines, user_name(interaction));
+        let tail = handle.status().tail(lines).join("\n");
+        if tail.is_empty() {
+            respond(api, interaction, CHANNEL_MESSAGE, ephemeral(messages.get("bot_logs_empty", &[])));
+        } else if tail.len() <= MAX_REPLY_CHARS {
+            respond(api, interaction, CHANNEL_MESSAGE, ephemeral(format!("```\n{}\n```", tail)));
+        } else {
+            respond_with_file(api, interaction, ephemeral(String::new()), "server-log.txt", tail);
+        }
+    }
+
+    /// `/cmd`: the server may take a moment to answer and the main loop may be
+    /// busy, so the reply is deferred and filled in from a thread of its own,
+    /// leaving the gateway free to keep up its heartbeat.
+    fn console_command(&self, interaction: &Value) {
+        let (api, settings, messages) = (&self.api, &self.settings, &self.messages);
+        let handle = match self.target(interaction) {
+            Ok(handle) => handle,
+            Err(reply) => return respond(api, interaction, CHANNEL_MESSAGE, reply),
+        };
+        let line = option(interaction, "command").and_then(Value::as_str).unwrap_or_default();
+        let line = line.trim().trim_start_matches('/').to_string();
+        let action = with_server(&format!("/cmd {}", line), &handle);
+        if !allowed(&settings.console_role_ids, &settings.console_user_ids, interaction) {
+            audit(interaction, &action, "refused: not allowed");
+            respond(api, interaction, CHANNEL_MESSAGE, ephemeral(messages.get("bot_not_allowed", &[])));
+            return;
+        }
+        let blocked = if line.is_empty() { Some(String::new()) } else { blocked_verb(&line, &settings.console_blocklist) };
+        if let Some(verb) = blocked {
+            warn!("Discord: refused /cmd {} from {}", line, user_name(interaction));
+            audit(interaction, &action, "refused: blocklisted");
+            let reply = ephemeral(messages.get("bot_command_blocked", &[("command", verb)]));
+            respond(api, interaction, CHANNEL_MESSAGE, reply);
+            return;
+        }
+
+        info!("Discord: /cmd {} requested by {}", line, user_name(interaction));
+        let (reply_tx, reply_rx) = mpsc::channel();
+        if handle.commands.send(Command::Query(line, reply_tx)).is_err() {
+            audit(interaction, &action, "golem shutting down");
+            respond(api, interaction, CHANNEL_MESSAGE, ephemeral("The golem is shutting down.".to_string()));
+            return;
+        }
+        audit(interaction, &action, "queued");
+        respond(api, interaction, DEFERRED_CHANNEL_MESSAGE, json!({ "flags": EPHEMERAL }));
+        let (Some(application_id), Some(token)) = (interaction["application_id"].as_str(), interaction["token"].as_str())
+        else {
+            return;
+        };
+        let path = format!("/webhooks/{}/{}/messages/@original", application_id, token);
+        let (api, messages) = (api.clone(), messages.clone());
+        thread::spawn(move || {
+            let content = match reply_rx.recv_timeout(Duration::from_secs(60)) {
+                Ok(Some(lines)) if lines.is_empty() => messages.get("bot_command_no_output", &[]),
+                Ok(Some(lines)) => code_block(&lines),
+                Ok(None) => messages.get("bot_command_offline", &[]),
+                Err(_) => messages.get("bot_command_timeout", &[]),
+            };
+            if let Err(e) = api.request(Method::PATCH, &path).json(&json!({ "content": content })).send() {
+                warn!("Discord: could not answer /cmd: {}", e);
+            }
+        });
+    }
+}
+
+/// The first blocklisted command in a console line, looking past a leading
+/// `/` and a `minecraft:` namespace, and at what follows every `run` as well
+/// as the first word: `execute as @s run minecraft:stop` runs `stop`.
+fn blocked_verb(line: &str, blocklist: &[String]) -> Option<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let commands = words.first().into_iter().chain(words.windows(2).filter(|pair| pair[0] == "run").map(|pair| &pair[1]));
+    commands
+        .map(|word| word.trim_start_matches('/'))
+        .map(|word| word.strip_prefix("minecraft:").unwrap_or(word))
+        .find(|verb| blocklist.iter().any(|blocked| blocked.eq_ignore_ascii_case(verb)))
+        .map(str::to_string)
+}
+
+/// A slash command's option by name, looking inside its subcommand too.
+fn option<'a>(interaction: &'a Value, name: &str) -> Option<&'a Value> {
+    let options = interaction["data"]["options"].as_array().into_iter().flatten();
+    let nested = interaction["data"]["options"][0]["options"].as_array().into_iter().flatten();
+    options.chain(nested).find(|o| o["name"] == name).map(|o| &o["value"])
+}
+
+/// Restorable backups of the server.
+fn available_backups(handle: &Handle) -> Vec<String> {
+    handle
+        .interpreter
+        .backup
+        .as_ref()
+        .map(|backup| backup::available(Path::new(&backup.directory)))
+        .unwrap_or_default()
+}
+
+/// The slash command for the audit log, e.g. `/stop`, and with several
+/// servers which one, e.g. `/stop (creative)`.
+fn action(interaction: &Value, handle: &Handle) -> String {
+    with_server(&format!("/{}", interaction["data"]["name"].as_str().unwrap_or_default()), handle)
+}
+
+fn with_server(action: &str, handle: &Handle) -> String {
+    match &handle.name {
+        Some(name) => format!("{} ({})", action, name),
+        None => action.to_string(),
+    }
+}
+
+/// Role or user allow-list; with neither configured, nobody is allowed.
+fn allowed(role_ids: &[String], user_ids: &[String], interaction: &Value) -> bool {
+    let user = user_id(interaction);
+    let roles = interaction["member"]["roles"].as_array().cloned().unwrap_or_default();
+    user_ids.iter().any(|id| id == user)
+        || roles
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|role| role_ids.iter().any(|id| id == role))
+}
+
+/// The server's answer without the `[time] [thread/LEVEL]: ` prefixes, cut
+/// to fit in one message.
+fn code_block(lines: &[String]) -> String {
+    let mut text = String::new();
+    for line in lines {
+        let line = line.split_once("]: ").map_or(line.as_str(), |(_, rest)| rest);
+        if text.len() + line.len() + 1 > MAX_REPLY_CHARS {
+            text.push_str("…\n");
+            break;
+        }
+        text.push_str(line);
+        text.push('\n');
+    }
+    format!("```\n{}```", text)
+}
+
+fn user_id(interaction: &Value) -> &str {
+    interaction["member"]["user"]["id"]
+        .as_str()
+        .or(interaction["user"]["id"].as_str())
+        .unwrap_or_default()
+}
+
+fn user_name(interaction: &Value) -> String {
+    let user = if interaction["member"].is_null() { &interaction["user"] } else { &interaction["member"]["user"] };
+    user["username"].as_str().unwrap_or("unknown").to_string()
+}
+
+/// The user's name and id, as names can be changed.
+fn audit(interaction: &Value, action: &str, result: &str) {
+    audit::record("discord", &format!("{} ({})", user_name(interaction), user_id(interaction)), action, result);
+}
+
+fn ephemeral(content: String) -> Value {
+    json!({ "content": content, "flags": EPHEMERAL })
+}
+
+fn respond(api: &DiscordApi, interaction: &Value, kind: u8, data: Value) {
+    let body = json!({ "type": kind, "data": data });
+    callback(api, interaction, |request| request.json(&body));
+}
+
+/// A reply message with a text file attached.
+fn respond_with_file(api: &DiscordApi, interaction: &Value, mut data: Value, file_name: &str, content: String) {
+    data["attachments"] = json!([{ "id": 0, "filename": file_name }]);
+    let body = json!({ "type": CHANNEL_MESSAGE, "data": data });
+    callback(api, interaction, |request| {
+        let file = Part::text(content).file_name(file_name.to_string());
+        request.multipart(Form::new().text("payload_json", body.to_string()).part("files[0]", file))
+    });
+}
+
+fn callback(api: &DiscordApi, interaction: &Value, body: impl FnOnce(RequestBuilder) -> RequestBuilder) {
+    let (Some(id), Some(token)) = (interaction["id"].as_str(), interaction["token"].as_str()) else {
+        return;
+    };
+    let path = format!("/interactions/{}/{}/callback", id, token);
+    if let Err(e) = body(api.request(Method::POST, &path)).send() {
+        warn!("Discord: could not answer /{}: {}", interaction["data"]["name"].as_str().unwrap_or_default(), e);
+    }
+}
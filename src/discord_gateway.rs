@@ -0,0 +1,129 @@
+use std::io;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rustls::{ClientConfig, ClientConnection, OwnedTrustAnchor, RootCertStore, ServerName, StreamOwned};
+use serde_json::{json, Value};
+use tracing::warn;
+use tungstenite::{Message, WebSocket};
+
+const GATEWAY_HOST: &str = "gateway.discord.gg";
+const GATEWAY_URL: &str = "wss://gateway.discord.gg/?v=10&encoding=json";
+
+type Socket = WebSocket<StreamOwned<ClientConnection, TcpStream>>;
+
+/// Stays connected to the Discord gateway as the bot, reconnecting with
+/// backoff, and hands every dispatched event (`t`, `d`) to `on_dispatch`.
+/// Never returns; run it on a thread of its own.
+pub fn run(token: &str, intents: u64, on_dispatch: &mut dyn FnMut(&str, &Value)) {
+    let mut backoff = 5;
+    loop {
+        match session(token, intents, on_dispatch) {
+            Ok(()) => backoff = 5,
+            Err(e) => {
+                warn!("Discord gateway: {}; reconnecting in {}s", e, backoff);
+                thread::sleep(Duration::from_secs(backoff));
+                backoff = (backoff * 2).min(300);
+            }
+        }
+    }
+}
+
+/// One connection, from Hello until Discord asks us to reconnect (Ok) or
+/// something fails (Err).
+fn session(token: &str, intents: u64, on_dispatch: &mut dyn FnMut(&str, &Value)) -> Result<(), String> {
+    let mut socket = connect()?;
+    let hello = loop {
+        if let Some(payload) = receive(&mut socket)? {
+            break payload;
+        }
+    };
+    let interval = Duration::from_millis(hello["d"]["heartbeat_interval"].as_u64().unwrap_or(41_250));
+    send(&mut socket, json!({
+        "op": 2,
+        "d": {
+            "token": token,
+            "intents": intents,
+            "properties": { "os": std::env::consts::OS, "browser": "rusty-golem", "device": "rusty-golem" }
+        }
+    }))?;
+
+    let mut sequence = Value::Null;
+    let mut next_heartbeat = Instant::now() + interval / 2;
+    let mut acknowledged = true;
+    loop {
+        if Instant::now() >= next_heartbeat {
+            // A zombie connection never acknowledges; drop it and reconnect
+            if !acknowledged {
+                return Err("heartbeat not acknowledged".to_string());
+            }
+            send(&mut socket, json!({ "op": 1, "d": sequence }))?;
+            acknowledged = false;
+            next_heartbeat = Instant::now() + interval;
+        }
+        let Some(payload) = receive(&mut socket)? else {
+            continue;
+        };
+        if !payload["s"].is_null() {
+            sequence = payload["s"].clone();
+        }
+        match payload["op"].as_u64() {
+            Some(0) => on_dispatch(payload["t"].as_str().unwrap_or_default(), &payload["d"]),
+            Some(1) => next_heartbeat = Instant::now(),
+            Some(7) => return Ok(()),
+            Some(9) => return Err("session invalidated".to_string()),
+            Some(11) => acknowledged = true,
+            _ => {}
+        }
+    }
+}
+
+fn connect() -> Result<Socket, String> {
+    let tcp = TcpStream::connect((GATEWAY_HOST, 443)).map_err(|e| e.to_string())?;
+    tcp.set_read_timeout(Some(Duration::from_secs(15))).map_err(|e| e.to_string())?;
+    let name = ServerName::try_from(GATEWAY_HOST).map_err(|e| e.to_string())?;
+    let tls = ClientConnection::new(tls_config(), name).map_err(|e| e.to_string())?;
+    let (socket, _) = tungstenite::client(GATEWAY_URL, StreamOwned::new(tls, tcp)).map_err(|e| e.to_string())?;
+    // Short reads from here on, so heartbeats go out on time
+    socket
+        .get_ref()
+        .sock
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+fn tls_config() -> Arc<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+    Arc::new(
+        ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth(),
+    )
+}
+
+/// The next JSON payload, or None when nothing arrived within the read timeout.
+fn receive(socket: &mut Socket) -> Result<Option<Value>, String> {
+    match socket.read() {
+        Ok(Message::Text(text)) => serde_json::from_str(&text).map(Some).map_err(|e| e.to_string()),
+        Ok(Message::Close(frame)) => Err(match frame {
+            Some(frame) => format!("closed by Discord ({}: {})", u16::from(frame.code), frame.reason),
+            None => "closed by Discord".to_string(),
+        }),
+        Ok(_) => Ok(None),
+        Err(tungstenite::Error::Io(e)) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+            Ok(None)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn send(socket: &mut Socket, payload: Value) -> Result<(), String> {
+    socket.send(Message::Text(payload.to_string())).map_err(|e| e.to_string())
+}
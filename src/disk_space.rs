@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+use crate::config::Config;
+use crate::messages::Messages;
+use crate::metrics::{self, format_bytes};
+use crate::notify::{EventKind, Notifiers};
+
+/// Warns once when the world or backup drive falls below its floor, and again
+/// only after it has recovered and fallen again.
+#[derive(Default)]
+pub struct DiskWatch {
+    world_low: bool,
+    backup_low: bool,
+}
+
+impl DiskWatch {
+    pub fn check(&mut self, config: &Config, messages: &Messages, notifiers: &Notifiers) {
+        let floors = &config.disk_space;
+        let world_dir = config.server_dir();
+        check_drive(&world_dir, floors.world_min_free_mb, &mut self.world_low, "disk_space_low_world", messages, notifiers);
+        if let Some(backup) = &config.backup {
+            let backup_dir = PathBuf::from(&backup.directory);
+            check_drive(&backup_dir, floors.backup_min_free_mb, &mut self.backup_low, "disk_space_low_backup", messages, notifiers);
+        }
+    }
+}
+
+fn check_drive(path: &Path, min_free_mb: u64, low: &mut bool, key: &str, messages: &Messages, notifiers: &Notifiers) {
+    if min_free_mb == 0 {
+        return;
+    }
+    let Some(free) = metrics::free_space(path) else {
+        return;
+    };
+    let floor = min_free_mb * 1024 * 1024;
+    if free >= floor {
+        *low = false;
+    } else if !*low {
+        *low = true;
+        warn!("Disk: only {} free for {}", format_bytes(free), path.display());
+        let message = messages.get(
+            key,
+            &[("free", format_bytes(free)), ("path", path.display().to_string()), ("floor", format_bytes(floor))],
+        );
+        notifiers.send(EventKind::DiskSpaceLow, &message);
+    }
+}
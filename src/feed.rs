@@ -0,0 +1,103 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde_json::{json, Value};
+use tungstenite::{Message, WebSocket};
+
+use crate::http::Stream;
+use crate::notify::Event;
+use crate::server_log;
+
+/// Live server console lines and golem events, fanned out to whoever is
+/// watching, e.g. `/ws/logs` connections. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct LogFeed {
+    subscribers: Arc<Mutex<Vec<Sender<Value>>>>,
+}
+
+impl LogFeed {
+    pub fn subscribe(&self) -> Receiver<Value> {
+        let (sender, receiver) = mpsc::channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    /// A console line, split into `[time] [thread/LEVEL]: message` where it
+    /// has that shape.
+    pub fn line(&self, line: &str) {
+        let (time, source) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("] ["))
+            .and_then(|(time, rest)| Some((time, rest.split_once("]: ")?.0)))
+            .unwrap_or_default();
+        let (thread, level) = source.rsplit_once('/').unwrap_or((source, ""));
+        self.publish(json!({
+            "type": "log",
+            "line": line,
+            "time": time,
+            "thread": thread,
+            "level": level,
+            "message": server_log::message(line),
+        }));
+    }
+
+    pub fn event(&self, event: &Event) {
+        self.publish(json!({
+            "type": "event",
+            "event": event.kind.as_str(),
+            "severity": event.severity.as_str(),
+            "message": event.message,
+            "timestamp": event.timestamp.to_rfc3339(),
+        }));
+    }
+
+    fn publish(&self, item: Value) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.send(item.clone()).is_ok());
+        }
+    }
+}
+
+/// Streams the feed to one WebSocket client as JSON text messages until it
+/// goes away. With a filter, only lines and events whose text matches are sent.
+pub fn stream(feed: &LogFeed, filter: Option<Regex>, mut socket: WebSocket<&mut dyn Stream>) {
+    let items = feed.subscribe();
+    let mut last_sent = Instant::now();
+    loop {
+        let item = match items.recv_timeout(Duration::from_secs(5)) {
+            Ok(item) => Some(item),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let wanted = item.filter(|item| matches(filter.as_ref(), item));
+        let message = match wanted {
+            Some(item) => Message::text(item.to_string()),
+            // Keeps proxies from closing a quiet connection, and finds out
+            // when the client has gone
+            None if last_sent.elapsed() >= Duration::from_secs(30) => Message::Ping(Vec::new()),
+            None => continue,
+        };
+        if socket.send(message).is_err() {
+            return;
+        }
+        last_sent = Instant::now();
+    }
+}
+
+/// Whether a line's or event's text matches the filter, if there is one.
+pub fn matches(filter: Option<&Regex>, item: &Value) -> bool {
+    let text = item["line"].as_str().or(item["message"].as_str()).unwrap_or_default();
+    filter.is_none_or(|filter| filter.is_match(text))
+}
+
+/// The `filter` query parameter as a regex, or the reason it is not one.
+pub fn filter(query: Option<&str>) -> Result<Option<Regex>, String> {
+    query
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| Regex::new(pattern).map_err(|e| e.to_string()))
+        .transpose()
+}
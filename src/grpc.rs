@@ -0,0 +1,275 @@
+// tonic's own signatures return its large Status as the error
+#![allow(clippy::result_large_err)]
+
+use std::net::SocketAddr;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde_json::Value;
+use tokio::sync::mpsc as async_mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status as RpcStatus};
+use tracing::{debug, info, warn};
+
+use crate::audit;
+use crate::config::{ApiTokenConfig, GrpcConfig, Scope};
+use crate::control::Command;
+use crate::dashboard::constant_time_eq;
+use crate::feed;
+use crate::instance::{Handle, Instances};
+use crate::rate_limit::RateLimiter;
+
+mod proto {
+    tonic::include_proto!("rustygolem.v1");
+}
+
+use proto::golem_server::{Golem, GolemServer};
+use proto::log_item::Item;
+
+/// The token's name and scope, attached to each authorized call.
+#[derive(Clone)]
+struct Caller {
+    name: Option<String>,
+    scope: Scope,
+}
+
+struct Service {
+    instances: Instances,
+}
+
+/// Serves proto/golem.proto on its own thread and runtime, the rest of the
+/// golem being free of async code.
+pub fn spawn(config: &GrpcConfig, instances: Instances) {
+    let address: SocketAddr = match config.bind.parse() {
+        Ok(address) => address,
+        Err(e) => {
+            warn!("gRPC: not started, bad bind address {}: {}", config.bind, e);
+            return;
+        }
+    };
+    if config.tokens.is_empty() && !address.ip().is_loopback() {
+        warn!("gRPC: no tokens configured, so anyone who can reach {} can control the server.", address);
+    }
+    let tokens = config.tokens.clone();
+    let limiter = RateLimiter::default();
+    let service = Service { instances };
+    thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                warn!("gRPC: not started: {}", e);
+                return;
+            }
+        };
+        info!("gRPC: listening on {}", address);
+        let server = GolemServer::with_interceptor(service, move |request| authorize(&tokens, &limiter, request));
+        let serving = tonic::transport::Server::builder().add_service(server).serve(address);
+        if let Err(e) = runtime.block_on(serving) {
+            warn!("gRPC: could not listen on {}: {}", address, e);
+        }
+    });
+}
+
+/// Attaches the caller of a valid token within its rate limit; the scope is
+/// checked by each call.
+fn authorize(tokens: &[ApiTokenConfig], limiter: &RateLimiter, mut request: Request<()>) -> Result<Request<()>, RpcStatus> {
+    if tokens.is_empty() {
+        request.extensions_mut().insert(Caller { name: None, scope: Scope::Admin });
+        return Ok(request);
+    }
+    let presented = request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    match tokens.iter().find(|t| constant_time_eq(t.token.as_bytes(), presented.trim().as_bytes())) {
+        Some(token) => {
+            if let Some(limit) = token.requests_per_minute {
+                if limiter.check(&token.name, limit).is_err() {
+                    warn!("gRPC: rate limited a call by {}", token.name);
+                    return Err(RpcStatus::resource_exhausted("too many requests"));
+                }
+            }
+            request.extensions_mut().insert(Caller { name: Some(token.name.clone()), scope: token.scope });
+            Ok(request)
+        }
+        None => {
+            let peer = request.remote_addr().map_or("unknown".to_string(), |a| a.to_string());
+            warn!("gRPC: rejected a call from {} (no valid token)", peer);
+            Err(RpcStatus::unauthenticated("no valid token"))
+        }
+    }
+}
+
+/// Refuses the call, named `action` for the audit log, unless its token has
+/// at least `needed`.
+fn require<T>(request: &Request<T>, action: &str, needed: Scope) -> Result<(), RpcStatus> {
+    match request.extensions().get::<Caller>() {
+        Some(caller) if caller.scope >= needed => Ok(()),
+        caller => {
+            let name = caller.and_then(|c| c.name.as_deref()).unwrap_or("unknown");
+            warn!("gRPC: refused a call by {} (needs the {} scope)", name, needed.as_str());
+            audit::record("grpc", name, action, &format!("refused: lacks the {} scope", needed.as_str()));
+            Err(RpcStatus::permission_denied(format!("this token lacks the {} scope", needed.as_str())))
+        }
+    }
+}
+
+impl Service {
+    /// The server a request names, the first one when it names none.
+    fn server(&self, name: &str) -> Result<Handle, RpcStatus> {
+        self.instances.find(Some(name).filter(|name| !name.is_empty())).map_err(RpcStatus::not_found)
+    }
+
+    /// Sends a command to the named server once the caller's scope allows it.
+    fn queue<T>(
+        &self,
+        request: &Request<T>,
+        server: &str,
+        name: &str,
+        needed: Scope,
+        command: Command,
+    ) -> Result<Response<proto::Queued>, RpcStatus> {
+        require(request, name, needed)?;
+        let server = self.server(server)?;
+        let caller = request.extensions().get::<Caller>().and_then(|c| c.name.as_deref());
+        let action = match &server.name {
+            Some(server) => format!("{} ({})", name, server),
+            None => name.to_string(),
+        };
+        match caller {
+            Some(caller) => info!("gRPC: {} by {}", action, caller),
+            None => info!("gRPC: {}", action),
+        }
+        let sent = server.commands.send(command).is_ok();
+        audit::record("grpc", caller.unwrap_or("anonymous"), &action, if sent { "queued" } else { "golem shutting down" });
+        if !sent {
+            return Err(RpcStatus::unavailable("the golem is shutting down"));
+        }
+        Ok(Response::new(proto::Queued { command: name.to_string() }))
+    }
+}
+
+fn unix(time: Option<DateTime<Local>>) -> Option<i64> {
+    time.map(|t| t.timestamp())
+}
+
+fn status(server: &Handle) -> proto::Status {
+    let status = server.status();
+    let online = |value| if status.online { value } else { None };
+    proto::Status {
+        online: status.online,
+        online_since: online(unix(status.online_since)),
+        players: status.players as u32,
+        player_names: status.player_names.clone(),
+        tps: status.tps.filter(|_| status.online),
+        rss_bytes: status.rss_bytes.filter(|_| status.online),
+        last_backup: unix(status.last_backup.as_ref().map(|r| r.created)),
+        closes_at: unix(status.closes_at),
+        opens_at: unix(status.opens_at),
+        schedule_paused: status.schedule_paused,
+        name: server.name.clone().unwrap_or_default(),
+    }
+}
+
+#[tonic::async_trait]
+impl Golem for Service {
+    async fn get_status(&self, request: Request<proto::StatusRequest>) -> Result<Response<proto::Status>, RpcStatus> {
+        require(&request, "status", Scope::Read)?;
+        Ok(Response::new(status(&self.server(&request.get_ref().server)?)))
+    }
+
+    async fn list_servers(&self, request: Request<proto::ListServersRequest>) -> Result<Response<proto::ServerList>, RpcStatus> {
+        require(&request, "servers", Scope::Read)?;
+        Ok(Response::new(proto::ServerList { servers: self.instances.all().iter().map(status).collect() }))
+    }
+
+    async fn start(&self, request: Request<proto::ServerRequest>) -> Result<Response<proto::Queued>, RpcStatus> {
+        self.queue(&request, &request.get_ref().server, "start", Scope::Control, Command::Start)
+    }
+
+    async fn stop(&self, request: Request<proto::ServerRequest>) -> Result<Response<proto::Queued>, RpcStatus> {
+        self.queue(&request, &request.get_ref().server, "stop", Scope::Control, Command::Stop)
+    }
+
+    async fn restart(&self, request: Request<proto::ServerRequest>) -> Result<Response<proto::Queued>, RpcStatus> {
+        self.queue(&request, &request.get_ref().server, "restart", Scope::Control, Command::Restart)
+    }
+
+    async fn extend(&self, request: Request<proto::ExtendRequest>) -> Result<Response<proto::Queued>, RpcStatus> {
+        let minutes = request.get_ref().minutes;
+        if minutes == 0 {
+            return Err(RpcStatus::invalid_argument("minutes must be positive"));
+        }
+        self.queue(&request, &request.get_ref().server, "extend", Scope::Control, Command::Extend(minutes))
+    }
+
+    async fn run_command(&self, request: Request<proto::CommandRequest>) -> Result<Response<proto::CommandReply>, RpcStatus> {
+        let line = request.get_ref().command.trim().trim_start_matches('/').to_string();
+        if line.is_empty() {
+            return Err(RpcStatus::invalid_argument("no command given"));
+        }
+        let (reply_tx, reply_rx) = mpsc::channel();
+        let server = &request.get_ref().server;
+        self.queue(&request, server, &format!("command {}", line), Scope::Admin, Command::Query(line, reply_tx))?;
+        // The main loop answers between its other work, so wait off the runtime
+        let answer = tokio::task::spawn_blocking(move || reply_rx.recv_timeout(Duration::from_secs(30)))
+            .await
+            .map_err(|e| RpcStatus::internal(e.to_string()))?;
+        match answer {
+            Ok(Some(lines)) => Ok(Response::new(proto::CommandReply { lines })),
+            Ok(None) => Err(RpcStatus::failed_precondition("the server is not running")),
+            Err(_) => Err(RpcStatus::deadline_exceeded("the golem did not answer in time")),
+        }
+    }
+
+    type StreamLogsStream = ReceiverStream<Result<proto::LogItem, RpcStatus>>;
+
+    async fn stream_logs(&self, request: Request<proto::LogsRequest>) -> Result<Response<Self::StreamLogsStream>, RpcStatus> {
+        require(&request, "logs", Scope::Read)?;
+        let filter = feed::filter(Some(&request.get_ref().filter)).map_err(|e| RpcStatus::invalid_argument(format!("bad filter: {}", e)))?;
+        let server = self.server(&request.get_ref().server)?;
+        debug!("gRPC: log stream opened");
+        let items = server.feed.subscribe();
+        let (sender, receiver) = async_mpsc::channel(64);
+        thread::spawn(move || loop {
+            match items.recv_timeout(Duration::from_secs(5)) {
+                Ok(item) if feed::matches(filter.as_ref(), &item) => {
+                    if sender.blocking_send(Ok(log_item(&item))).is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) if sender.is_closed() => return,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// A feed item in its proto form.
+fn log_item(item: &Value) -> proto::LogItem {
+    let text = |name: &str| item[name].as_str().unwrap_or_default().to_string();
+    let item = if item["type"] == "event" {
+        Item::Event(proto::GolemEvent {
+            event: text("event"),
+            severity: text("severity"),
+            message: text("message"),
+            timestamp: text("timestamp"),
+        })
+    } else {
+        Item::Line(proto::LogLine {
+            line: text("line"),
+            time: text("time"),
+            thread: text("thread"),
+            level: text("level"),
+            message: text("message"),
+        })
+    };
+    proto::LogItem { item: Some(item) }
+}
@@ -0,0 +1,59 @@
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::Client;
+use tracing::{info, warn};
+
+use crate::config::HeartbeatConfig;
+
+/// Pings an external monitor (healthchecks.io, UptimeRobot, ...) from the main
+/// loop, so a hung loop, a dead golem and a dead host all fall silent alike.
+/// The request itself goes out on a thread of its own.
+pub struct Heartbeat {
+    beats: SyncSender<()>,
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl Heartbeat {
+    pub fn spawn(config: &HeartbeatConfig) -> Heartbeat {
+        // One beat in flight at most; a slow monitor just misses a few
+        let (beats, pending) = mpsc::sync_channel(1);
+        let url = config.url.clone();
+        thread::spawn(move || {
+            let client = Client::new();
+            let mut failing = false;
+            for () in pending {
+                let sent = client
+                    .get(&url)
+                    .timeout(Duration::from_secs(10))
+                    .send()
+                    .and_then(|response| response.error_for_status());
+                match sent {
+                    Ok(_) if failing => {
+                        failing = false;
+                        info!("Heartbeat: reaching {} again", url);
+                    }
+                    Ok(_) => {}
+                    Err(e) if !failing => {
+                        failing = true;
+                        warn!("Heartbeat: {} failed: {}", url, e);
+                    }
+                    Err(_) => {}
+                }
+            }
+        });
+        Heartbeat { beats, interval: Duration::from_secs(config.interval_seconds.max(10)), last: None }
+    }
+
+    /// Called on every pass of the main loop while all is well; pings at most
+    /// once per interval.
+    pub fn beat(&mut self) {
+        if self.last.is_some_and(|t| t.elapsed() < self.interval) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        let _ = self.beats.try_send(());
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Days, Local, NaiveDate};
+use tracing::warn;
+
+use crate::instance;
+
+/// One CSV file per series, `<time>,<value>` per line.
+const HISTORY_DIR: &str = "stats";
+
+fn series_path(series: &str) -> PathBuf {
+    instance::path(HISTORY_DIR).join(format!("{}.csv", series))
+}
+
+/// Appends one sample, e.g. `append("players", now, "3")`.
+pub fn append(series: &str, time: DateTime<Local>, value: &str) {
+    let path = series_path(series);
+    let written = fs::create_dir_all(instance::path(HISTORY_DIR))
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(&path))
+        .and_then(|mut file| writeln!(file, "{},{}", time.to_rfc3339(), value));
+    if let Err(e) = written {
+        warn!("History: could not write {}: {}", path.display(), e);
+    }
+}
+
+/// The samples of `series` from `since` on, oldest first.
+pub fn read_since(series: &str, since: DateTime<Local>) -> Vec<(DateTime<Local>, String)> {
+    let Ok(content) = fs::read_to_string(series_path(series)) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let (time, value) = line.split_once(',')?;
+            let time = DateTime::parse_from_rfc3339(time).ok()?.with_timezone(&Local);
+            (time >= since).then(|| (time, value.to_string()))
+        })
+        .collect()
+}
+
+/// The days covered by `--week` (the default) or `--month`, and the start of
+/// the first of them.
+fn period(args: &[&str]) -> Option<(u64, DateTime<Local>)> {
+    let days = match args.first() {
+        None | Some(&"--week") => 7,
+        Some(&"--month") => 30,
+        Some(_) => return None,
+    };
+    Some((days, start_of_last(days)))
+}
+
+/// Midnight at the start of the last `days` days, today included.
+pub fn start_of_last(days: u64) -> DateTime<Local> {
+    let today = Local::now().date_naive();
+    let first = today.checked_sub_days(Days::new(days - 1)).unwrap_or(today);
+    first.and_hms_opt(0, 0, 0).and_then(|t| t.and_local_timezone(Local).earliest()).unwrap_or_else(Local::now)
+}
+
+/// `stats players [--week|--month]`: peak, when it was reached and the
+/// average while running, per day over the last 7 (or 30) days.
+pub fn player_lines(args: &[&str]) -> Vec<String> {
+    let Some((days, since)) = period(args) else {
+        return vec!["Usage: stats players [--week|--month]".to_string()];
+    };
+
+    // Peak, time of the peak, sum and count of the samples
+    let mut by_day: BTreeMap<NaiveDate, (usize, DateTime<Local>, usize, usize)> = BTreeMap::new();
+    for (time, value) in read_since("players", since) {
+        let Ok(players) = value.parse::<usize>() else {
+            continue;
+        };
+        let day = by_day.entry(time.date_naive()).or_insert((0, time, 0, 0));
+        if players > day.0 {
+            day.0 = players;
+            day.1 = time;
+        }
+        day.2 += players;
+        day.3 += 1;
+    }
+    if by_day.is_empty() {
+        return vec![format!("No player counts recorded in the last {} days.", days)];
+    }
+    let mut lines = vec![format!("{:<10} {:>5} {:>6} {:>8} {:>9}", "Day", "Peak", "at", "Average", "Hours up")];
+    for (date, (peak, peak_at, sum, samples)) in by_day {
+        lines.push(format!(
+            "{:<10} {:>5} {:>6} {:>8.1} {:>9.1}",
+            date.format("%a %m-%d"),
+            peak,
+            peak_at.format("%H:%M"),
+            sum as f64 / samples as f64,
+            // One sample per minute while the server runs
+            samples as f64 / 60.0
+        ));
+    }
+    lines
+}
+
+/// `stats tps [--week|--month]`: lowest and average TPS, and the average
+/// milliseconds per tick where the server reports them, per day.
+pub fn tps_lines(args: &[&str]) -> Vec<String> {
+    let Some((days, since)) = period(args) else {
+        return vec!["Usage: stats tps [--week|--month]".to_string()];
+    };
+    // Lowest TPS, TPS sum and count, MSPT sum and count
+    let mut by_day: BTreeMap<NaiveDate, (f64, f64, usize, f64, usize)> = BTreeMap::new();
+    for (time, value) in read_since("tps", since) {
+        let (tps, mspt) = value.split_once(',').unwrap_or((&value, ""));
+        let Ok(tps) = tps.parse::<f64>() else {
+            continue;
+        };
+        let day = by_day.entry(time.date_naive()).or_insert((f64::MAX, 0.0, 0, 0.0, 0));
+        day.0 = day.0.min(tps);
+        day.1 += tps;
+        day.2 += 1;
+        if let Ok(mspt) = mspt.parse::<f64>() {
+            day.3 += mspt;
+            day.4 += 1;
+        }
+    }
+    if by_day.is_empty() {
+        return vec![format!("No TPS recorded in the last {} days; is tps_command set?", days)];
+    }
+    let mut lines = vec![format!("{:<10} {:>7} {:>8} {:>9}", "Day", "Lowest", "Average", "Avg MSPT")];
+    for (date, (lowest, sum, samples, mspt_sum, mspt_samples)) in by_day {
+        let mspt = if mspt_samples == 0 { "-".to_string() } else { format!("{:.1}", mspt_sum / mspt_samples as f64) };
+        lines.push(format!(
+            "{:<10} {:>7.1} {:>8.1} {:>9}",
+            date.format("%a %m-%d"),
+            lowest,
+            sum / samples as f64,
+            mspt
+        ));
+    }
+    lines
+}
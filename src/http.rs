@@ -0,0 +1,333 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use rustls::{Certificate, PrivateKey, ServerConfig, ServerConnection, StreamOwned};
+use tracing::{debug, debug_span};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::WebSocket;
+
+/// Largest request head (request line plus headers) accepted.
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+pub struct Request {
+    pub method: String,
+    /// Without the query string.
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub peer: SocketAddr,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The body as JSON; an empty body reads as `null`.
+    pub fn json(&self) -> Result<serde_json::Value, String> {
+        if self.body.is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        serde_json::from_slice(&self.body).map_err(|e| e.to_string())
+    }
+}
+
+/// A connection handed over after a `101 Switching Protocols` response.
+pub trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// Takes over the connection once the response is written.
+pub type Upgrade = Box<dyn FnOnce(&mut dyn Stream) + Send>;
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub upgrade: Option<Upgrade>,
+}
+
+impl Response {
+    pub fn json(status: u16, value: &serde_json::Value) -> Self {
+        Response {
+            status,
+            content_type: "application/json",
+            headers: Vec::new(),
+            body: value.to_string().into_bytes(),
+            upgrade: None,
+        }
+    }
+
+    pub fn html(body: &str) -> Self {
+        Response {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+            upgrade: None,
+        }
+    }
+
+    pub fn text(body: &str) -> Self {
+        Response {
+            status: 200,
+            content_type: "text/plain; charset=utf-8",
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+            upgrade: None,
+        }
+    }
+
+    pub fn empty(status: u16) -> Self {
+        Response {
+            status,
+            content_type: "text/plain",
+            headers: Vec::new(),
+            body: Vec::new(),
+            upgrade: None,
+        }
+    }
+
+    /// Accepts a WebSocket upgrade and hands the socket to `serve`, which
+    /// keeps the connection for as long as it runs.
+    pub fn websocket(request: &Request, serve: impl FnOnce(WebSocket<&mut dyn Stream>) + Send + 'static) -> Self {
+        let upgrading = request.header("Upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+        let Some(key) = request.header("Sec-WebSocket-Key").filter(|_| upgrading) else {
+            return Response::error(400, "expected a WebSocket upgrade");
+        };
+        let mut response = Response::empty(101)
+            .with_header("Upgrade", "websocket")
+            .with_header("Connection", "Upgrade")
+            .with_header("Sec-WebSocket-Accept", &derive_accept_key(key.as_bytes()));
+        response.upgrade = Some(Box::new(move |stream| {
+            serve(WebSocket::from_raw_socket(stream, Role::Server, None));
+        }));
+        response
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Response::json(status, &serde_json::json!({ "error": message }))
+    }
+}
+
+pub type Handler = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
+
+/// Reads a PEM certificate chain and private key (PKCS#8, RSA or EC).
+pub fn load_tls(cert_path: &str, key_path: &str) -> Result<Arc<ServerConfig>, String> {
+    let open = |path: &str| File::open(path).map(BufReader::new).map_err(|e| format!("{}: {}", path, e));
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut open(cert_path)?)
+        .map_err(|e| format!("{}: {}", cert_path, e))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if certs.is_empty() {
+        return Err(format!("{}: no certificate found", cert_path));
+    }
+    let key = rustls_pemfile::read_all(&mut open(key_path)?)
+        .map_err(|e| format!("{}: {}", key_path, e))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key) | rustls_pemfile::Item::RSAKey(key) | rustls_pemfile::Item::ECKey(key) => {
+                Some(PrivateKey(key))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| format!("{}: no private key found", key_path))?;
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| e.to_string())?;
+    Ok(Arc::new(config))
+}
+
+/// Listens on `bind`, over TLS when given its config, and answers each
+/// connection on a thread of its own, one request per connection.
+pub fn serve(bind: &str, tls: Option<Arc<ServerConfig>>, handler: Handler) -> io::Result<()> {
+    let listener = TcpListener::bind(bind)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handler = handler.clone();
+            let tls = tls.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, tls, &handler) {
+                    debug!("HTTP: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, tls: Option<Arc<ServerConfig>>, handler: &Handler) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(15)))?;
+    let peer = stream.peer_addr()?;
+    let Some(tls) = tls else {
+        return exchange(&mut stream, peer, handler);
+    };
+    let connection = ServerConnection::new(tls).map_err(io::Error::other)?;
+    let mut stream = StreamOwned::new(connection, stream);
+    exchange(&mut stream, peer, handler)?;
+    stream.conn.send_close_notify();
+    stream.flush()
+}
+
+fn exchange(stream: &mut impl Stream, peer: SocketAddr, handler: &Handler) -> io::Result<()> {
+    let mut response = match read_request(stream, peer) {
+        Ok(Some(request)) => {
+            let _span = debug_span!("request", method = %request.method, path = %request.path, %peer).entered();
+            handler(&request)
+        }
+        Ok(None) => return Ok(()),
+        Err(e) => Response::error(400, &e),
+    };
+    write_response(stream, &response)?;
+    if let Some(upgrade) = response.upgrade.take() {
+        upgrade(stream);
+    }
+    Ok(())
+}
+
+fn read_request(stream: &mut impl Read, peer: SocketAddr) -> Result<Option<Request>, String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let (head_len, method, target, headers) = loop {
+        let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        let mut slots = [httparse::EMPTY_HEADER; 64];
+        let mut parsed = httparse::Request::new(&mut slots);
+        match parsed.parse(&buffer).map_err(|e| e.to_string())? {
+            httparse::Status::Complete(len) => {
+                let headers: Vec<(String, String)> = parsed
+                    .headers
+                    .iter()
+                    .map(|h| (h.name.to_string(), String::from_utf8_lossy(h.value).to_string()))
+                    .collect();
+                break (
+                    len,
+                    parsed.method.unwrap_or_default().to_string(),
+                    parsed.path.unwrap_or("/").to_string(),
+                    headers,
+                );
+            }
+            httparse::Status::Partial if buffer.len() > MAX_HEAD_BYTES => return Err("request head too large".to_string()),
+            httparse::Status::Partial => {}
+        }
+    };
+
+    let length = headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        return Err("request body too large".to_string());
+    }
+    let mut body = buffer.split_off(head_len);
+    while body.len() < length {
+        let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(length);
+
+    let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(&name.replace('+', " ")), percent_decode(&value.replace('+', " ")))
+        })
+        .collect();
+    Ok(Some(Request {
+        method,
+        path: percent_decode(path),
+        query,
+        headers,
+        body,
+        peer,
+    }))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, byte) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn write_response(stream: &mut impl Write, response: &Response) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason(response.status));
+    if response.upgrade.is_none() {
+        head.push_str(&format!(
+            "Content-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            response.content_type,
+            response.body.len()
+        ));
+    }
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        101 => "Switching Protocols",
+        200 => "OK",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        429 => "Too Many Requests",
+        _ => "Internal Server Error",
+    }
+}
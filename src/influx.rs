@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Duration;
+
+use chrono::Local;
+use reqwest::blocking::Client;
+use tracing::{info, warn};
+
+use crate::config::InfluxConfig;
+use crate::control::Status;
+use crate::instance::Instances;
+
+/// Pushes the status to an InfluxDB (or any line-protocol) write endpoint on
+/// an interval, from a thread of its own: a line per server, tagged with its
+/// `server` name when it has one.
+pub fn spawn(config: &InfluxConfig, instances: Instances) {
+    let config = config.clone();
+    thread::spawn(move || {
+        let client = Client::new();
+        let interval = Duration::from_secs(config.interval_seconds.max(1));
+        let mut failing = false;
+        loop {
+            thread::sleep(interval);
+            let lines: Vec<String> = instances
+                .all()
+                .iter()
+                .map(|server| {
+                    let mut tags = config.tags.clone();
+                    if let Some(name) = &server.name {
+                        tags.insert("server".to_string(), name.clone());
+                    }
+                    render(&server.status(), &config.measurement, &tags)
+                })
+                .collect();
+            let body = lines.join("\n");
+            let mut request = client.post(&config.url).timeout(Duration::from_secs(10)).body(body);
+            if let Some(token) = &config.token {
+                request = request.header("Authorization", format!("Token {}", token));
+            }
+            match request.send().and_then(|response| response.error_for_status()) {
+                Ok(_) if failing => {
+                    failing = false;
+                    info!("InfluxDB: writing to {} again", config.url);
+                }
+                Ok(_) => {}
+                // Said once, not on every interval while the database is down
+                Err(e) if !failing => {
+                    failing = true;
+                    warn!("InfluxDB: write to {} failed: {}", config.url, e);
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+/// One line: `<measurement>,<tags> <fields> <unix seconds>`. As with
+/// Prometheus, figures that only exist while the server runs are left out
+/// otherwise.
+fn render(status: &Status, measurement: &str, tags: &BTreeMap<String, String>) -> String {
+    let mut line = escape(measurement, false);
+    for (key, value) in tags {
+        line.push_str(&format!(",{}={}", escape(key, true), escape(value, true)));
+    }
+    let mut fields = vec![
+        format!("server_up={}", status.online),
+        format!("players={}i", status.players),
+        format!("crashes={}i", status.counters.crashes),
+        format!("freezes={}i", status.counters.freezes),
+        format!("restarts={}i", status.counters.restarts),
+    ];
+    if status.online {
+        if let Some(tps) = status.tps {
+            fields.push(format!("tps={}", tps));
+        }
+        if let Some(rss) = status.rss_bytes {
+            fields.push(format!("rss_bytes={}i", rss));
+        }
+        if let Some(since) = status.online_since {
+            fields.push(format!("uptime_seconds={}i", (Local::now() - since).num_seconds().max(0)));
+        }
+        if let Some(latency) = status.latency {
+            fields.push(format!("latency_ms={}", latency.as_secs_f64() * 1000.0));
+        }
+    }
+    if let Some(host) = &status.host {
+        fields.push(format!("host_cpu_percent={}", host.cpu_percent));
+        fields.push(format!("host_memory_used_bytes={}i", host.memory_used));
+    }
+    format!("{} {} {}\n", line, fields.join(","), Local::now().timestamp())
+}
+
+/// Backslashes before the characters that end a name: commas and spaces, and
+/// in tags also `=`.
+fn escape(text: &str, tag: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == ',' || c == ' ' || (tag && c == '=') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
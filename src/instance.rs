@@ -0,0 +1,170 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::console::Interpreter;
+use crate::control::{Command, Status};
+use crate::feed::LogFeed;
+use crate::monitor::Pulse;
+use crate::notify::Escalations;
+
+/// Folder under which each server in `[[servers]]` keeps its state files.
+const SERVERS_DIR: &str = "servers";
+
+/// The first server's folder, for the threads that belong to no server in
+/// particular (the console, the API, the bot): they work with the first one.
+static PRIMARY: OnceLock<PathBuf> = OnceLock::new();
+
+thread_local! {
+    static STATE_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Where the server a thread supervises keeps its schedule changes, history,
+/// events and availability: the working folder with a single server,
+/// `servers/<name>` for each of several.
+pub fn path(file: &str) -> PathBuf {
+    let dir = STATE_DIR.with(|dir| dir.borrow().clone()).or_else(|| PRIMARY.get().cloned());
+    dir.unwrap_or_default().join(file)
+}
+
+/// Makes this thread work with the named server's state files.
+pub fn enter(name: &str, primary: bool) {
+    let dir = PathBuf::from(SERVERS_DIR).join(name);
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        tracing::warn!("Could not create {}: {}", dir.display(), e);
+    }
+    if primary {
+        let _ = PRIMARY.set(dir.clone());
+    }
+    STATE_DIR.with(|state| *state.borrow_mut() = Some(dir));
+}
+
+/// The folder this thread chose with `enter`, if it did.
+fn current() -> Option<PathBuf> {
+    STATE_DIR.with(|dir| dir.borrow().clone())
+}
+
+/// Runs `f` with this thread working with the state files in `dir`, then
+/// goes back to its own.
+fn within<T>(dir: Option<PathBuf>, f: impl FnOnce() -> T) -> T {
+    let own = STATE_DIR.with(|state| state.replace(dir));
+    let result = f();
+    STATE_DIR.with(|state| *state.borrow_mut() = own);
+    result
+}
+
+/// One supervised server as the console and the remote interfaces reach it.
+#[derive(Clone)]
+pub struct Handle {
+    /// As in `[[servers]]`; None for the only server.
+    pub name: Option<String>,
+    /// Its `display_name`, for titles; None for the only server.
+    pub display_name: Option<String>,
+    pub commands: Sender<Command>,
+    /// Its status, queue, backups and aliases, and the console's command set
+    /// over them.
+    pub interpreter: Interpreter,
+    pub feed: LogFeed,
+    /// Its alerts awaiting acknowledgement, with `[escalation]`.
+    pub escalations: Option<Escalations>,
+    /// Its main loop's progress, for the heartbeat file.
+    pub pulse: Pulse,
+    dir: Option<PathBuf>,
+}
+
+impl Handle {
+    /// For the server the calling thread supervises, after `enter`.
+    pub fn new(
+        name: Option<String>,
+        display_name: Option<String>,
+        commands: Sender<Command>,
+        interpreter: Interpreter,
+        feed: LogFeed,
+        escalations: Option<Escalations>,
+    ) -> Self {
+        Handle { name, display_name, commands, interpreter, feed, escalations, pulse: Pulse::new(), dir: current() }
+    }
+
+    pub fn status(&self) -> Status {
+        self.interpreter.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// `[<display name>] text` for one of several servers, to tell them
+    /// apart; the text as it is for the only one.
+    pub fn titled(&self, text: String) -> String {
+        match &self.display_name {
+            Some(name) => format!("[{}] {}", name, text),
+            None => text,
+        }
+    }
+
+    /// Runs `f` with the calling thread working with this server's state
+    /// files: its history, stats and availability.
+    pub fn within<T>(&self, f: impl FnOnce() -> T) -> T {
+        within(self.dir.clone(), f)
+    }
+}
+
+/// Every server the golem supervises, in `[[servers]]` order, as each one's
+/// supervisor registers it. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct Instances(Arc<Mutex<Vec<(usize, Handle)>>>);
+
+impl Instances {
+    /// `index` is the server's place in `[[servers]]`, 0 for the only one.
+    pub fn register(&self, index: usize, handle: Handle) {
+        let mut handles = self.0.lock().unwrap();
+        handles.retain(|(i, _)| *i != index);
+        let at = handles.partition_point(|(i, _)| *i < index);
+        handles.insert(at, (index, handle));
+    }
+
+    pub fn all(&self) -> Vec<Handle> {
+        self.0.lock().unwrap().iter().map(|(_, handle)| handle.clone()).collect()
+    }
+
+    /// The first server, which the interfaces work with unless told otherwise.
+    /// It registers before it starts them.
+    pub fn primary(&self) -> Handle {
+        self.all().into_iter().next().expect("registered before the interfaces start")
+    }
+
+    /// Whether the servers come from `[[servers]]`, so requests may name one.
+    pub fn named(&self) -> bool {
+        self.primary().name.is_some()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.all().into_iter().filter_map(|handle| handle.name).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Handle> {
+        self.all().into_iter().find(|handle| handle.name.as_deref() == Some(name))
+    }
+
+    /// Acknowledges every server's outstanding alerts, returning how many;
+    /// None without `[escalation]`.
+    pub fn acknowledge_all(&self) -> Option<usize> {
+        let escalations: Vec<Escalations> = self.all().into_iter().filter_map(|handle| handle.escalations).collect();
+        (!escalations.is_empty()).then(|| escalations.iter().map(Escalations::acknowledge_all).sum())
+    }
+
+    /// The named server, or the first one for None.
+    pub fn find(&self, name: Option<&str>) -> Result<Handle, String> {
+        match name {
+            None => Ok(self.primary()),
+            Some(_) if !self.named() => Err("There is only one server; leave out the server name.".to_string()),
+            Some(name) => self
+                .get(name)
+                .ok_or_else(|| format!("No server named {}; the servers are {}.", name, self.names().join(", "))),
+        }
+    }
+}
+
+/// Makes this thread keep its state files in `dir`.
+#[cfg(test)]
+pub fn enter_dir(dir: PathBuf) {
+    let _ = std::fs::create_dir_all(&dir);
+    STATE_DIR.with(|state| *state.borrow_mut() = Some(dir));
+}
@@ -0,0 +1,165 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::thread;
+
+use tracing::{info, warn};
+
+use crate::audit;
+use crate::console;
+use crate::control::Command;
+use crate::instance::Instances;
+
+/// Serves the console's command set on `path`, a Unix domain socket or, on
+/// Windows, a named pipe such as `\\.\pipe\rusty-golem`: a client writes one
+/// command line and reads the answer until the golem closes the connection.
+/// Lines name their server as on the console.
+pub fn spawn(path: &str, instances: Instances) {
+    match platform::listen(path) {
+        Ok(listener) => {
+            info!("Control socket on {}", path);
+            thread::spawn(move || {
+                platform::accept_loop(listener, &mut |connection| {
+                    if let Err(e) = serve(connection, &instances).and_then(platform::finish) {
+                        warn!("Control socket: {}", e);
+                    }
+                })
+            });
+        }
+        Err(e) => warn!("Control socket: cannot listen on {}: {}", path, e),
+    }
+}
+
+fn serve<C: Read + Write>(connection: C, instances: &Instances) -> io::Result<C> {
+    let mut reader = BufReader::new(connection);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let (mut answer, command) = console::route(instances, &line);
+    if let Some((command, handle)) = command {
+        let queued = match &command {
+            Command::Console(line) => format!("Sent to the server console: {}", line),
+            Command::ConsoleLines(lines) => format!("Sent to the server console: {}", lines.join("; ")),
+            _ => "Queued.".to_string(),
+        };
+        let sent = handle.commands.send(command).is_ok();
+        audit::record("ipc", "local", line.trim(), if sent { "queued" } else { "golem shutting down" });
+        answer.push(if sent { queued } else { "The golem is shutting down.".to_string() });
+    }
+    let mut connection = reader.into_inner();
+    for line in answer {
+        writeln!(connection, "{}", line)?;
+    }
+    Ok(connection)
+}
+
+/// `rusty-golem ctl <command...>`: hands one command line to the running golem
+/// and prints its answer.
+pub fn send(path: &str, line: &str) -> io::Result<()> {
+    let mut connection = platform::connect(path)?;
+    writeln!(connection, "{}", line)?;
+    connection.flush()?;
+    let mut answer = String::new();
+    connection.read_to_string(&mut answer)?;
+    print!("{}", answer);
+    Ok(())
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::fs;
+    use std::io;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::time::Duration;
+
+    pub fn listen(path: &str) -> io::Result<UnixListener> {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, "another golem is listening"));
+        }
+        // Left behind by a golem that did not shut down cleanly
+        let _ = fs::remove_file(path);
+        UnixListener::bind(path)
+    }
+
+    pub fn accept_loop(listener: UnixListener, serve: &mut dyn FnMut(UnixStream)) {
+        for stream in listener.incoming().flatten() {
+            // One client at a time, so a silent one must not hold up the rest
+            if stream.set_read_timeout(Some(Duration::from_secs(5))).is_ok() {
+                serve(stream);
+            }
+        }
+    }
+
+    pub fn finish(stream: UnixStream) -> io::Result<()> {
+        stream.shutdown(std::net::Shutdown::Both)
+    }
+
+    pub fn connect(path: &str) -> io::Result<UnixStream> {
+        UnixStream::connect(path)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+    use std::ptr;
+
+    const PIPE_ACCESS_DUPLEX: u32 = 0x3;
+    const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+    const ERROR_PIPE_CONNECTED: i32 = 535;
+    const INVALID_HANDLE_VALUE: isize = -1;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateNamedPipeW(
+            name: *const u16,
+            open_mode: u32,
+            pipe_mode: u32,
+            max_instances: u32,
+            out_buffer_size: u32,
+            in_buffer_size: u32,
+            default_timeout: u32,
+            security_attributes: *mut c_void,
+        ) -> isize;
+        fn ConnectNamedPipe(pipe: isize, overlapped: *mut c_void) -> i32;
+    }
+
+    /// Named pipes have no listener object; each connection needs a fresh
+    /// pipe instance, created in `accept_loop`.
+    pub struct PipeName(Vec<u16>);
+
+    pub fn listen(path: &str) -> io::Result<PipeName> {
+        let name: Vec<u16> = std::ffi::OsStr::new(path).encode_wide().chain(Some(0)).collect();
+        Ok(PipeName(name))
+    }
+
+    pub fn accept_loop(name: PipeName, serve: &mut dyn FnMut(File)) {
+        loop {
+            // Byte-mode, blocking pipe
+            let handle = unsafe {
+                CreateNamedPipeW(name.0.as_ptr(), PIPE_ACCESS_DUPLEX, 0, PIPE_UNLIMITED_INSTANCES, 4096, 4096, 0, ptr::null_mut())
+            };
+            if handle == INVALID_HANDLE_VALUE {
+                warn!("Control socket: cannot create the pipe: {}", io::Error::last_os_error());
+                return;
+            }
+            let connected = unsafe { ConnectNamedPipe(handle, ptr::null_mut()) } != 0
+                || io::Error::last_os_error().raw_os_error() == Some(ERROR_PIPE_CONNECTED);
+            let pipe = unsafe { File::from_raw_handle(handle as _) };
+            if connected {
+                serve(pipe);
+            }
+        }
+    }
+
+    /// Waits for the client to read everything; closing the pipe earlier
+    /// discards unread data.
+    pub fn finish(pipe: File) -> io::Result<()> {
+        pipe.sync_all()
+    }
+
+    pub fn connect(path: &str) -> io::Result<File> {
+        OpenOptions::new().read(true).write(true).open(path)
+    }
+}
@@ -0,0 +1,116 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Value};
+
+use crate::backup::BackupResult;
+
+/// Finished jobs kept for lookup; older ones are forgotten.
+const KEPT_JOBS: usize = 50;
+
+/// Backups requested through the API, so the caller can follow them to the
+/// end. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct BackupJobs {
+    jobs: Arc<Mutex<Vec<Job>>>,
+}
+
+struct Job {
+    id: u64,
+    requested: DateTime<Local>,
+    started: Option<DateTime<Local>>,
+    finished: Option<DateTime<Local>>,
+    /// The world backup's outcome, once finished.
+    outcome: Option<Result<Value, String>>,
+}
+
+/// One job, handed to the main loop with the backup command.
+pub struct BackupJob {
+    jobs: BackupJobs,
+    pub id: u64,
+}
+
+impl BackupJobs {
+    pub fn create(&self) -> BackupJob {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let id = jobs.last().map_or(1, |job| job.id + 1);
+        jobs.push(Job { id, requested: Local::now(), started: None, finished: None, outcome: None });
+        let excess = jobs.len().saturating_sub(KEPT_JOBS);
+        jobs.drain(..excess);
+        BackupJob { jobs: self.clone(), id }
+    }
+
+    /// The job as JSON: `state` is queued, running, succeeded or failed.
+    pub fn get(&self, id: u64) -> Option<Value> {
+        let jobs = self.jobs.lock().ok()?;
+        let job = jobs.iter().find(|job| job.id == id)?;
+        let state = match (&job.started, &job.outcome) {
+            (None, _) => "queued",
+            (Some(_), None) => "running",
+            (Some(_), Some(Ok(_))) => "succeeded",
+            (Some(_), Some(Err(_))) => "failed",
+        };
+        let (result, error) = match &job.outcome {
+            Some(Ok(result)) => (Some(result.clone()), None),
+            Some(Err(e)) => (None, Some(e.clone())),
+            None => (None, None),
+        };
+        Some(json!({
+            "id": job.id,
+            "state": state,
+            "requested_at": job.requested.to_rfc3339(),
+            "started_at": job.started.map(|t| t.to_rfc3339()),
+            "finished_at": job.finished.map(|t| t.to_rfc3339()),
+            "elapsed_secs": job.started.map(|t| (job.finished.unwrap_or_else(Local::now) - t).num_milliseconds() as f64 / 1000.0),
+            "result": result,
+            "error": error,
+        }))
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            if let Some(job) = jobs.iter_mut().find(|job| job.id == id) {
+                change(job);
+            }
+        }
+    }
+}
+
+impl BackupJob {
+    pub fn started(&self) {
+        self.jobs.update(self.id, |job| job.started = Some(Local::now()));
+    }
+
+    /// Records the world backup's outcome. A backup that was written but
+    /// failed verification counts as failed; failed uploads are listed in
+    /// the result.
+    pub fn finished(&self, outcome: &io::Result<BackupResult>) {
+        let outcome = match outcome {
+            Ok(result) => {
+                let uploads: Vec<Value> = result
+                    .uploads
+                    .iter()
+                    .map(|(destination, outcome)| json!({ "destination": destination, "error": outcome.as_ref().err() }))
+                    .collect();
+                let details = json!({
+                    "file": result.file_name(),
+                    "size_bytes": result.size_bytes,
+                    "duration_secs": result.duration.as_secs_f64(),
+                    "verified": result.verification.as_ref().map(|v| v.is_ok()),
+                    "uploads": uploads,
+                });
+                match &result.verification {
+                    Some(Err(e)) => Err(format!("verification failed: {}", e)),
+                    _ => Ok(details),
+                }
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        self.jobs.update(self.id, |job| {
+            job.started.get_or_insert_with(Local::now);
+            job.finished = Some(Local::now());
+            job.outcome = Some(outcome);
+        });
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::instance;
+
+/// One JSON object per line, only ever appended to, kept across golem
+/// restarts.
+const EVENTS_FILE: &str = "events.jsonl";
+
+static WRITING: Mutex<()> = Mutex::new(());
+
+/// Notes a start, stop, crash, restart or watchdog decision, e.g.
+/// `record("crash", "exited unexpectedly", Some(1))`.
+pub fn record(event: &str, reason: &str, exit_code: Option<i32>) {
+    let entry = json!({
+        "time": Local::now().to_rfc3339(),
+        "event": event,
+        "reason": reason,
+        "exit_code": exit_code,
+    });
+    let _guard = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(instance::path(EVENTS_FILE))
+        .and_then(|mut file| writeln!(file, "{}", entry));
+    if let Err(e) = written {
+        warn!("Events: could not write {}: {}", EVENTS_FILE, e);
+    }
+}
+
+/// The last `count` entries, oldest first, narrowed to those whose event or
+/// reason mentions `filter` if given.
+pub fn entries(count: usize, filter: Option<&str>) -> Vec<Value> {
+    let Ok(content) = fs::read_to_string(instance::path(EVENTS_FILE)) else {
+        return Vec::new();
+    };
+    let filter = filter.map(str::to_lowercase);
+    let mut entries: Vec<Value> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|entry| {
+            let Some(filter) = &filter else {
+                return true;
+            };
+            ["event", "reason"]
+                .iter()
+                .any(|field| entry[field].as_str().is_some_and(|v| v.to_lowercase().contains(filter)))
+        })
+        .collect();
+    entries.drain(..entries.len().saturating_sub(count));
+    entries
+}
+
+/// `history [<count>] [<filter>]`; the last 20 entries by default.
+pub fn list(args: &[&str]) -> Vec<String> {
+    let (count, filter) = match args.split_first() {
+        Some((count, filter)) if count.parse::<usize>().is_ok() => (count.parse().unwrap_or_default(), filter),
+        _ => (20, args),
+    };
+    let filter = filter.join(" ");
+    let entries = entries(count, Some(filter.as_str()).filter(|f| !f.is_empty()));
+    if entries.is_empty() {
+        return vec!["Nothing recorded yet.".to_string()];
+    }
+    let text = |entry: &Value, field: &str| entry[field].as_str().unwrap_or_default().to_string();
+    entries
+        .iter()
+        .map(|entry| {
+            let time = DateTime::parse_from_rfc3339(&text(entry, "time"))
+                .map_or(text(entry, "time"), |t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+            let code = entry["exit_code"].as_i64().map_or(String::new(), |c| format!(" (exit code {})", c));
+            format!("{}  {:<17} {}{}", time, text(entry, "event"), text(entry, "reason"), code)
+        })
+        .collect()
+}
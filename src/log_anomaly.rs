@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+use tracing::warn;
+
+use crate::config::LogAnomalyConfig;
+use crate::messages::Messages;
+use crate::notify::{EventKind, Notifiers};
+use crate::server_log;
+
+/// Example lines quoted in the warning.
+const EXAMPLES: usize = 3;
+
+/// Counts the server's WARN and ERROR lines per minute and warns when a
+/// minute has far more of them than the minutes before it did, once per
+/// spike.
+#[derive(Default)]
+pub struct LogAnomaly {
+    /// Counts of the finished minutes, oldest first.
+    baseline: VecDeque<u32>,
+    /// The minute being counted (in minutes since the epoch), its count and
+    /// its first few lines.
+    minute: i64,
+    count: u32,
+    examples: Vec<String>,
+    alerting: bool,
+}
+
+impl LogAnomaly {
+    /// Forgets everything when the server stops; the next one starts afresh.
+    pub fn reset(&mut self) {
+        *self = LogAnomaly::default();
+    }
+
+    /// Counts a console line towards the current minute.
+    pub fn line(&mut self, line: &str) {
+        if !server_log::is_warning(line) {
+            return;
+        }
+        self.count += 1;
+        if self.examples.len() < EXAMPLES {
+            self.examples.push(server_log::message(line).to_string());
+        }
+    }
+
+    /// Called before the pass's lines are counted: once the minute is over,
+    /// compares it to the ones before and starts the next.
+    pub fn check(&mut self, now: DateTime<Local>, config: &LogAnomalyConfig, messages: &Messages, notifiers: &Notifiers) {
+        let minute = now.timestamp() / 60;
+        if self.minute == 0 {
+            self.minute = minute;
+        }
+        if minute <= self.minute {
+            return;
+        }
+        let (count, examples) = (self.count, std::mem::take(&mut self.examples));
+        self.minute = minute;
+        self.count = 0;
+
+        let wanted = config.baseline_minutes.max(2);
+        if self.baseline.len() >= wanted / 2 {
+            let n = self.baseline.len() as f64;
+            let mean = self.baseline.iter().map(|c| *c as f64).sum::<f64>() / n;
+            let deviation = (self.baseline.iter().map(|c| (*c as f64 - mean).powi(2)).sum::<f64>() / n).sqrt();
+            // A perfectly quiet log would make a single line a spike
+            let limit = mean + config.deviations * deviation.max(1.0);
+            let spike = count >= config.min_lines && count as f64 > limit;
+            if spike && !self.alerting {
+                warn!("Server log: {} warnings and errors in a minute, against {:.1} usually", count, mean);
+                let message = messages.get("log_anomaly", &[("count", count.to_string()), ("usual", format!("{:.1}", mean))]);
+                let fields = vec![(messages.get("log_anomaly_examples", &[]), examples.join("\n"))];
+                notifiers.send_with_fields(EventKind::LogAnomaly, &message, fields);
+            }
+            self.alerting = spike;
+        }
+        self.baseline.push_back(count);
+        while self.baseline.len() > wanted {
+            self.baseline.pop_front();
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use chrono::Local;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::LogFileConfig;
+
+/// The log file once the config asks for one; until then only stdout is written.
+static LOG_FILE: Mutex<Option<RotatingFile>> = Mutex::new(None);
+
+/// Local wall-clock time, matching the timestamps of the server's own log.
+struct LocalTime;
+
+impl FormatTime for LocalTime {
+    fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
+        write!(w, "{}", Local::now().format("%Y-%m-%d %H:%M:%S"))
+    }
+}
+
+/// Sends the golem's own messages to stdout, and to the log file once
+/// [`open_file`] has been called. `RUST_LOG` picks what is shown, e.g. `debug`
+/// or `info,rusty_golem::backup=debug`; `info` if unset.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_timer(LocalTime).with_target(false).with_ansi(false))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_timer(LocalTime)
+                .with_target(false)
+                .with_ansi(false)
+                .with_writer(|| FileWriter),
+        )
+        .init();
+}
+
+/// Starts appending to the configured log file.
+pub fn open_file(config: &LogFileConfig) -> io::Result<()> {
+    let file = RotatingFile::open(config)?;
+    *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    Ok(())
+}
+
+/// Hands each formatted event to the log file, if there is one.
+struct FileWriter;
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(file) = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            file.write(buf);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct RotatingFile {
+    path: String,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(config: &LogFileConfig) -> io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        Ok(RotatingFile {
+            path: config.path.clone(),
+            size: file.metadata()?.len(),
+            file,
+            max_bytes: config.max_size_mb.max(1) * 1024 * 1024,
+            keep: config.keep,
+        })
+    }
+
+    /// Failures go to stderr: logging them through tracing would come back here.
+    fn write(&mut self, buf: &[u8]) {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            if let Err(e) = self.rotate() {
+                eprintln!("Log file: could not rotate {}: {}", self.path, e);
+            }
+        }
+        match self.file.write_all(buf) {
+            Ok(()) => self.size += buf.len() as u64,
+            Err(e) => eprintln!("Log file: could not write {}: {}", self.path, e),
+        }
+    }
+
+    /// `<path>` becomes `<path>.1`, `<path>.1` becomes `<path>.2` and so on up
+    /// to `keep`; the oldest is dropped.
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = |n: usize| format!("{}.{}", self.path, n);
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(rotated(self.keep));
+            for n in (1..self.keep).rev() {
+                let _ = fs::rename(rotated(n), rotated(n + 1));
+            }
+            fs::rename(&self.path, rotated(1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
@@ -1,191 +1,1096 @@
-use std::fs;
-use std::io::{self, Write};
-use std::process::{Child, Command, Stdio};
+mod api;
+mod audit;
+mod availability;
+mod backup;
+mod cli;
+mod config;
+mod console;
+mod control;
+mod crash_logs;
+mod cron;
+mod dashboard;
+mod digest;
+mod discord_api;
+mod discord_chat;
+mod discord_commands;
+mod discord_gateway;
+mod disk_space;
+mod feed;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod heartbeat;
+mod history;
+mod http;
+mod influx;
+mod instance;
+mod ipc;
+mod jobs;
+mod lifecycle;
+mod log_anomaly;
+mod logging;
+mod memory_trend;
+mod messages;
+mod metrics;
+mod monitor;
+mod mqtt;
+mod notify;
+mod out_of_memory;
+mod prometheus;
+mod probe;
+mod queue;
+mod rate_limit;
+mod safe_mode;
+mod schedule;
+mod server;
+mod server_log;
+mod server_props;
+mod stagger;
+mod startup;
+mod status_file;
+mod status_message;
+mod status_page;
+mod template;
+mod watchdog;
+mod weekly;
+mod world_growth;
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
-
-use chrono::{Local, NaiveTime};
-use serde::Deserialize;
-use reqwest::blocking::Client;
-
-#[derive(Deserialize, Debug)]
-struct Config {
-    server_bat_path: String,
-    start_time: String,
-    end_time: String,
-    discord_webhook_url: String,
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use tracing::{debug, error, info, info_span, warn};
+
+use availability::Availability;
+use backup::Trigger;
+use config::{load_config, CleanExit, StopBackup};
+use control::{Command, Counters, SharedStatus, Status};
+use digest::DailyStats;
+use disk_space::DiskWatch;
+use feed::LogFeed;
+use heartbeat::Heartbeat;
+use instance::{Handle, Instances};
+use monitor::HeartbeatFile;
+use jobs::{BackupJob, BackupJobs};
+use log_anomaly::LogAnomaly;
+use memory_trend::MemoryTrend;
+use messages::Messages;
+use metrics::Metrics;
+use notify::{Event, EventKind, Notifiers};
+use probe::Probe;
+use queue::ActionQueue;
+use schedule::PlayWindow;
+use server::Server;
+use server_log::LogEvent;
+use stagger::StartGate;
+use status_file::StatusFile;
+use status_message::StatusMessage;
+use watchdog::{ExitClass, NextStart, Watchdog};
+use world_growth::WorldGrowth;
+
+/// Console lines kept in the shared status for the dashboard and `/logs`; as
+/// many as the server keeps.
+const STATUS_LOG_LINES: usize = 500;
+
+/// Asked of a server that stopped answering pings; any reply means it is
+/// not frozen.
+const LIVENESS_COMMAND: &str = "list";
+
+/// Metrics embed fields for lifecycle notifications, if enabled.
+fn lifecycle_fields(
+    config: &config::Config,
+    messages: &Messages,
+    metrics: &mut Metrics,
+    pid: Option<u32>,
+    uptime: Option<Duration>,
+    players: usize,
+) -> Vec<(String, String)> {
+    if !config.notifications.include_metrics {
+        return Vec::new();
+    }
+    metrics
+        .snapshot(&config.server_dir(), pid, uptime, players)
+        .fields(messages)
 }
 
-fn load_config() -> Config {
-    let content = fs::read_to_string("config.toml").expect("Failed to read config.toml");
-    toml::from_str(&content).expect("Failed to parse config.toml")
+/// When the watchdog will start the server again, for the notification.
+fn next_start(next: NextStart, now: chrono::DateTime<Local>, messages: &Messages) -> String {
+    match next {
+        NextStart::At(at) => messages.get(
+            "crash_next_start_at",
+            &[("delay", watchdog::format_delay((at - now).num_seconds())), ("time", at.format("%H:%M:%S").to_string())],
+        ),
+        NextStart::OutOfAttempts => messages.get("crash_next_start_none", &[]),
+        NextStart::Halted => messages.get("crash_next_start_halted", &[]),
+    }
 }
 
-fn send_discord_message(url: &str, message: &str) {
-    let client = Client::new();
-    let payload = serde_json::json!({
-        "content": message
-    });
-    
-    // Ignore errors for now to avoid crashing the main loop
-    let _ = client.post(url).json(&payload).send();
+/// Runs a backup (hot if a running server is given) and describes the outcome
+/// as a notification field. Failures are also raised as an alert of their own.
+/// Backup sets without a schedule of their own follow along. An API job, if
+/// given, is kept up to date with the world backup.
+fn backup_field(
+    config: &config::Config,
+    messages: &Messages,
+    notifiers: &Notifiers,
+    stats: &mut DailyStats,
+    server: Option<&mut Server>,
+    trigger: Trigger,
+    job: Option<&BackupJob>,
+) -> Option<(String, String)> {
+    let Some(backup_config) = config.backup.as_ref() else {
+        if let Some(job) = job {
+            job.finished(&Err(std::io::Error::other("no [backup] section in config.toml")));
+        }
+        return None;
+    };
+    if let Some(job) = job {
+        job.started();
+    }
+    let outcome = match server {
+        Some(server) => backup::create_hot(config, backup_config, server, trigger),
+        None => backup::create(config, backup_config, trigger),
+    };
+    if let Some(job) = job {
+        job.finished(&outcome);
+    }
+    let value = report_backup(messages, notifiers, stats, "world", outcome);
+    for set in backup_config.sets.iter().filter(|s| s.interval_minutes.is_none() && s.schedule.is_empty()) {
+        report_backup(messages, notifiers, stats, &set.name, backup::create_set(config, backup_config, set, trigger));
+    }
+    Some((messages.get("backup_field", &[]), value))
 }
 
-fn start_server(path: &str) -> io::Result<Child> {
-    if cfg!(target_os = "windows") {
-        Command::new("cmd")
-            .args(&["/C", path])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()
-    } else {
-        // Assume Linux/Unix
-        // Execute directly if executable, or use sh if needed.
-        // For .sh files, often need "sh script.sh" or "./script.sh".
-        // Using "sh -c" helps if path includes arguments or environment setup.
-        // But simply Command::new(path) works if it has shebang and execute perms.
-        // To be safe and mimicking "cmd /C", let's use "sh -c".
-        
-         Command::new("sh")
-            .arg("-c")
-            .arg(path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()
+/// Notifies about a finished (or failed) backup of `job` (the world or a
+/// set's name), records it in the stats store and returns a one-line summary.
+fn report_backup(
+    messages: &Messages,
+    notifiers: &Notifiers,
+    stats: &mut DailyStats,
+    job: &str,
+    outcome: std::io::Result<backup::BackupResult>,
+) -> String {
+    backup::record_outcome(job, &outcome);
+    match outcome {
+        Ok(result) => {
+            stats.record_backup(&result.file_name(), result.size_bytes);
+            let mut value = messages.get(
+                "backup_summary",
+                &[("file", result.file_name()), ("size", metrics::format_bytes(result.size_bytes))],
+            );
+            notifiers.send_with_fields(
+                EventKind::BackupCompleted,
+                &messages.get("backup_completed", &[("file", result.file_name())]),
+                result.fields(messages),
+            );
+            if let Some(Err(e)) = &result.verification {
+                let failed = messages.get("backup_verify_failed", &[("file", result.file_name()), ("error", e.clone())]);
+                notifiers.send(EventKind::BackupFailed, &failed);
+                value.push('\n');
+                value.push_str(&failed);
+            }
+            for (destination, outcome) in &result.uploads {
+                if let Err(e) = outcome {
+                    value.push('\n');
+                    value.push_str(&messages.get(
+                        "backup_upload_failed",
+                        &[("destination", destination.clone()), ("error", e.clone())],
+                    ));
+                }
+            }
+            value
+        }
+        Err(e) => {
+            error!("Backup failed: {}", e);
+            let failed = messages.get("backup_failed", &[("error", e.to_string())]);
+            notifiers.send(EventKind::BackupFailed, &failed);
+            failed
+        }
     }
 }
 
-fn send_command(child: &mut Child, command: &str) {
-    if let Some(stdin) = child.stdin.as_mut() {
-        let _ = writeln!(stdin, "{}", command);
+/// Stops the server if it runs, restores backup `name` (or, without one, rolls
+/// back to the newest pre-update backup) and announces it. The schedule starts
+/// the server again on the next pass if it should be up.
+fn restore_backup(
+    config: &config::Config,
+    messages: &Messages,
+    notifiers: &Notifiers,
+    stats: &mut DailyStats,
+    server_process: &mut Option<Server>,
+    name: Option<&str>,
+) {
+    let Some(backup_config) = config.backup.as_ref() else {
+        return;
+    };
+    let label = name.unwrap_or("the pre-update backup");
+    if let Some(mut server) = server_process.take() {
+        info!("Stopping server to restore {}...", label);
+        server.send_command(&format!("say {}", messages.get("ingame_restore", &[])));
+        let code = server.stop();
+        lifecycle::record("stop", "restoring a backup", code);
+        stats.record_stopped(server.started_at.elapsed());
     }
+    let outcome = match name {
+        Some(name) => backup::restore(config, backup_config, name),
+        None => backup::rollback(config, backup_config),
+    };
+    match outcome {
+        Ok(result) => {
+            let moved = result
+                .moved_aside
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let fields = if moved.is_empty() {
+                Vec::new()
+            } else {
+                vec![(messages.get("restore_moved_aside", &[]), moved)]
+            };
+            notifiers.send_with_fields(
+                EventKind::BackupRestored,
+                &messages.get("backup_restored", &[("file", result.name)]),
+                fields,
+            );
+        }
+        Err(e) => {
+            error!("Restore failed: {}", e);
+            notifiers.send(
+                EventKind::BackupFailed,
+                &messages.get("restore_failed", &[("file", label.to_string()), ("error", e.to_string())]),
+            );
+        }
+    }
+}
+
+fn hot_backup_interval(config: &config::Config) -> Option<Duration> {
+    config
+        .backup
+        .as_ref()
+        .and_then(|b| b.hot_interval_minutes)
+        .map(|m| Duration::from_secs(m * 60))
+}
+
+/// The weekly report's schedule, unless it is turned off. Checked with the
+/// rest of the config.
+fn weekly_report_schedule(config: &config::Config) -> Option<cron::Schedule> {
+    (!config.weekly_report.is_empty())
+        .then(|| cron::Schedule::parse(&config.weekly_report).unwrap_or_else(|err| panic!("Invalid weekly_report: {}", err)))
 }
 
-fn stop_server(child: &mut Child) {
-    send_command(child, "stop");
-    // Wait a bit for it to stop gracefully
-    // In a real production app we might want to wait on child.wait() with a timeout, 
-    // but std::process doesn't have a simple timeout wait. 
-    // We will just let the main loop handle the cleanup or wait endlessly if that's safer.
-    // For now, let's just send stop and let the watchdog/loop handle the rest.
-    let _ = child.wait(); 
+/// The world's cron schedules and each backup set's, by name. The config has
+/// been checked, so they all parse.
+fn backup_schedules(config: &config::Config) -> (Vec<cron::Schedule>, HashMap<String, Vec<cron::Schedule>>) {
+    let parse_schedules = |expressions: &[String]| -> Vec<cron::Schedule> {
+        expressions
+            .iter()
+            .map(|e| cron::Schedule::parse(e).unwrap_or_else(|err| panic!("Invalid backup schedule: {}", err)))
+            .collect()
+    };
+    let world = config.backup.as_ref().map_or(Vec::new(), |b| parse_schedules(&b.schedule));
+    let sets = config
+        .backup
+        .iter()
+        .flat_map(|b| &b.sets)
+        .map(|set| (set.name.clone(), parse_schedules(&set.schedule)))
+        .collect();
+    (world, sets)
 }
 
 fn main() {
+    logging::init();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if cli::run(&args) {
+        return;
+    }
+
     let config = load_config();
-    println!("Loaded config: {:?}", config);
+    if let Some(log_file) = &config.log_file {
+        if let Err(e) = logging::open_file(log_file) {
+            error!("Log file: could not open {}: {}", log_file.path, e);
+        }
+    }
+    debug!("Loaded config: {:?}", config);
+    let instances = Instances::default();
+    if config.servers.is_empty() {
+        supervise(config, None, instances);
+        return;
+    }
+    let gate = StartGate::new(&config.stagger);
+    let supervisors: Vec<_> = config
+        .servers
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let server_config = config.for_server(entry);
+            let gate = gate.clone();
+            let instances = instances.clone();
+            thread::Builder::new()
+                .name(entry.name.clone())
+                .spawn(move || supervise(server_config, Some((index, gate)), instances))
+                .expect("Failed to start a server's supervisor thread")
+        })
+        .collect();
+    for supervisor in supervisors {
+        let _ = supervisor.join();
+    }
+}
+
+/// Runs one server: the main loop. `instance` is its place in `[[servers]]`,
+/// with the gate it shares with the others, or None for the only one. The
+/// first also takes the console, the control socket and the remote
+/// interfaces, which reach every server through `instances`.
+fn supervise(mut config: config::Config, instance: Option<(usize, StartGate)>, instances: Instances) {
+    let (instance, gate) = instance.unzip();
+    let name = instance.map(|index| config.servers[index].name.clone());
+    let primary = instance.is_none_or(|index| index == 0);
+    let _span = instance.map(|index| {
+        let name = &config.servers[index].name;
+        instance::enter(name, primary);
+        info_span!("server", name = %name).entered()
+    });
+    let messages = Messages::from_config(&config);
+    let feed = LogFeed::default();
+    let mut notifiers = Notifiers::from_config(&config, &messages).with_feed(feed.clone());
+    if let Some(index) = instance {
+        notifiers = notifiers.with_server_name(config.servers[index].display_name());
+    }
+    let (command_tx, commands) = mpsc::channel();
+    let shared_status: SharedStatus = Arc::new(Mutex::new(Status::default()));
+    let queue = ActionQueue::default();
+    let availability = Availability::load();
+    let interpreter = console::Interpreter {
+        aliases: config.aliases.clone(),
+        availability: availability.clone(),
+        backup: config.backup.clone(),
+        messages: messages.clone(),
+        queue: queue.clone(),
+        status: shared_status.clone(),
+    };
+    let display_name = instance.map(|index| config.servers[index].display_name().to_string());
+    let escalations = notifiers.escalations().cloned();
+    let handle = Handle::new(name.clone(), display_name, command_tx, interpreter, feed.clone(), escalations);
+    let pulse = handle.pulse.clone();
+    instances.register(instance.unwrap_or(0), handle);
+    if let Some(bot) = config.discord_bot.as_ref().filter(|_| primary) {
+        let servers = config.servers.iter().map(|entry| entry.name.clone()).collect();
+        discord_commands::spawn(bot, servers, instances.clone());
+    }
+    if let Some(api_config) = config.api.as_ref().filter(|_| primary) {
+        api::spawn(api_config, instances.clone(), BackupJobs::default());
+    }
+    if let Some(mqtt_config) = config.mqtt.as_ref().filter(|_| primary) {
+        mqtt::spawn(mqtt_config, instances.clone());
+    }
+    if let Some(influx_config) = config.influxdb.as_ref().filter(|_| primary) {
+        influx::spawn(influx_config, instances.clone());
+    }
+    if let Some(grpc_config) = config.grpc.as_ref().filter(|_| primary) {
+        #[cfg(feature = "grpc")]
+        grpc::spawn(grpc_config, instances.clone());
+        #[cfg(not(feature = "grpc"))]
+        warn!("gRPC: [grpc] is ignored; this build has no gRPC support (build with --features grpc). {}", grpc_config.bind);
+    }
+    if primary {
+        HeartbeatFile::spawn(&config.monitor, instances.clone());
+        if !config.control_socket.is_empty() {
+            ipc::spawn(&config.control_socket, instances.clone());
+        }
+        console::spawn(instances.clone());
+    }
+    let mut metrics = Metrics::new();
+    // Slower-moving status figures, refreshed once a minute
+    let mut probed_at: Option<Instant> = None;
+    let mut tps = None;
+    let mut rss_bytes = None;
+    let mut host = None;
+    // Set by a lag warning until TPS is back above lag_tps
+    let mut lagging = false;
+    // Minutes in a row the server answered neither pings nor its console
+    let mut unresponsive_minutes = 0;
+    let mut last_backup = config.backup.as_ref().and_then(backup::last_backup);
+    let mut backup_jobs = backup::job_stats();
+    let mut counters = Counters::default();
+    // Set by a crash or restart command, so the next start counts as a restart
+    let mut restarting = false;
     
     // Parse times
-    let start_time = NaiveTime::parse_from_str(&config.start_time, "%H:%M").expect("Invalid start_time format");
-    let end_time = NaiveTime::parse_from_str(&config.end_time, "%H:%M").expect("Invalid end_time format");
+    let (start_time, end_time) = config.daily_hours().expect("checked when loaded");
+    let mut window = PlayWindow::new(start_time, end_time);
+    // Set by a stop command, so the stop notice says it wasn't the schedule
+    let mut stop_requested = false;
+    // A command that woke the loop early
+    let mut woken: Option<Command> = None;
     
-    let mut server_process: Option<Child> = None;
+    let mut server_process: Option<Server> = None;
     
-    // Warning states
-    let mut warned_10_min = false;
-    let mut warned_5_min = false;
-    let mut warned_1_min = false;
+    // Minutes before the stop already warned about
+    let mut warned: Vec<i64> = Vec::new();
     
-    // Watchdog history
-    let mut crash_timestamps: Vec<chrono::DateTime<Local>> = Vec::new();
+    let mut watchdog = Watchdog::new(&config.watchdog);
+    if let Some(name) = &name {
+        watchdog = watchdog.with_server_name(name);
+    }
+
+    // Daily digest, posted when the running window closes
+    let mut stats = DailyStats::default();
+    let mut was_running_time = false;
+    let mut disk_watch = DiskWatch::default();
+    let mut world_growth = WorldGrowth::load();
+    let mut log_anomaly = LogAnomaly::default();
+    let mut heartbeat = config.heartbeat.as_ref().filter(|_| primary).map(Heartbeat::spawn);
+    let probe = config.probe.as_ref().map(|probe_config| {
+        let address = probe_config.address.clone().unwrap_or_else(|| {
+            let server_dir = config.server_dir();
+            let ip = server_props::get(&server_dir, "server-ip").filter(|ip| !ip.is_empty());
+            let port = server_props::get(&server_dir, "server-port").unwrap_or_else(|| "25565".to_string());
+            format!("{}:{}", ip.as_deref().unwrap_or("127.0.0.1"), port)
+        });
+        Probe::spawn(probe_config, address, messages.clone(), notifiers.clone())
+    });
+    let mut memory_trend = config
+        .memory_trend
+        .as_ref()
+        .map(|c| MemoryTrend::new(c, messages.clone(), notifiers.clone(), queue.clone()));
+    // Set once the running server logs "Done", for the probe
+    let mut server_ready = false;
+
+    // Periodic hot backups while the server runs
+    let mut hot_interval = hot_backup_interval(&config);
+    let mut last_hot_backup = Instant::now();
+    // Set when an updated server jar is started, until it logs "Done"
+    let mut pending_update: Option<Instant> = None;
+    let mut update_backup_taken = false;
+    // Backup sets with their own interval, by name
+    let mut last_set_backup: HashMap<String, Instant> = HashMap::new();
+    // Cron-scheduled backups, checked for every minute since the last iteration
+    let (mut world_schedules, mut set_schedules) = backup_schedules(&config);
+    let mut last_schedule_check = Local::now();
+    let mut weekly_report = weekly_report_schedule(&config);
 
-    send_discord_message(&config.discord_webhook_url, "Rusty-Golem started.");
+    let mut status_file = config.status_file.as_deref().filter(|_| primary).map(StatusFile::new);
+    let mut status_message = config
+        .status_message
+        .as_ref()
+        .filter(|_| primary)
+        .map(|c| StatusMessage::new(c, &config.discord_webhook_url));
+
+    if primary {
+        notifiers.send(EventKind::GolemStarted, &messages.get("golem_started", &[]));
+    }
 
     loop {
         let now = Local::now();
-        let current_time = now.time();
-        
-        let is_running_time = if start_time <= end_time {
-             current_time >= start_time && current_time < end_time
-        } else {
-             current_time >= start_time || current_time < end_time
-        };
+        window.tick(now);
         
         let mut is_alive = false;
-        if let Some(child) = server_process.as_mut() {
-            match child.try_wait() {
-                Ok(Some(_)) => is_alive = false,
-                Ok(None) => is_alive = true,
-                Err(_) => is_alive = false,
+        let mut update_failed = false;
+        if let Some(server) = server_process.as_mut() {
+            log_anomaly.check(now, &config.log_anomaly, &messages, &notifiers);
+            for line in server.drain_lines() {
+                log_anomaly.line(&line);
+                match server_log::parse_line(&line) {
+                    Some(LogEvent::PlayerJoined(name)) => {
+                        stats.player_joined(&name);
+                        notifiers.send(EventKind::PlayerJoined, &messages.get("player_joined", &[("player", name)]));
+                    }
+                    Some(LogEvent::PlayerLeft(name)) => {
+                        stats.player_left(&name);
+                        notifiers.send(EventKind::PlayerLeft, &messages.get("player_left", &[("player", name)]));
+                    }
+                    Some(LogEvent::Done) => {
+                        server_ready = true;
+                        if let (Some(gate), Some(name)) = (&gate, &name) {
+                            gate.ready(name);
+                        }
+                        let took = server.boot_time().unwrap_or_else(|| server.started_at.elapsed());
+                        startup::record(took, &config.startup_trend, &messages, &notifiers);
+                        if safe_mode::active(&config.server_dir()) {
+                            server.send_command(&format!("say {}", messages.get("ingame_safe_mode", &[])));
+                        }
+                        if let Some(backup_config) = config.backup.as_ref().filter(|b| b.pre_update) {
+                            if pending_update.take().is_some() {
+                                info!("Update: the updated server started successfully.");
+                            }
+                            if let Err(e) = backup::mark_good(&config, backup_config) {
+                                warn!("Update: could not record the server jar: {}", e);
+                            }
+                            update_backup_taken = false;
+                        }
+                    }
+                    Some(LogEvent::Chat { .. }) | None => {}
+                }
+            }
+            match server.poll_exit() {
+                None => is_alive = true,
+                // Someone typed `stop` in its console, or a plugin shut it down
+                Some(Some(0)) => {
+                    thread::sleep(Duration::from_millis(200));
+                    server.drain_lines();
+                    lifecycle::record("stop", "exited cleanly by itself", Some(0));
+                    info!("Server exited cleanly (exit code 0) without being asked to.");
+                    stats.record_stopped(server.started_at.elapsed());
+                    server_process = None;
+                    let key = match (config.watchdog.policies.clean_exit, config.watchdog.on_clean_exit) {
+                        (Some(_), _) | (None, CleanExit::Restart) => match watchdog.down(ExitClass::CleanExit, now) {
+                            NextStart::Halted => "server_exited_leave",
+                            _ => {
+                                restarting = true;
+                                "server_exited_restart"
+                            }
+                        },
+                        (None, CleanExit::LeaveStopped) => {
+                            window.close();
+                            "server_exited_leave"
+                        }
+                        (None, CleanExit::Ask) => {
+                            watchdog.hold();
+                            if let Some(bot) = config.discord_bot.as_ref() {
+                                let entry = config.servers.iter().find(|entry| Some(&entry.name) == name.as_ref());
+                                discord_commands::ask_to_start(bot, &messages, entry);
+                            }
+                            "server_exited_ask"
+                        }
+                    };
+                    notifiers.send(EventKind::ServerStopping, &messages.get(key, &[]));
+                }
+                Some(code) => {
+                    // We never lose track of a process we stopped ourselves, so anything else is a crash
+                    // Pick up the final lines the reader thread saw before the pipe closed
+                    thread::sleep(Duration::from_millis(200));
+                    server.drain_lines();
+                    let last_lines = server.tail(crash_logs::SIGNATURE_LINES);
+                    // Some problems come back on every start; no point trying
+                    let blocked = crash_logs::unrecoverable(&last_lines);
+                    let oom = out_of_memory::detect(&last_lines, &config.server_dir(), instance.is_some(), server.started_at_wall).filter(|_| blocked.is_none());
+                    lifecycle::record("crash", if oom.is_some() { "out of memory" } else { "exited unexpectedly" }, code);
+                    let code = code.map_or("unknown".to_string(), |c| c.to_string());
+                    let (kind, key) = match oom {
+                        Some(_) => (EventKind::ServerOutOfMemory, "server_out_of_memory"),
+                        None => (EventKind::ServerCrashed, "server_crashed"),
+                    };
+                    error!("Server exited unexpectedly (exit code {}){}.", code, if oom.is_some() { ", out of memory" } else { "" });
+                    let message = messages.get(key, &[("code", code)]);
+                    let mut attachments = if config.notifications.attach_crash_logs {
+                        crash_logs::collect(
+                            &config.server_dir(),
+                            server.started_at_wall,
+                            &server.tail(config.notifications.crash_log_lines),
+                        )
+                    } else {
+                        Vec::new()
+                    };
+                    // The process is gone, so there is no RAM figure to report
+                    let mut fields = lifecycle_fields(&config, &messages, &mut metrics, None, Some(server.started_at.elapsed()), stats.online_count());
+                    fields.extend(crash_logs::report_fields(&config.server_dir(), server.started_at_wall, &messages));
+                    let next_start = match &oom {
+                        _ if blocked.is_some() => messages.get("crash_next_start_blocked", &[]),
+                        Some(oom) => {
+                            // Memory from the last sample, a minute old at most
+                            fields.extend(out_of_memory::respond(oom, &config, rss_bytes, &messages));
+                            if let Some(error_log) = oom.error_log.as_deref().filter(|_| config.notifications.attach_crash_logs) {
+                                attachments.extend(crash_logs::attachment(error_log));
+                            }
+                            next_start(watchdog.down(ExitClass::OutOfMemory, now), now, &messages)
+                        }
+                        None => next_start(watchdog.down(ExitClass::Crash, now), now, &messages),
+                    };
+                    fields.push((messages.get("crash_next_start", &[]), next_start));
+                    notifiers.dispatch(Event::new(kind, message).with_fields(fields).with_attachments(attachments));
+                    if let Some((cause, line)) = blocked {
+                        watchdog.unrecoverable(cause, &line, &messages, &notifiers);
+                    }
+                    stats.record_crash();
+                    counters.crashes += 1;
+                    restarting = true;
+                    stats.record_stopped(server.started_at.elapsed());
+                    if let Some(backup_config) = config.backup.as_ref() {
+                        match pulse.busy(|| backup::create_crash_state(&config, backup_config)) {
+                            Ok(Some(result)) => info!("Crash state saved as {}.", result.file_name()),
+                            Ok(None) => {}
+                            Err(e) => warn!("Could not save the crash state: {}", e),
+                        }
+                    }
+                    server_process = None;
+                    update_failed = pending_update.take().is_some();
+                }
             }
         }
+
+        if let Some(backup_config) = config.backup.as_ref() {
+            let timeout = Duration::from_secs(backup_config.update_start_timeout_minutes * 60);
+            if is_alive && pending_update.is_some_and(|t| t.elapsed() >= timeout) {
+                warn!("Update: the server did not finish starting in time.");
+                pending_update = None;
+                update_failed = true;
+            }
+            if update_failed {
+                if backup_config.auto_rollback {
+                    notifiers.send(EventKind::UpdateFailed, &messages.get("update_failed_rolling_back", &[]));
+                    pulse.busy(|| restore_backup(&config, &messages, &notifiers, &mut stats, &mut server_process, None));
+                    is_alive = false;
+                } else {
+                    notifiers.send(EventKind::UpdateFailed, &messages.get("update_failed", &[]));
+                }
+            }
+        }
+
+        let due = queue.take_due(is_alive, stats.online_count());
+        let pending: Vec<Command> = woken.take().into_iter().chain(commands.try_iter()).chain(due).collect();
+        for command in pending {
+            match command {
+                Command::Restore(name) => {
+                    pulse.busy(|| restore_backup(&config, &messages, &notifiers, &mut stats, &mut server_process, Some(&name)));
+                    is_alive = false;
+                }
+                Command::Rollback => {
+                    pulse.busy(|| restore_backup(&config, &messages, &notifiers, &mut stats, &mut server_process, None));
+                    pending_update = None;
+                    is_alive = false;
+                }
+                Command::Start => {
+                    window.open(now, config.manual_session_minutes);
+                    watchdog.rearm();
+                }
+                Command::WatchdogReset => {
+                    info!("Watchdog: reset; starting the server if it is down.");
+                    lifecycle::record("watchdog_reset", "requested", None);
+                    watchdog.rearm();
+                    window.open(now, config.manual_session_minutes);
+                }
+                Command::Stop => {
+                    window.close();
+                    stop_requested = true;
+                }
+                Command::Restart => {
+                    if let Some(mut server) = server_process.take().filter(|_| is_alive) {
+                        info!("Restarting server...");
+                        notifiers.send(EventKind::ServerStopping, &messages.get("server_restarting", &[]));
+                        let code = pulse.busy(|| server.stop());
+                        lifecycle::record("restart", "requested", code);
+                        stats.record_stopped(server.started_at.elapsed());
+                    }
+                    window.open(now, config.manual_session_minutes);
+                    watchdog.rearm();
+                    restarting = true;
+                    is_alive = false;
+                }
+                Command::Extend(minutes) => match window.extend(now, minutes) {
+                    Some(until) => {
+                        let time = until.format("%H:%M").to_string();
+                        info!("Session extended by {} minutes, until {}.", minutes, time);
+                        if let Some(server) = server_process.as_mut().filter(|_| is_alive) {
+                            server.send_command(&format!("say {}", messages.get("ingame_session_extended", &[("time", time.clone())])));
+                        }
+                        let placeholders = [("minutes", minutes.to_string()), ("time", time)];
+                        notifiers.send(EventKind::StopWarning, &messages.get("session_extended", &placeholders));
+                        warned.clear();
+                    }
+                    None => warn!("Not extending: the server is not scheduled to run now."),
+                },
+                Command::Backup(job) => {
+                    info!("Starting requested backup...");
+                    let server = server_process.as_mut().filter(|_| is_alive);
+                    pulse.busy(|| backup_field(&config, &messages, &notifiers, &mut stats, server, Trigger::Manual, job.as_ref()));
+                }
+                Command::PauseSchedule => {
+                    window.pause(now);
+                    info!("Schedule paused; the server stays as it is until `resume-schedule`.");
+                }
+                Command::ResumeSchedule => {
+                    window.resume();
+                    info!("Schedule resumed.");
+                }
+                Command::SetHours(changes) => {
+                    for (day, hours) in changes {
+                        if let Err(e) = window.set_hours(now, day, hours) {
+                            warn!("Could not save the schedule: {}", e);
+                        }
+                        let placeholders = [("day", schedule::day_name(day).to_string()), ("hours", window.hours(day).to_string())];
+                        let message = messages.get(if hours.is_some() { "schedule_changed" } else { "schedule_reset" }, &placeholders);
+                        info!("{}", message);
+                        notifiers.send(EventKind::ScheduleChanged, &message);
+                    }
+                }
+                Command::ApplyConfig(new_config) => {
+                    // Each server finds its own entry, wherever it moved to
+                    let derived = match &name {
+                        Some(name) => new_config.servers.iter().find(|entry| &entry.name == name).map(|entry| new_config.for_server(entry)),
+                        None => Some(*new_config),
+                    };
+                    let Some(derived) = derived else {
+                        warn!("Config: not applied, the edited config.toml no longer has this server.");
+                        continue;
+                    };
+                    config = derived;
+                    let (start_time, end_time) = config.daily_hours().expect("checked before it was sent");
+                    window.set_daily(now, start_time, end_time);
+                    hot_interval = hot_backup_interval(&config);
+                    (world_schedules, set_schedules) = backup_schedules(&config);
+                    weekly_report = weekly_report_schedule(&config);
+                    watchdog.set_config(&config.watchdog);
+                    // Threads spawned at startup keep the settings they were given
+                    info!(
+                        "Config: applied the edited config.toml. Notifications, messages, the API, Discord, MQTT \
+                         and gRPC follow it after the golem is restarted."
+                    );
+                }
+                Command::Console(line) => match server_process.as_mut().filter(|_| is_alive) {
+                    Some(server) => {
+                        info!("> {}", line);
+                        server.send_command(&line);
+                    }
+                    None => warn!("Not sent, the server is not running: {}", line),
+                },
+                Command::ConsoleLines(lines) => match server_process.as_mut().filter(|_| is_alive) {
+                    Some(server) => {
+                        for line in lines {
+                            info!("> {}", line);
+                            server.send_command(&line);
+                        }
+                    }
+                    None => warn!("Not sent, the server is not running: {}", lines.join("; ")),
+                },
+                Command::Query(line, reply) => {
+                    let answer = server_process.as_mut().filter(|_| is_alive).map(|server| {
+                        info!("> {}", line);
+                        server.query(&line, Duration::from_secs(2))
+                    });
+                    let _ = reply.send(answer);
+                }
+            }
+        }
+        let is_running_time = window.is_open(now);
+        availability.record(now, is_alive, is_running_time);
+        disk_watch.check(&config, &messages, &notifiers);
+        world_growth.check(&config, now, !is_alive, &messages, &notifiers);
+        server_ready &= is_alive;
+        if !is_alive {
+            log_anomaly.reset();
+        }
+        if let Some(probe) = &probe {
+            probe.set_active(server_ready);
+        }
+        pulse.beat();
+
+        if let Some(backup_config) = config.backup.as_ref() {
+            for set in &backup_config.sets {
+                let Some(minutes) = set.interval_minutes else {
+                    continue;
+                };
+                let due = last_set_backup
+                    .get(&set.name)
+                    .is_none_or(|t| t.elapsed() >= Duration::from_secs(minutes * 60));
+                if due {
+                    info!("Starting scheduled backup of {}...", set.name);
+                    let outcome = pulse.busy(|| backup::create_set(&config, backup_config, set, Trigger::Interval));
+                    report_backup(&messages, &notifiers, &mut stats, &set.name, outcome);
+                    last_set_backup.insert(set.name.clone(), Instant::now());
+                }
+            }
+
+            let fired = |schedules: &[cron::Schedule]| schedules.iter().any(|s| s.fired_between(last_schedule_check, now));
+            if fired(&world_schedules) {
+                match server_process.as_mut().filter(|_| is_alive) {
+                    Some(server) => {
+                        info!("Starting cron hot backup...");
+                        pulse.busy(|| backup_field(&config, &messages, &notifiers, &mut stats, Some(server), Trigger::Cron, None));
+                    }
+                    None => {
+                        info!("Starting cron backup while the server is down...");
+                        pulse.busy(|| backup_field(&config, &messages, &notifiers, &mut stats, None, Trigger::Cron, None));
+                    }
+                }
+            }
+            for set in &backup_config.sets {
+                if set_schedules.get(&set.name).is_some_and(|s| fired(s)) {
+                    info!("Starting cron backup of {}...", set.name);
+                    let outcome = pulse.busy(|| backup::create_set(&config, backup_config, set, Trigger::Cron));
+                    report_backup(&messages, &notifiers, &mut stats, &set.name, outcome);
+                }
+            }
+        }
+        if weekly_report.as_ref().is_some_and(|s| s.fired_between(last_schedule_check, now)) {
+            info!("Posting the weekly report...");
+            let fields = weekly::fields(&availability, &messages);
+            notifiers.send_with_fields(EventKind::WeeklyReport, &messages.get("weekly_title", &[]), fields);
+        }
+        last_schedule_check = now;
+
+        if probed_at.is_none_or(|t| t.elapsed() >= Duration::from_secs(60)) {
+            probed_at = Some(Instant::now());
+            let server = server_process.as_mut().filter(|_| is_alive);
+            rss_bytes = server.as_ref().and_then(|s| metrics.process_tree_rss(s.pid()));
+            let answer = match (server, &config.tps_command) {
+                (Some(server), Some(command)) => server.query(command, Duration::from_secs(2)),
+                _ => Vec::new(),
+            };
+            tps = metrics::parse_tps(&answer);
+            let sampled = metrics.host();
+            host = Some(sampled);
+            if let Some(trend) = memory_trend.as_mut() {
+                match rss_bytes {
+                    Some(rss) => trend.sample(now, rss, sampled.memory_total, window.closes_at(now)),
+                    None => trend.reset(),
+                }
+            }
+            match (tps, config.lag_tps) {
+                (Some(tps), Some(limit)) if tps < limit && !lagging => {
+                    lagging = true;
+                    warn!("TPS is down to {:.1}; host: {}", tps, sampled.summary());
+                    // A starved machine points away from the server itself
+                    let key = if sampled.starved() { "lag_host_busy" } else { "lag" };
+                    let message = messages.get(key, &[("tps", format!("{:.1}", tps))]);
+                    let fields = vec![(messages.get("status_host", &[]), sampled.summary())];
+                    notifiers.send_with_fields(EventKind::Lag, &message, fields);
+                }
+                (Some(tps), Some(limit)) if tps >= limit => lagging = false,
+                _ => {}
+            }
+            let silent = probe.as_ref().is_some_and(Probe::unanswered);
+            let frozen = server_process
+                .as_mut()
+                .filter(|_| server_ready && silent)
+                .is_some_and(|server| server.query(LIVENESS_COMMAND, Duration::from_secs(5)).is_empty());
+            unresponsive_minutes = if frozen { unresponsive_minutes + 1 } else { 0 };
+            let freeze_checks = config.probe.as_ref().map_or(0, |p| p.freeze_checks);
+            if freeze_checks > 0 && unresponsive_minutes >= freeze_checks {
+                if let Some(mut server) = server_process.take() {
+                    unresponsive_minutes = 0;
+                    error!("The server has not answered for {} minutes; killing it.", freeze_checks);
+                    let code = server.kill();
+                    lifecycle::record("freeze", &format!("no answer for {} minutes", freeze_checks), code);
+                    let attachments = if config.notifications.attach_crash_logs {
+                        crash_logs::collect(
+                            &config.server_dir(),
+                            server.started_at_wall,
+                            &server.tail(config.notifications.crash_log_lines),
+                        )
+                    } else {
+                        Vec::new()
+                    };
+                    let mut fields = lifecycle_fields(&config, &messages, &mut metrics, None, Some(server.started_at.elapsed()), stats.online_count());
+                    fields.push((messages.get("crash_next_start", &[]), next_start(watchdog.down(ExitClass::Freeze, now), now, &messages)));
+                    let message = messages.get("server_frozen", &[("minutes", freeze_checks.to_string())]);
+                    notifiers.dispatch(
+                        Event::new(EventKind::ServerFrozen, message)
+                            .with_fields(fields)
+                            .with_attachments(attachments),
+                    );
+                    counters.freezes += 1;
+                    restarting = true;
+                    stats.record_stopped(server.started_at.elapsed());
+                    is_alive = false;
+                    server_ready = false;
+                }
+            }
+            last_backup = config.backup.as_ref().and_then(backup::last_backup);
+            backup_jobs = backup::job_stats();
+            if is_alive {
+                history::append("players", now, &stats.online_count().to_string());
+            }
+            if let Some(tps) = tps {
+                let mspt = metrics::parse_mspt(&answer).map_or(String::new(), |ms| format!("{:.1}", ms));
+                history::append("tps", now, &format!("{:.1},{}", tps, mspt));
+            }
+        }
+        let status = Status {
+            online: is_alive,
+            // Up while it should be, or down because it should be
+            healthy: is_alive || !is_running_time,
+            online_since: server_process.as_ref().filter(|_| is_alive).map(|s| s.started_at_wall.into()),
+            pid: server_process.as_ref().filter(|_| is_alive).map(Server::pid),
+            players: stats.online_count(),
+            player_names: stats.online_players(),
+            tps,
+            rss_bytes,
+            host,
+            latency: probe.as_ref().and_then(Probe::latency),
+            last_backup: last_backup.clone(),
+            backup_jobs: backup_jobs.clone(),
+            closes_at: window.closes_at(now),
+            opens_at: window.opens_at(now).filter(|_| !window.is_paused()),
+            schedule_paused: window.is_paused(),
+            week: window.week(),
+            log_tail: server_process.as_ref().map(|s| s.tail(STATUS_LOG_LINES)).unwrap_or_default(),
+            counters,
+        };
+        if let Ok(mut shared) = shared_status.lock() {
+            *shared = status;
+        }
+        // The first server reports for all of them
+        let servers = if primary { instances.all() } else { Vec::new() };
+        if let Some(status_message) = status_message.as_mut() {
+            let embeds: Vec<_> = servers
+                .iter()
+                .map(|server| (server.titled(messages.get("status_title", &[])), server.status().fields(&messages)))
+                .collect();
+            status_message.update_if_due(&embeds);
+        }
+        if let Some(status_file) = status_file.as_mut() {
+            status_file.write(&servers);
+        }
+        if let Some(heartbeat) = heartbeat.as_mut().filter(|_| servers.iter().all(|server| server.status().healthy)) {
+            heartbeat.beat();
+        }
         
         if !is_alive {
-            if is_running_time {
-                 // Check watchdog limits
-                 crash_timestamps.retain(|&t| (now - t).num_minutes() <= 5);
-                 
-                 if crash_timestamps.len() >= 3 {
-                      println!("Watchdog: Too many crashes (3 in 5 mins). Stopping auto-restart.");
-                      send_discord_message(&config.discord_webhook_url, "Watchdog: Server crashed 3 times. Giving up.");
-                      thread::sleep(Duration::from_secs(60));
-                      continue; 
+            if !is_running_time {
+                 // The window closed before the server came back
+                 restarting = false;
+                 watchdog.reset();
+            } else if watchdog.allows_start(now, &messages, &notifiers)
+                && gate.as_ref().zip(name.as_deref()).is_none_or(|(gate, name)| gate.try_start(name))
+            {
+                 let updated = config
+                     .backup
+                     .as_ref()
+                     .filter(|b| b.pre_update && backup::jar_changed(&config, b));
+                 if let Some(backup_config) = updated {
+                      if !update_backup_taken {
+                           info!("Update: the server jar changed; taking a pre-update backup first...");
+                           report_backup(&messages, &notifiers, &mut stats, "world", pulse.busy(|| backup::create_pre_update(&config, backup_config)));
+                           update_backup_taken = true;
+                      }
                  }
+
+                 // After enough crashes in a row, a reduced profile; otherwise the usual one
+                 let server_dir = config.server_dir();
+                 let safe = config.watchdog.safe_mode.as_ref().filter(|s| watchdog.safe_mode() || watchdog.streak() >= s.after_crashes);
+                 let switched = match safe {
+                     Some(safe) => safe_mode::enter(&server_dir, safe),
+                     None => safe_mode::leave(&server_dir),
+                 };
+                 if let Err(e) = switched {
+                      warn!("Safe mode: could not switch the launch profile: {}", e);
+                 }
+                 let script = safe.and_then(|s| s.start_script.as_deref()).unwrap_or(&config.server_bat_path);
+                 let starting = match safe {
+                     Some(_) => {
+                          warn!("Starting server in safe mode after {} crashes in a row...", watchdog.streak());
+                          messages.get("server_starting_safe_mode", &[("crashes", watchdog.streak().to_string())])
+                     }
+                     None => {
+                          info!("Starting server...");
+                          messages.get("server_starting", &[])
+                     }
+                 };
+                 let fields = lifecycle_fields(&config, &messages, &mut metrics, None, None, 0);
+                 notifiers.send_with_fields(EventKind::ServerStarting, &starting, fields);
                  
-                 println!("Starting server...");
-                 send_discord_message(&config.discord_webhook_url, "Starting Minecraft Server...");
-                 
-                 match start_server(&config.server_bat_path) {
-                     Ok(child) => {
-                         server_process = Some(child);
-                         crash_timestamps.push(now);
+                 match Server::start(script, feed.clone()) {
+                     Ok(server) => {
+                         server_process = Some(server);
+                         if updated.is_some() {
+                              pending_update = Some(Instant::now());
+                         }
+                         let reason = match (safe, restarting) {
+                             (Some(_), _) => "restart in safe mode",
+                             (None, true) => "restart",
+                             (None, false) => "running window open",
+                         };
+                         lifecycle::record("start", reason, None);
+                         stats.record_start();
+                         counters.starts += 1;
+                         if restarting {
+                              counters.restarts += 1;
+                              restarting = false;
+                         }
+                         last_hot_backup = Instant::now();
+                         watchdog.attempted(now, true);
                          // Reset warnings
-                         warned_10_min = false;
-                         warned_5_min = false;
-                         warned_1_min = false;
+                         warned.clear();
                      }
                      Err(e) => {
-                         println!("Failed to start: {}", e);
-                         crash_timestamps.push(now);
+                         error!("Failed to start: {}", e);
+                         notifiers.send(EventKind::ServerStartFailed, &messages.get("server_start_failed", &[("error", e.to_string())]));
+                         lifecycle::record("start_failed", &e.to_string(), None);
+                         watchdog.attempted(now, false);
                      }
                  }
-            } else {
-                 if server_process.is_some() {
-                     server_process = None;
-                 }
             }
         } else {
              // Alive
+             if let Some(server) = server_process.as_ref() {
+                 watchdog.up(server.started_at.elapsed());
+             }
              if !is_running_time {
-                 println!("Time to stop. Stopping server...");
-                 send_discord_message(&config.discord_webhook_url, "Stopping Minecraft Server (Schedule)...");
-                 if let Some(mut child) = server_process.take() {
-                      stop_server(&mut child);
-                 }
-             } else {
-                 let minutes_left = if start_time <= end_time {
-                      (end_time - current_time).num_minutes()
-                 } else {
-                      if current_time < end_time {
-                          (end_time - current_time).num_minutes()
-                      } else {
-                          (end_time - current_time).num_minutes() + 24 * 60
+                 info!("Time to stop. Stopping server...");
+                 let mut fields = lifecycle_fields(
+                     &config,
+                     &messages,
+                     &mut metrics,
+                     server_process.as_ref().map(|s| s.pid()),
+                     server_process.as_ref().map(|s| s.started_at.elapsed()),
+                     stats.online_count(),
+                 );
+                 let on_stop = config.backup.as_ref().map_or(StopBackup::Off, |b| b.on_stop);
+                 if let Some(mut server) = server_process.take() {
+                      if on_stop == StopBackup::Before {
+                           fields.extend(pulse.busy(|| backup_field(&config, &messages, &notifiers, &mut stats, Some(&mut server), Trigger::Stop, None)));
                       }
-                 };
-                 
-                 if minutes_left == 10 && !warned_10_min {
-                      if let Some(child) = server_process.as_mut() {
-                          send_command(child, "say Server will stop in 10 minutes!");
-                          warned_10_min = true;
+                      // With an "after" backup the message waits until the archive exists
+                      let stopping = messages.get(if stop_requested { "server_stopping_requested" } else { "server_stopping" }, &[]);
+                      if on_stop != StopBackup::After {
+                           notifiers.send_with_fields(EventKind::ServerStopping, &stopping, fields.clone());
+                      }
+                      let code = pulse.busy(|| server.stop());
+                      lifecycle::record("stop", if stop_requested { "requested" } else { "running window closed" }, code);
+                      stats.record_stopped(server.started_at.elapsed());
+                      if on_stop == StopBackup::After {
+                           fields.extend(pulse.busy(|| backup_field(&config, &messages, &notifiers, &mut stats, None, Trigger::Stop, None)));
+                           notifiers.send_with_fields(EventKind::ServerStopping, &stopping, fields);
                       }
                  }
-                 else if minutes_left == 5 && !warned_5_min {
-                      if let Some(child) = server_process.as_mut() {
-                          send_command(child, "say Server will stop in 5 minutes!");
-                          warned_5_min = true;
+                 stop_requested = false;
+             } else {
+                 if let (Some(interval), Some(server)) = (hot_interval, server_process.as_mut()) {
+                      if last_hot_backup.elapsed() >= interval {
+                           info!("Starting scheduled hot backup...");
+                           pulse.busy(|| backup_field(&config, &messages, &notifiers, &mut stats, Some(server), Trigger::Interval, None));
+                           last_hot_backup = Instant::now();
                       }
                  }
-                 else if minutes_left == 1 && !warned_1_min {
-                      if let Some(child) = server_process.as_mut() {
-                          send_command(child, "say Server will stop in 1 minute!");
-                          warned_1_min = true;
+
+                 let minutes_left = window.closes_at(now).map_or(i64::MAX, |closes| (closes - now).num_minutes());
+                 
+                 if config.stop_warning_minutes.contains(&minutes_left) && !warned.contains(&minutes_left) {
+                      if let Some(server) = server_process.as_mut() {
+                          let (ingame, notice) = if minutes_left == 1 {
+                              (messages.get("ingame_stop_warning_last", &[]), messages.get("stop_warning_last", &[]))
+                          } else {
+                              let minutes = [("minutes", minutes_left.to_string())];
+                              (messages.get("ingame_stop_warning", &minutes), messages.get("stop_warning", &minutes))
+                          };
+                          server.send_command(&format!("say {}", ingame));
+                          notifiers.send(EventKind::StopWarning, &notice);
+                          warned.push(minutes_left);
                       }
                  }
              }
         }
         
-        thread::sleep(Duration::from_secs(10));
+        if was_running_time && !is_running_time {
+             let mut fields = stats.fields(&messages);
+             fields.extend(availability.fields(&messages));
+             notifiers.send_with_fields(EventKind::DailyDigest, &messages.get("digest_title", &[]), fields);
+             stats = DailyStats::default();
+        }
+        was_running_time = is_running_time;
+
+        // Sleep, but wake up at once for a command
+        woken = match commands.recv_timeout(Duration::from_secs(10)) {
+            Ok(command) => Some(command),
+            Err(RecvTimeoutError::Timeout) => None,
+            // Every command source is gone (e.g. stdin closed); just sleep
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(Duration::from_secs(10));
+                None
+            }
+        };
     }
 }
@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Duration, Local};
+use tracing::warn;
+
+use crate::config::MemoryTrendConfig;
+use crate::messages::Messages;
+use crate::metrics::format_bytes;
+use crate::notify::{EventKind, Notifiers};
+use crate::queue::{Action, ActionQueue, When};
+
+/// Fewer minutes of samples than this say nothing about a trend.
+const MIN_SPAN_MINUTES: i64 = 60;
+/// How far ahead to look when the running window has no end in sight.
+const OPEN_ENDED_HOURS: i64 = 6;
+
+/// Fits a line through the server's recent memory samples and warns, once per
+/// server run, when it will cross the limit before the session ends.
+pub struct MemoryTrend {
+    config: MemoryTrendConfig,
+    messages: Messages,
+    notifiers: Notifiers,
+    queue: ActionQueue,
+    samples: VecDeque<(DateTime<Local>, u64)>,
+    warned: bool,
+}
+
+impl MemoryTrend {
+    pub fn new(config: &MemoryTrendConfig, messages: Messages, notifiers: Notifiers, queue: ActionQueue) -> MemoryTrend {
+        MemoryTrend { config: config.clone(), messages, notifiers, queue, samples: VecDeque::new(), warned: false }
+    }
+
+    /// Forgets the samples of a server that has stopped.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.warned = false;
+    }
+
+    /// Takes the minute's RSS sample. The limit is the configured one or else
+    /// `host_memory`; `ends_at` is when the running window closes.
+    pub fn sample(&mut self, now: DateTime<Local>, rss: u64, host_memory: u64, ends_at: Option<DateTime<Local>>) {
+        let config = &self.config;
+        let limit = config.limit_mb.map_or(host_memory, |mb| mb * 1024 * 1024);
+        self.samples.push_back((now, rss));
+        let window = Duration::minutes(config.window_minutes.max(MIN_SPAN_MINUTES as u64) as i64);
+        while self.samples.front().is_some_and(|(t, _)| now - *t > window) {
+            self.samples.pop_front();
+        }
+        let Some(&(first, _)) = self.samples.front() else {
+            return;
+        };
+        if self.warned || limit == 0 || now - first < Duration::minutes(MIN_SPAN_MINUTES) || rss >= limit {
+            return;
+        }
+        let Some((slope, intercept)) = fit(first, &self.samples) else {
+            return;
+        };
+        if slope <= 0.0 {
+            return;
+        }
+        let ends_at = ends_at.unwrap_or(now + Duration::hours(OPEN_ENDED_HOURS));
+        let projected = intercept + slope * (ends_at - first).num_seconds() as f64;
+        if projected < limit as f64 {
+            return;
+        }
+        self.warned = true;
+        let hits_at = first + Duration::seconds(((limit as f64 - intercept) / slope) as i64);
+        let per_hour = format_bytes((slope * 3600.0) as u64);
+        warn!(
+            "Memory: growing by {}/h, at {} now; {} would be reached around {}",
+            per_hour,
+            format_bytes(rss),
+            format_bytes(limit),
+            hits_at.format("%H:%M")
+        );
+        let key = if config.restart { "memory_growth_restart" } else { "memory_growth" };
+        let message = self.messages.get(
+            key,
+            &[
+                ("rss", format_bytes(rss)),
+                ("rate", per_hour),
+                ("limit", format_bytes(limit)),
+                ("time", hits_at.format("%H:%M").to_string()),
+            ],
+        );
+        self.notifiers.send(EventKind::MemoryGrowth, &message);
+        if config.restart {
+            self.queue.add(Action::Restart, When::Empty, "memory trend");
+        }
+    }
+}
+
+/// Least squares over the samples, with time in seconds since `first`:
+/// bytes per second and the bytes at `first`.
+fn fit(first: DateTime<Local>, samples: &VecDeque<(DateTime<Local>, u64)>) -> Option<(f64, f64)> {
+    let n = samples.len() as f64;
+    let points: Vec<(f64, f64)> = samples.iter().map(|(t, rss)| ((*t - first).num_seconds() as f64, *rss as f64)).collect();
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let slope = covariance / variance;
+    Some((slope, mean_y - slope * mean_x))
+}
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use crate::config::Config;
+use crate::template;
+
+type Catalog = &'static [(&'static str, &'static str)];
+
+const EN: Catalog = &[
+    ("golem_started", "Rusty-Golem started."),
+    ("golem_unresponsive", "The golem's heartbeat was {{seconds}} seconds old; the monitor killed it and started a fresh one."),
+    ("test_notification", "This is a test notification from Rusty-Golem."),
+    ("server_starting", "Starting Minecraft Server..."),
+    ("server_starting_safe_mode", "Starting Minecraft Server in safe mode after {{crashes}} crashes in a row. Some settings stay reduced until it is next started normally."),
+    ("ingame_safe_mode", "The server is running in safe mode after repeated crashes; some settings are reduced."),
+    ("server_start_failed", "Failed to start Minecraft Server: {{error}}"),
+    ("server_stopping", "Stopping Minecraft Server (Schedule)..."),
+    ("server_stopping_requested", "Stopping Minecraft Server (on request)..."),
+    ("server_restarting", "Restarting Minecraft Server (on request)..."),
+    ("session_extended", "The session was extended by {{minutes}} minutes; the server now stops at {{time}}."),
+    ("ingame_session_extended", "The session was extended! The server now stops at {{time}}."),
+    ("schedule_changed", "The schedule changed: {{day}} is now {{hours}}."),
+    ("lag", "The server is lagging: TPS is down to {{tps}}."),
+    ("lag_host_busy", "The server is lagging (TPS {{tps}}), but the machine itself is overloaded, so the cause may lie outside the server."),
+    ("probe_slow", "The server port ({{address}}) took {{ms}} ms to answer a status ping."),
+    ("probe_failing", "{{percent}}% of the recent status pings to the server port ({{address}}) failed or were too slow."),
+    ("disk_space_low_world", "Only {{free}} is free on the world drive ({{path}}), below {{floor}}."),
+    ("disk_space_low_backup", "Only {{free}} is free on the backup drive ({{path}}), below {{floor}}."),
+    ("log_anomaly", "The server logged {{count}} warnings and errors in the last minute, against {{usual}} a minute before. A plugin or mod may be misbehaving."),
+    ("log_anomaly_examples", "First lines"),
+    ("world_growth", "The world grew by {{grown}} since {{since}}, to {{size}}. Growth like this often comes from a chunk-loading machine or someone flying out to new terrain."),
+    ("memory_growth", "The server's memory keeps growing ({{rss}} now, +{{rate}} per hour) and would reach {{limit}} around {{time}}, before the session ends. A restart would clear it."),
+    ("memory_growth_restart", "The server's memory keeps growing ({{rss}} now, +{{rate}} per hour) and would reach {{limit}} around {{time}}, before the session ends. It will restart once nobody is on."),
+    ("startup_slow", "The server took {{took}} to start, {{percent}}% longer than its usual {{usual}}. A growing world or plugin database is the usual cause."),
+    ("schedule_reset", "The schedule changed: {{day}} is back to the daily hours ({{hours}})."),
+    ("server_crashed", "Server crashed (exit code {{code}})."),
+    ("server_frozen", "The server stopped answering (no status pings or console replies for {{minutes}} minutes) and was killed."),
+    ("crash_description", "Crash"),
+    ("crash_cause", "Exception"),
+    ("crash_suspects", "Suspected mods"),
+    ("crash_next_start", "Next start"),
+    ("server_exited_restart", "The server stopped by itself (exit code 0), not as a crash. Starting it again."),
+    ("server_exited_leave", "The server stopped by itself (exit code 0), not as a crash. It stays off until it is next scheduled."),
+    ("server_exited_ask", "The server stopped by itself (exit code 0), not as a crash. It stays off unless someone starts it."),
+    ("crash_next_start_at", "In {{delay}}, at {{time}}"),
+    ("crash_next_start_none", "None, the watchdog has used up its attempts"),
+    ("crash_next_start_blocked", "None, restarting cannot fix this"),
+    ("crash_next_start_halted", "None, the policy for this leaves it down"),
+    ("server_out_of_memory", "Server ran out of memory (exit code {{code}})."),
+    ("oom_error", "Error"),
+    ("oom_heap_limit", "Heap limit (-Xmx)"),
+    ("oom_memory_used", "Memory in use before"),
+    ("oom_heap_next", "Heap for the next start"),
+    ("oom_heap_raised", "Raised to {{heap}}"),
+    ("oom_heap_ceiling", "{{heap}}, already at the ceiling"),
+    ("oom_heap_not_found", "Unchanged, the start script sets no -Xmx"),
+    ("oom_heap_failed", "Unchanged, the start script could not be edited"),
+    ("player_joined", "{{player}} joined the game."),
+    ("player_left", "{{player}} left the game."),
+    ("stop_warning", "Server will stop in {{minutes}} minutes."),
+    ("stop_warning_last", "Server will stop in 1 minute."),
+    ("ingame_stop_warning", "Server will stop in {{minutes}} minutes!"),
+    ("ingame_stop_warning_last", "Server will stop in 1 minute!"),
+    ("watchdog_gave_up", "Watchdog: Server went down {{crashes}} times in {{minutes}} minutes. Giving up."),
+    ("watchdog_retry_later", "Watchdog: Server went down {{crashes}} times in {{minutes}} minutes. Trying again at {{time}}."),
+    ("watchdog_daily_limit", "Watchdog: The server has been restarted automatically {{restarts}} times today, the daily limit. It stays down, today and after, until someone starts it or runs `watchdog reset`."),
+    ("watchdog_fallback", "Watchdog: Server went down {{crashes}} times in {{minutes}} minutes. Running {{script}}."),
+    ("watchdog_fallback_failed", "Watchdog: The fallback script failed ({{error}}). Giving up."),
+    ("watchdog_unrecoverable", "Watchdog: Not restarting the server: {{cause}} Starting it again cannot fix that; start it by hand once it is fixed."),
+    ("watchdog_unrecoverable_line", "Console"),
+    ("unrecoverable_port", "its port is already in use, probably by another server."),
+    ("unrecoverable_world", "the world was saved by a newer Minecraft version."),
+    ("unrecoverable_dependency", "a mod or plugin is missing a dependency."),
+    ("unrecoverable_java", "the server needs a newer Java version."),
+    ("watchdog_gave_up_cooldown", "Watchdog: Server went down {{crashes}} times in {{minutes}} minutes. Pausing restarts; one more try at {{time}}."),
+    ("watchdog_fallback_failed_cooldown", "Watchdog: The fallback script failed ({{error}}). Pausing restarts; one more try at {{time}}."),
+    ("flood_summary", "{{message}} (x{{count}} in {{minutes}} min)"),
+    ("escalation_message", "[Unacknowledged for {{minutes}} min] {{message}}"),
+    ("flood_suppressed", "{{count}} notification(s) suppressed by flood protection."),
+    ("digest_title", "Daily summary"),
+    ("digest_uptime", "Uptime"),
+    ("digest_restarts", "Restarts"),
+    ("digest_crashes", "Crashes"),
+    ("digest_peak_players", "Peak players"),
+    ("digest_unique_players", "Unique players"),
+    ("digest_backups", "Backups"),
+    ("digest_availability", "Availability"),
+    ("digest_availability_week", "Availability, last 7 days"),
+    ("digest_availability_value", "{{percent}} ({{down}} down in the window)"),
+    ("weekly_title", "Weekly report"),
+    ("weekly_uptime", "Hours run"),
+    ("weekly_unique_players", "Unique players"),
+    ("weekly_playtime", "Playtime"),
+    ("weekly_crashes", "Crashes"),
+    ("weekly_average_tps", "Average TPS"),
+    ("weekly_world_size", "World size"),
+    ("weekly_world_size_value", "{{size}} ({{change}} this week)"),
+    ("backup_field", "Backup"),
+    ("backup_summary", "{{file}} ({{size}})"),
+    ("backup_failed", "Backup failed: {{error}}"),
+    ("backup_verify_failed", "Backup {{file}} failed verification: {{error}}"),
+    ("backup_completed", "Backup finished: {{file}}"),
+    ("backup_size", "Size"),
+    ("backup_size_change", "{{size}} ({{change}} vs. previous)"),
+    ("backup_duration", "Duration"),
+    ("backup_destinations", "Stored at"),
+    ("backup_local", "local"),
+    ("backup_verified", "Verified"),
+    ("backup_verified_yes", "yes"),
+    ("backup_verified_no", "FAILED"),
+    ("backup_free_space", "Free disk space"),
+    ("backup_config_changes", "Config changes"),
+    ("backup_config_more", "…and {{count}} more"),
+    ("backup_restored", "Restored world backup {{file}}"),
+    ("restore_failed", "Restoring {{file}} failed: {{error}}"),
+    ("restore_moved_aside", "Previous world moved to"),
+    ("update_failed", "The updated server did not finish starting. Type `rollback` in the golem console to restore the pre-update backup."),
+    ("update_failed_rolling_back", "The updated server did not finish starting; rolling back to the pre-update backup."),
+    ("ingame_restore", "Server is restarting to restore a backup."),
+    ("backup_upload_failed", "upload to {{destination}} failed: {{error}}"),
+    ("metrics_ram", "RAM"),
+    ("metrics_uptime", "Uptime"),
+    ("metrics_players", "Players"),
+    ("metrics_world_size", "World size"),
+    ("status_title", "Server status"),
+    ("status_state", "State"),
+    ("status_online", "Online"),
+    ("status_offline", "Offline"),
+    ("status_uptime", "Uptime"),
+    ("status_players", "Players"),
+    ("status_tps", "TPS"),
+    ("status_ram", "Memory"),
+    ("status_host", "Host"),
+    ("status_latency", "Latency"),
+    ("status_next_event", "Next"),
+    ("status_next_start", "Opens at {{time}}"),
+    ("status_next_stop", "Closes at {{time}}"),
+    ("status_paused", "Schedule paused"),
+    ("status_last_backup", "Last backup"),
+    ("bot_not_allowed", "You are not allowed to do that."),
+    ("bot_logs_empty", "No console output; the server is not running."),
+    ("bot_vote_no_stop", "There is no scheduled stop to vote against."),
+    ("bot_vote_too_early", "Voting to extend opens {{minutes}} minutes before the stop."),
+    ("bot_vote_already", "You already voted ({{votes}}/{{needed}})."),
+    ("bot_vote_counted", "{{player}} voted to extend the session ({{votes}}/{{needed}}). Use /extend to vote too."),
+    ("bot_vote_passed", "Vote passed! Extending the session by {{minutes}} minutes."),
+    ("ingame_extend_vote", "{{player}} voted on Discord to extend the session ({{votes}}/{{needed}})."),
+    ("bot_starting", "Starting the server."),
+    ("bot_watchdog_reset", "Watchdog reset: the crashes so far are forgotten. Starting the server."),
+    ("bot_stopping", "Stopping the server. It stays off until it is next scheduled."),
+    ("bot_restarting", "Restarting the server."),
+    ("bot_extending", "Extending the session by {{minutes}} minutes."),
+    ("bot_schedule_setting", "Setting {{day}} to {{hours}}."),
+    ("schedule_title", "Weekly schedule"),
+    ("bot_confirm_stop", "Stop the server? {{players}} player(s) online will be disconnected, and it stays off until it is next scheduled."),
+    ("bot_confirm_restart", "Restart the server? {{players}} player(s) online will be disconnected."),
+    ("bot_confirm_restore", "Restore {{name}}? The server stops ({{players}} player(s) online) and the current world is moved aside."),
+    ("bot_confirm_within", "Confirm within {{seconds}} seconds."),
+    ("bot_confirm", "Confirm"),
+    ("bot_cancel", "Cancel"),
+    ("bot_cancelled", "Cancelled."),
+    ("bot_clean_exit_question", "The server was stopped from its console. Start it again?"),
+    ("bot_clean_exit_start", "Start"),
+    ("bot_clean_exit_leave", "Leave stopped"),
+    ("bot_clean_exit_left", "Left stopped by {{user}}."),
+    ("bot_clean_exit_started", "Started again by {{user}}."),
+    ("bot_confirm_expired", "This confirmation has expired. Run the command again."),
+    ("bot_restoring", "Restoring {{name}}."),
+    ("bot_no_backup", "There is no backup named {{name}}."),
+    ("bot_command_blocked", "`{{command}}` cannot be run from Discord."),
+    ("bot_no_alias", "There is no alias `{{name}}`."),
+    ("bot_alias_running", "Running `{{name}}`: {{commands}}"),
+    ("bot_command_no_output", "Sent. The server printed nothing in reply."),
+    ("bot_command_offline", "The server is not running."),
+    ("bot_command_timeout", "Sent, but the golem is busy; check the console later."),
+];
+
+const JA: Catalog = &[
+    ("golem_started", "Rusty-Golem が起動しました。"),
+    ("golem_unresponsive", "Rusty-Golem のハートビートが{{seconds}}秒間途絶えたため、モニターが強制終了して起動し直しました。"),
+    ("test_notification", "Rusty-Golem からのテスト通知です。"),
+    ("server_starting", "Minecraftサーバーを起動しています..."),
+    ("server_starting_safe_mode", "{{crashes}}回連続でクラッシュしたため、Minecraftサーバーをセーフモードで起動しています。次に通常起動するまで一部の設定を抑えています。"),
+    ("ingame_safe_mode", "クラッシュが続いたため、サーバーはセーフモードで動作しています。一部の設定を抑えています。"),
+    ("server_start_failed", "Minecraftサーバーの起動に失敗しました: {{error}}"),
+    ("server_stopping", "Minecraftサーバーを停止しています（スケジュール）..."),
+    ("server_stopping_requested", "Minecraftサーバーを停止しています（リクエスト）..."),
+    ("server_restarting", "Minecraftサーバーを再起動しています（リクエスト）..."),
+    ("session_extended", "セッションが{{minutes}}分延長されました。サーバーは {{time}} に停止します。"),
+    ("ingame_session_extended", "セッションが延長されました！サーバーは {{time}} に停止します。"),
+    ("schedule_changed", "スケジュールが変更されました: {{day}} は {{hours}} になりました。"),
+    ("lag", "サーバーが重くなっています: TPS が {{tps}} まで下がりました。"),
+    ("lag_host_busy", "サーバーが重くなっています (TPS {{tps}})。ただしマシン自体が高負荷のため、原因はサーバーの外にあるかもしれません。"),
+    ("probe_slow", "サーバーのポート ({{address}}) がステータス ping に応答するまで {{ms}} ms かかりました。"),
+    ("probe_failing", "サーバーのポート ({{address}}) への最近のステータス ping の {{percent}}% が失敗したか遅すぎました。"),
+    ("disk_space_low_world", "ワールドのドライブ ({{path}}) の空き容量が {{free}} しかありません（下限 {{floor}}）。"),
+    ("disk_space_low_backup", "バックアップのドライブ ({{path}}) の空き容量が {{free}} しかありません（下限 {{floor}}）。"),
+    ("log_anomaly", "直近1分間にサーバーが警告・エラーを {{count}} 件出力しました（普段は1分あたり {{usual}} 件）。プラグインやMODが異常動作しているかもしれません。"),
+    ("log_anomaly_examples", "最初の行"),
+    ("world_growth", "ワールドが {{since}} から {{grown}} 増えて {{size}} になりました。チャンクローダーや、新しい地形の探索が原因のことがよくあります。"),
+    ("memory_growth", "サーバーのメモリ使用量が増え続けています（現在 {{rss}}、1時間あたり +{{rate}}）。このままでは終了前の {{time}} ごろに {{limit}} に達します。再起動で解消できます。"),
+    ("memory_growth_restart", "サーバーのメモリ使用量が増え続けています（現在 {{rss}}、1時間あたり +{{rate}}）。このままでは終了前の {{time}} ごろに {{limit}} に達するため、誰もいなくなったら再起動します。"),
+    ("startup_slow", "サーバーの起動に {{took}} かかりました。普段 ({{usual}}) より {{percent}}% 長くなっています。ワールドやプラグインのデータベースの肥大化がよくある原因です。"),
+    ("schedule_reset", "スケジュールが変更されました: {{day}} は通常の時間 ({{hours}}) に戻りました。"),
+    ("server_crashed", "サーバーがクラッシュしました（終了コード {{code}}）。"),
+    ("server_frozen", "サーバーが応答しなくなったため（{{minutes}}分間、ステータス ping にもコンソールにも応答なし）、強制終了しました。"),
+    ("crash_description", "クラッシュ内容"),
+    ("crash_cause", "例外"),
+    ("crash_suspects", "原因と思われる Mod"),
+    ("crash_next_start", "次の起動"),
+    ("server_exited_restart", "サーバーがクラッシュではなく自ら停止しました（終了コード 0）。再起動します。"),
+    ("server_exited_leave", "サーバーがクラッシュではなく自ら停止しました（終了コード 0）。次のスケジュールまで停止したままにします。"),
+    ("server_exited_ask", "サーバーがクラッシュではなく自ら停止しました（終了コード 0）。誰かが起動するまで停止したままにします。"),
+    ("crash_next_start_at", "{{delay}}後（{{time}}）"),
+    ("crash_next_start_none", "なし（ウォッチドッグの試行回数を使い切りました）"),
+    ("crash_next_start_blocked", "なし（再起動では解決しません）"),
+    ("crash_next_start_halted", "なし（ポリシーにより停止したままにします）"),
+    ("server_out_of_memory", "サーバーがメモリ不足で停止しました（終了コード {{code}}）。"),
+    ("oom_error", "エラー"),
+    ("oom_heap_limit", "ヒープ上限（-Xmx）"),
+    ("oom_memory_used", "停止前のメモリ使用量"),
+    ("oom_heap_next", "次回起動時のヒープ"),
+    ("oom_heap_raised", "{{heap}} に引き上げ"),
+    ("oom_heap_ceiling", "{{heap}}（上限に達しています）"),
+    ("oom_heap_not_found", "変更なし（起動スクリプトに -Xmx がありません）"),
+    ("oom_heap_failed", "変更なし（起動スクリプトを書き換えられませんでした）"),
+    ("player_joined", "{{player}} がゲームに参加しました。"),
+    ("player_left", "{{player}} がゲームから退出しました。"),
+    ("stop_warning", "サーバーはあと{{minutes}}分で停止します。"),
+    ("stop_warning_last", "サーバーはあと1分で停止します。"),
+    ("ingame_stop_warning", "サーバーはあと{{minutes}}分で停止します！"),
+    ("ingame_stop_warning_last", "サーバーはあと1分で停止します！"),
+    ("watchdog_gave_up", "ウォッチドッグ: サーバーが{{minutes}}分間に{{crashes}}回停止しました。自動再起動を中止します。"),
+    ("watchdog_retry_later", "ウォッチドッグ: サーバーが{{minutes}}分間に{{crashes}}回停止しました。{{time}}に再試行します。"),
+    ("watchdog_daily_limit", "ウォッチドッグ: 本日の自動再起動が上限の{{restarts}}回に達しました。手動で起動するか `watchdog reset` を実行するまで、翌日以降もサーバーは停止したままです。"),
+    ("watchdog_fallback", "ウォッチドッグ: サーバーが{{minutes}}分間に{{crashes}}回停止しました。{{script}}を実行します。"),
+    ("watchdog_fallback_failed", "ウォッチドッグ: フォールバックスクリプトが失敗しました（{{error}}）。自動再起動を中止します。"),
+    ("watchdog_unrecoverable", "ウォッチドッグ: サーバーを再起動しません: {{cause}}再起動しても解決しないため、修正してから手動で起動してください。"),
+    ("watchdog_unrecoverable_line", "コンソール"),
+    ("unrecoverable_port", "ポートがすでに使われています（別のサーバーが起動している可能性があります）。"),
+    ("unrecoverable_world", "ワールドが新しいバージョンの Minecraft で保存されています。"),
+    ("unrecoverable_dependency", "Mod またはプラグインの依存関係が不足しています。"),
+    ("unrecoverable_java", "サーバーに新しいバージョンの Java が必要です。"),
+    ("watchdog_gave_up_cooldown", "ウォッチドッグ: サーバーが{{minutes}}分間に{{crashes}}回停止しました。自動再起動を一時停止し、{{time}}にもう一度試します。"),
+    ("watchdog_fallback_failed_cooldown", "ウォッチドッグ: フォールバックスクリプトが失敗しました（{{error}}）。自動再起動を一時停止し、{{time}}にもう一度試します。"),
+    ("flood_summary", "{{message}}（{{minutes}}分間に{{count}}回）"),
+    ("escalation_message", "[{{minutes}}分間未確認] {{message}}"),
+    ("flood_suppressed", "フラッド保護により{{count}}件の通知を抑制しました。"),
+    ("digest_title", "本日のまとめ"),
+    ("digest_uptime", "稼働時間"),
+    ("digest_restarts", "再起動回数"),
+    ("digest_crashes", "クラッシュ回数"),
+    ("digest_peak_players", "最大同時接続数"),
+    ("digest_unique_players", "ユニークプレイヤー数"),
+    ("digest_backups", "バックアップ"),
+    ("digest_availability", "稼働率"),
+    ("digest_availability_week", "稼働率（直近7日）"),
+    ("digest_availability_value", "{{percent}}（時間内の停止 {{down}}）"),
+    ("weekly_title", "週間レポート"),
+    ("weekly_uptime", "稼働時間"),
+    ("weekly_unique_players", "ユニークプレイヤー数"),
+    ("weekly_playtime", "プレイ時間"),
+    ("weekly_crashes", "クラッシュ回数"),
+    ("weekly_average_tps", "平均TPS"),
+    ("weekly_world_size", "ワールドサイズ"),
+    ("weekly_world_size_value", "{{size}}（今週 {{change}}）"),
+    ("backup_field", "バックアップ"),
+    ("backup_summary", "{{file}}（{{size}}）"),
+    ("backup_failed", "バックアップに失敗しました: {{error}}"),
+    ("backup_verify_failed", "バックアップ {{file}} の検証に失敗しました: {{error}}"),
+    ("backup_completed", "バックアップ完了: {{file}}"),
+    ("backup_size", "サイズ"),
+    ("backup_size_change", "{{size}}（前回比 {{change}}）"),
+    ("backup_duration", "所要時間"),
+    ("backup_destinations", "保存先"),
+    ("backup_local", "ローカル"),
+    ("backup_verified", "検証"),
+    ("backup_verified_yes", "OK"),
+    ("backup_verified_no", "失敗"),
+    ("backup_free_space", "ディスク空き容量"),
+    ("backup_config_changes", "設定の変更"),
+    ("backup_config_more", "…他 {{count}} 件"),
+    ("backup_restored", "ワールドのバックアップ {{file}} を復元しました"),
+    ("restore_failed", "{{file}} の復元に失敗しました: {{error}}"),
+    ("restore_moved_aside", "以前のワールドの移動先"),
+    ("update_failed", "更新後のサーバーが起動を完了しませんでした。golem のコンソールで `rollback` と入力すると更新前のバックアップに戻せます。"),
+    ("update_failed_rolling_back", "更新後のサーバーが起動を完了しなかったため、更新前のバックアップに戻します。"),
+    ("ingame_restore", "バックアップを復元するため、サーバーを再起動します。"),
+    ("backup_upload_failed", "{{destination}} へのアップロードに失敗しました: {{error}}"),
+    ("metrics_ram", "メモリ"),
+    ("metrics_uptime", "稼働時間"),
+    ("metrics_players", "プレイヤー"),
+    ("metrics_world_size", "ワールドサイズ"),
+    ("status_title", "サーバーステータス"),
+    ("status_state", "状態"),
+    ("status_online", "オンライン"),
+    ("status_offline", "オフライン"),
+    ("status_uptime", "稼働時間"),
+    ("status_players", "プレイヤー"),
+    ("status_tps", "TPS"),
+    ("status_ram", "メモリ"),
+    ("status_host", "ホスト"),
+    ("status_latency", "応答時間"),
+    ("status_next_event", "次の予定"),
+    ("status_next_start", "{{time}} に開始"),
+    ("status_next_stop", "{{time}} に停止"),
+    ("status_paused", "スケジュール一時停止中"),
+    ("status_last_backup", "最終バックアップ"),
+    ("bot_not_allowed", "この操作を行う権限がありません。"),
+    ("bot_logs_empty", "コンソール出力がありません。サーバーは起動していません。"),
+    ("bot_vote_no_stop", "投票できる停止予定がありません。"),
+    ("bot_vote_too_early", "延長の投票は停止の{{minutes}}分前から受け付けます。"),
+    ("bot_vote_already", "すでに投票済みです ({{votes}}/{{needed}})。"),
+    ("bot_vote_counted", "{{player}} さんが延長に投票しました ({{votes}}/{{needed}})。/extend で投票できます。"),
+    ("bot_vote_passed", "投票が成立しました！セッションを{{minutes}}分延長します。"),
+    ("ingame_extend_vote", "{{player}} さんが Discord でセッション延長に投票しました ({{votes}}/{{needed}})。"),
+    ("bot_starting", "サーバーを起動します。"),
+    ("bot_watchdog_reset", "ウォッチドッグをリセットし、これまでのクラッシュ記録を消去しました。サーバーを起動します。"),
+    ("bot_stopping", "サーバーを停止します。次の予定まで停止したままになります。"),
+    ("bot_restarting", "サーバーを再起動します。"),
+    ("bot_extending", "セッションを{{minutes}}分延長します。"),
+    ("bot_schedule_setting", "{{day}} を {{hours}} に設定します。"),
+    ("schedule_title", "週間スケジュール"),
+    ("bot_confirm_stop", "サーバーを停止しますか？オンラインの{{players}}人が切断され、次の予定まで停止したままになります。"),
+    ("bot_confirm_restart", "サーバーを再起動しますか？オンラインの{{players}}人が切断されます。"),
+    ("bot_confirm_restore", "{{name}} を復元しますか？サーバーが停止し（オンライン{{players}}人）、現在のワールドは退避されます。"),
+    ("bot_confirm_within", "{{seconds}}秒以内に確定してください。"),
+    ("bot_confirm", "確定"),
+    ("bot_cancel", "キャンセル"),
+    ("bot_cancelled", "キャンセルしました。"),
+    ("bot_clean_exit_question", "サーバーがコンソールから停止されました。もう一度起動しますか？"),
+    ("bot_clean_exit_start", "起動"),
+    ("bot_clean_exit_leave", "停止したまま"),
+    ("bot_clean_exit_left", "{{user}} が停止したままにしました。"),
+    ("bot_clean_exit_started", "{{user}} が再起動しました。"),
+    ("bot_confirm_expired", "確認の期限が切れました。もう一度コマンドを実行してください。"),
+    ("bot_restoring", "{{name}} を復元します。"),
+    ("bot_no_backup", "{{name}} という名前のバックアップはありません。"),
+    ("bot_command_blocked", "`{{command}}` はDiscordから実行できません。"),
+    ("bot_no_alias", "エイリアス `{{name}}` はありません。"),
+    ("bot_alias_running", "`{{name}}` を実行します: {{commands}}"),
+    ("bot_command_no_output", "送信しました。サーバーからの応答はありません。"),
+    ("bot_command_offline", "サーバーは起動していません。"),
+    ("bot_command_timeout", "送信しましたが、Golemが処理中です。後でコンソールを確認してください。"),
+];
+
+/// Built-in message catalog for the configured language, with per-key
+/// overrides from the `[messages]` config table.
+#[derive(Clone)]
+pub struct Messages {
+    catalog: Catalog,
+    overrides: HashMap<String, String>,
+}
+
+impl Messages {
+    pub fn from_config(config: &Config) -> Self {
+        let catalog = match config.language.as_str() {
+            "en" => EN,
+            "ja" => JA,
+            other => panic!("Unsupported language: {} (expected \"en\" or \"ja\")", other),
+        };
+        Messages {
+            catalog,
+            overrides: config.messages.clone(),
+        }
+    }
+
+    pub fn get(&self, key: &str, vars: &[(&str, String)]) -> String {
+        let text = self
+            .overrides
+            .get(key)
+            .map(|s| s.as_str())
+            .or_else(|| lookup(self.catalog, key))
+            .or_else(|| lookup(EN, key))
+            .unwrap_or(key);
+        template::render(text, vars)
+    }
+}
+
+fn lookup(catalog: Catalog, key: &str) -> Option<&'static str> {
+    catalog.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
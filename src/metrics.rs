@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use sysinfo::{Disks, Pid, ProcessesToUpdate, System};
+
+use crate::digest::format_duration;
+use crate::messages::Messages;
+use crate::server_props;
+
+/// Point-in-time figures attached to lifecycle notifications.
+#[derive(Debug, Default, Clone)]
+pub struct Snapshot {
+    pub rss_bytes: Option<u64>,
+    pub uptime: Option<Duration>,
+    pub players: usize,
+    pub world_size_bytes: Option<u64>,
+}
+
+impl Snapshot {
+    pub fn fields(&self, messages: &Messages) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        if let Some(rss) = self.rss_bytes {
+            fields.push((messages.get("metrics_ram", &[]), format_bytes(rss)));
+        }
+        if let Some(uptime) = self.uptime {
+            fields.push((messages.get("metrics_uptime", &[]), format_duration(uptime)));
+        }
+        fields.push((messages.get("metrics_players", &[]), self.players.to_string()));
+        if let Some(size) = self.world_size_bytes {
+            fields.push((messages.get("metrics_world_size", &[]), format_bytes(size)));
+        }
+        fields
+    }
+}
+
+/// The machine as a whole, to tell a busy host from a slow server.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Host {
+    /// All cores, averaged since the previous sample.
+    pub cpu_percent: f32,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    /// One-minute load average; None on Windows, which has none.
+    pub load: Option<f64>,
+}
+
+impl Host {
+    /// The CPU nearly saturated or nearly all memory taken, by anything.
+    pub fn starved(&self) -> bool {
+        self.cpu_percent >= 90.0 || (self.memory_total > 0 && self.memory_used * 20 >= self.memory_total * 19)
+    }
+
+    /// E.g. "CPU 97%, RAM 15.2 GB of 16.0 GB, load 4.10".
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "CPU {:.0}%, RAM {} of {}",
+            self.cpu_percent,
+            format_bytes(self.memory_used),
+            format_bytes(self.memory_total)
+        );
+        if let Some(load) = self.load {
+            summary.push_str(&format!(", load {:.2}", load));
+        }
+        summary
+    }
+}
+
+pub struct Metrics {
+    system: System,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            system: System::new(),
+        }
+    }
+
+    pub fn snapshot(
+        &mut self,
+        server_dir: &Path,
+        pid: Option<u32>,
+        uptime: Option<Duration>,
+        players: usize,
+    ) -> Snapshot {
+        Snapshot {
+            rss_bytes: pid.and_then(|pid| self.process_tree_rss(pid)),
+            uptime,
+            players,
+            world_size_bytes: Some(world_size(server_dir)).filter(|&size| size > 0),
+        }
+    }
+
+    /// CPU, memory and load of the whole machine. The CPU figure covers the time
+    /// since the previous call, so the first one reads 0.
+    pub fn host(&mut self) -> Host {
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+        Host {
+            cpu_percent: self.system.global_cpu_usage(),
+            memory_used: self.system.used_memory(),
+            memory_total: self.system.total_memory(),
+            load: Some(System::load_average().one).filter(|_| !cfg!(windows)),
+        }
+    }
+
+    /// Resident memory of a process and all of its descendants. The launcher is
+    /// usually `cmd`/`sh` running a script, so java itself is a grandchild.
+    pub fn process_tree_rss(&mut self, root: u32) -> Option<u64> {
+        self.system.refresh_processes(ProcessesToUpdate::All, true);
+        let processes = self.system.processes();
+        let root = Pid::from_u32(root);
+        processes.get(&root)?;
+
+        let mut total = 0;
+        for (pid, process) in processes {
+            let mut current = Some(*pid);
+            while let Some(p) = current {
+                if p == root {
+                    total += process.memory();
+                    break;
+                }
+                current = processes.get(&p).and_then(|proc_| proc_.parent());
+            }
+        }
+        Some(total)
+    }
+}
+
+/// Free space on the disk holding `path`: the mount point that is the longest
+/// prefix of it.
+pub fn free_space(path: &Path) -> Option<u64> {
+    let path = fs::canonicalize(path).ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|d| path.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.available_space())
+}
+
+pub fn world_size(server_dir: &Path) -> u64 {
+    server_props::world_dirs(server_dir).iter().map(|d| dir_size(d)).sum()
+}
+
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Ticks per second from the answer to `tps` (Paper, Spigot: "TPS from last
+/// 1m, 5m, 15m: 19.9, 20.0, 20.0", the 1m figure) or `tick query` (vanilla
+/// 1.20.3+, worked out from "Average time per tick: 12.3ms").
+pub fn parse_tps(lines: &[String]) -> Option<f64> {
+    lines.iter().find_map(|line| {
+        if let Some((_, figures)) = line.split_once("TPS from last 1m, 5m, 15m:") {
+            first_number(figures)
+        } else if let Some((_, figures)) = line.split_once("Average time per tick:") {
+            first_number(figures).filter(|&ms| ms > 0.0).map(|ms| (1000.0 / ms).min(20.0))
+        } else {
+            None
+        }
+    })
+}
+
+/// Milliseconds per tick, which only `tick query` reports ("Average time per
+/// tick: 12.3ms"); unlike TPS it shows how close a server is to lagging.
+pub fn parse_mspt(lines: &[String]) -> Option<f64> {
+    lines
+        .iter()
+        .find_map(|line| line.split_once("Average time per tick:").and_then(|(_, figures)| first_number(figures)))
+}
+
+/// Skips colour codes such as "§a" and marks such as Paper's "*20.0".
+fn first_number(text: &str) -> Option<f64> {
+    let mut plain = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .find_map(|word| word.parse::<f64>().ok())
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
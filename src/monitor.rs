@@ -0,0 +1,195 @@
+use std::env;
+use std::fs;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use tracing::{error, info, warn};
+
+use crate::config::{load_config, MonitorConfig};
+use crate::instance::Instances;
+use crate::messages::Messages;
+use crate::notify::{EventKind, Notifiers};
+use crate::server;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// One server's progress, fed by its main loop. A pass can block for minutes
+/// in a backup, a restore or a stop; the server counts as working meanwhile.
+#[derive(Clone)]
+pub struct Pulse(Arc<Mutex<(Instant, usize)>>);
+
+impl Pulse {
+    pub fn new() -> Pulse {
+        Pulse(Arc::new(Mutex::new((Instant::now(), 0))))
+    }
+
+    /// Called on every pass of the main loop.
+    pub fn beat(&self) {
+        self.0.lock().unwrap().0 = Instant::now();
+    }
+
+    /// Runs a backup, restore or stop, which may take longer than the monitor
+    /// waits for a beat.
+    pub fn busy<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.0.lock().unwrap().1 += 1;
+        let result = f();
+        let mut pulse = self.0.lock().unwrap();
+        *pulse = (Instant::now(), pulse.1 - 1);
+        result
+    }
+
+    /// Time since the last beat; zero while busy.
+    fn age(&self) -> Duration {
+        let (at, busy) = *self.0.lock().unwrap();
+        if busy > 0 {
+            Duration::ZERO
+        } else {
+            at.elapsed()
+        }
+    }
+}
+
+/// `<time> <pid>` in a file, for `rusty-golem monitor` to tell a hung or dead
+/// golem from a working one. Rewritten from a thread of its own, with the
+/// time of the beat of the server that has gone longest without one, so any
+/// one server hanging makes it stale.
+pub struct HeartbeatFile {
+    path: String,
+    failing: bool,
+}
+
+impl HeartbeatFile {
+    pub fn spawn(config: &MonitorConfig, instances: Instances) {
+        let mut file = HeartbeatFile { path: config.heartbeat_file.clone(), failing: false };
+        let stale = Duration::from_secs(config.stale_seconds.max(30));
+        thread::spawn(move || {
+            let mut hung: Vec<String> = Vec::new();
+            loop {
+                let servers = instances.all();
+                let ages: Vec<(String, Duration)> = servers
+                    .iter()
+                    .map(|server| (server.name.clone().unwrap_or_else(|| "the server".to_string()), server.pulse.age()))
+                    .collect();
+                for (name, age) in &ages {
+                    // Said once per hang
+                    if *age > stale && !hung.contains(name) {
+                        warn!("Heartbeat: {} has made no progress for {} s.", name, age.as_secs());
+                    }
+                }
+                hung = ages.iter().filter(|(_, age)| *age > stale).map(|(name, _)| name.clone()).collect();
+                let oldest = ages.iter().map(|(_, age)| *age).max().unwrap_or_default();
+                file.beat(Local::now() - chrono::Duration::from_std(oldest).unwrap_or_default());
+                thread::sleep(CHECK_INTERVAL);
+            }
+        });
+    }
+
+    fn beat(&mut self, time: DateTime<Local>) {
+        let beat = format!("{} {}", time.to_rfc3339(), std::process::id());
+        match fs::write(&self.path, beat) {
+            Ok(()) => self.failing = false,
+            // Said once, not every ten seconds
+            Err(e) if !self.failing => {
+                self.failing = true;
+                warn!("Heartbeat: could not write {}: {}", self.path, e);
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// The time and golem PID of the latest beat, if there is one.
+fn read(path: &str) -> Option<(DateTime<Local>, u32)> {
+    let content = fs::read_to_string(path).ok()?;
+    let (time, pid) = content.trim().split_once(' ')?;
+    Some((DateTime::parse_from_rfc3339(time).ok()?.with_timezone(&Local), pid.parse().ok()?))
+}
+
+/// `rusty-golem monitor`: keeps a golem running from this folder. Starts one
+/// when there is no heartbeat, and when the heartbeat goes stale kills the
+/// golem (and the server it started) and starts a fresh one. Never returns.
+pub fn run() {
+    let config = load_config();
+    let messages = Messages::from_config(&config);
+    let notifiers = Notifiers::from_config(&config, &messages);
+    let settings = config.monitor;
+    let stale = chrono::Duration::seconds(settings.stale_seconds.max(30) as i64);
+    let grace = Duration::from_secs(settings.grace_seconds);
+    info!("Monitor: watching {} (stale after {} s).", settings.heartbeat_file, stale.num_seconds());
+
+    let mut golem: Option<(Child, Instant)> = None;
+    loop {
+        // Reap a golem that exited, and give a new one time to write its first beat
+        if let Some((child, started)) = golem.as_mut() {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    warn!("Monitor: the golem exited ({}).", status);
+                    golem = None;
+                }
+                _ if started.elapsed() < grace => {
+                    thread::sleep(CHECK_INTERVAL);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        let beat = read(&settings.heartbeat_file);
+        let age = beat.map(|(time, _)| Local::now() - time);
+        if age.is_some_and(|age| age <= stale) {
+            thread::sleep(CHECK_INTERVAL);
+            continue;
+        }
+        let stale_for = beat.map(|(_, pid)| {
+            let seconds = age.unwrap_or_default().num_seconds();
+            error!("Monitor: the golem's last heartbeat is {} s old; restarting it.", seconds);
+            kill(pid);
+            seconds
+        });
+        if stale_for.is_none() {
+            info!("Monitor: no heartbeat yet; starting the golem.");
+        }
+        if let Some((mut child, _)) = golem.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        golem = match start() {
+            Ok(child) => Some((child, Instant::now())),
+            Err(e) => {
+                error!("Monitor: could not start the golem: {}", e);
+                None
+            }
+        };
+        // After the start, as delivery may take a while
+        if let Some(seconds) = stale_for {
+            notifiers.send(EventKind::GolemUnresponsive, &messages.get("golem_unresponsive", &[("seconds", seconds.to_string())]));
+        }
+        thread::sleep(CHECK_INTERVAL);
+    }
+}
+
+/// The hung golem and whatever it started: a server it no longer manages
+/// would hold the world and the port against the next one. A PID from an old
+/// beat may belong to something else by now, so only a golem is killed.
+fn kill(pid: u32) {
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let Some(process) = system.process(Pid::from_u32(pid)) else {
+        return;
+    };
+    let own_name = env::current_exe().ok().and_then(|exe| exe.file_name().map(|name| name.to_os_string()));
+    if own_name.as_deref() != Some(process.name()) {
+        return;
+    }
+    server::kill_descendants(pid);
+    process.kill();
+}
+
+/// This same executable without arguments, from this folder; its output goes
+/// where the monitor's does.
+fn start() -> std::io::Result<Child> {
+    Command::new(env::current_exe()?).stdin(Stdio::null()).spawn()
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::audit;
+use crate::config::MqttConfig;
+use crate::control::Command;
+use crate::instance::{Handle, Instances};
+
+/// Seconds the broker waits without hearing from us before it gives up on
+/// the connection and publishes the last will.
+const KEEP_ALIVE_SECS: u16 = 60;
+
+/// Publishes the server's state to an MQTT broker (retained, so a newly
+/// started Home Assistant sees it at once) and takes `start`, `stop`,
+/// `restart` and `backup` on `<prefix>/command`. With `[[servers]]` each
+/// server has its topics under `<prefix>/<name>/` instead. Reconnects for as
+/// long as the golem runs.
+pub fn spawn(config: &MqttConfig, instances: Instances) {
+    let config = config.clone();
+    thread::spawn(move || loop {
+        if let Err(e) = session(&config, &instances) {
+            warn!("MQTT: {}:{}: {}; reconnecting in 30s", config.host, config.port, e);
+        }
+        thread::sleep(Duration::from_secs(30));
+    });
+}
+
+fn session(config: &MqttConfig, instances: &Instances) -> io::Result<()> {
+    let mut stream = TcpStream::connect((config.host.as_str(), config.port))?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let prefix = config.topic_prefix.trim_end_matches('/');
+    let availability = format!("{}/availability", prefix);
+    stream.write_all(&connect_packet(config, &availability))?;
+    match read_packet(&mut stream)? {
+        Some((0x20, body)) if body.get(1) == Some(&0) => {}
+        Some((0x20, body)) => return Err(io::Error::other(format!("connection refused (code {:?})", body.get(1)))),
+        _ => return Err(io::Error::other("no CONNACK from the broker")),
+    }
+    info!("MQTT: connected to {}:{}", config.host, config.port);
+
+    let commands = if instances.named() { format!("{}/+/command", prefix) } else { format!("{}/command", prefix) };
+    stream.write_all(&subscribe_packet(&commands))?;
+    stream.write_all(&publish_packet(&availability, b"online", true))?;
+
+    // By server, as each is first seen: supervisors register as they start
+    let mut published: HashMap<Option<String>, (bool, usize)> = HashMap::new();
+    let mut last_sent = Instant::now();
+    loop {
+        for server in instances.all() {
+            let (online, players) = server.interpreter.status.lock().map(|s| (s.online, s.players)).unwrap_or_default();
+            let topics = server_prefix(prefix, &server);
+            if config.discovery && !published.contains_key(&server.name) {
+                for (topic, entity) in discovery(config, prefix, &server) {
+                    stream.write_all(&publish_packet(&topic, entity.to_string().as_bytes(), true))?;
+                }
+            }
+            if published.get(&server.name) != Some(&(online, players)) {
+                let state = if online { "online" } else { "offline" };
+                stream.write_all(&publish_packet(&format!("{}/state", topics), state.as_bytes(), true))?;
+                stream.write_all(&publish_packet(&format!("{}/players", topics), players.to_string().as_bytes(), true))?;
+                published.insert(server.name.clone(), (online, players));
+                last_sent = Instant::now();
+            }
+        }
+        if last_sent.elapsed() >= Duration::from_secs(KEEP_ALIVE_SECS as u64 / 2) {
+            stream.write_all(&[0xC0, 0])?;
+            last_sent = Instant::now();
+        }
+        match read_packet(&mut stream)? {
+            Some((header, body)) if header >> 4 == 3 => {
+                let Some((topic, payload)) = incoming_publish(header, &body) else {
+                    continue;
+                };
+                let server = instances.all().into_iter().find(|server| topic == format!("{}/command", server_prefix(prefix, server)));
+                let Some(server) = server else {
+                    continue;
+                };
+                let command = match payload.trim().to_lowercase().as_str() {
+                    "start" => Command::Start,
+                    "stop" => Command::Stop,
+                    "restart" => Command::Restart,
+                    "backup" => Command::Backup(None),
+                    other => {
+                        warn!("MQTT: ignoring unknown command {:?}", other);
+                        continue;
+                    }
+                };
+                info!("MQTT: {} requested on {}", payload.trim(), topic);
+                let sent = server.commands.send(command).is_ok();
+                audit::record("mqtt", &topic, payload.trim(), if sent { "queued" } else { "golem shutting down" });
+                if !sent {
+                    return Ok(());
+                }
+            }
+            // CONNACK, SUBACK, PINGRESP and nothing at all need no answer
+            _ => {}
+        }
+    }
+}
+
+/// `<prefix>`, or with `[[servers]]` `<prefix>/<name>`: where the server's
+/// topics go.
+fn server_prefix(prefix: &str, server: &Handle) -> String {
+    match &server.name {
+        Some(name) => format!("{}/{}", prefix, name),
+        None => prefix.to_string(),
+    }
+}
+
+/// Home Assistant MQTT discovery: the server as a running sensor, the player
+/// count, and start/stop buttons, grouped as one device per server.
+fn discovery(config: &MqttConfig, prefix: &str, server: &Handle) -> Vec<(String, Value)> {
+    let id = match &server.name {
+        Some(name) => format!("{}_{}", config.client_id, name),
+        None => config.client_id.clone(),
+    };
+    let node: String = id.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    let name = match &server.display_name {
+        Some(name) => format!("Rusty-Golem {}", name),
+        None => "Rusty-Golem".to_string(),
+    };
+    let device = json!({ "identifiers": [node], "name": name, "model": "Minecraft server" });
+    let availability = format!("{}/availability", prefix);
+    let prefix = server_prefix(prefix, server);
+    let topic = |component: &str, object: &str| format!("{}/{}/{}/{}/config", config.discovery_prefix, component, node, object);
+    let mut entities = vec![
+        (
+            topic("binary_sensor", "server"),
+            json!({
+                "name": "Server",
+                "unique_id": format!("{}_server", node),
+                "device_class": "running",
+                "state_topic": format!("{}/state", prefix),
+                "payload_on": "online",
+                "payload_off": "offline",
+            }),
+        ),
+        (
+            topic("sensor", "players"),
+            json!({
+                "name": "Players",
+                "unique_id": format!("{}_players", node),
+                "state_topic": format!("{}/players", prefix),
+                "unit_of_measurement": "players",
+                "state_class": "measurement",
+                "icon": "mdi:account-multiple",
+            }),
+        ),
+    ];
+    for action in ["start", "stop"] {
+        entities.push((
+            topic("button", action),
+            json!({
+                "name": if action == "start" { "Start" } else { "Stop" },
+                "unique_id": format!("{}_{}", node, action),
+                "command_topic": format!("{}/command", prefix),
+                "payload_press": action,
+                "icon": if action == "start" { "mdi:play" } else { "mdi:stop" },
+            }),
+        ));
+    }
+    for (_, entity) in &mut entities {
+        entity["device"] = device.clone();
+        entity["availability_topic"] = json!(availability);
+    }
+    entities
+}
+
+/// MQTT 3.1.1 CONNECT with a clean session and a retained "offline" will.
+fn connect_packet(config: &MqttConfig, availability: &str) -> Vec<u8> {
+    let mut flags = 0x02 | 0x04 | 0x20;
+    let mut body = Vec::new();
+    push_str(&mut body, "MQTT");
+    body.push(4);
+    if config.username.is_some() {
+        flags |= 0x80;
+    }
+    if config.password.is_some() {
+        flags |= 0x40;
+    }
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+    push_str(&mut body, &config.client_id);
+    push_str(&mut body, availability);
+    push_str(&mut body, "offline");
+    for credential in [&config.username, &config.password].into_iter().flatten() {
+        push_str(&mut body, credential);
+    }
+    packet(0x10, &body)
+}
+
+fn subscribe_packet(topic: &str) -> Vec<u8> {
+    let mut body = 1u16.to_be_bytes().to_vec();
+    push_str(&mut body, topic);
+    body.push(0);
+    packet(0x82, &body)
+}
+
+/// At most once delivery, which is all state and commands need.
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_str(&mut body, topic);
+    body.extend_from_slice(payload);
+    packet(0x30 | retain as u8, &body)
+}
+
+/// The topic and payload of a PUBLISH from the broker.
+fn incoming_publish(header: u8, body: &[u8]) -> Option<(String, String)> {
+    let length = u16::from_be_bytes([*body.first()?, *body.get(1)?]) as usize;
+    let topic = String::from_utf8_lossy(body.get(2..2 + length)?).to_string();
+    // QoS 1 and 2 carry a packet id; we subscribe at 0, but skip it anyway
+    let payload_start = 2 + length + if (header >> 1) & 3 > 0 { 2 } else { 0 };
+    let payload = String::from_utf8_lossy(body.get(payload_start..)?).to_string();
+    Some((topic, payload))
+}
+
+fn push_str(buffer: &mut Vec<u8>, text: &str) {
+    buffer.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(text.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// The next packet's header byte and body, or None if nothing arrived within
+/// the read timeout.
+fn read_packet(stream: &mut TcpStream) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0u8];
+    match stream.read(&mut header) {
+        Ok(0) => return Err(io::Error::new(ErrorKind::UnexpectedEof, "the broker closed the connection")),
+        Ok(_) => {}
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut length = 0usize;
+    for shift in 0..4 {
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte)?;
+        length |= ((byte[0] & 0x7F) as usize) << (7 * shift);
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body)?;
+    Ok(Some((header[0], body)))
+}
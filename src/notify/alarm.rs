@@ -0,0 +1,73 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use super::{DeliveryError, Event, Notifier, Severity};
+use crate::config::AlarmConfig;
+
+/// Makes noise on the host itself: plays a sound file, or rings the console bell.
+pub struct AlarmNotifier {
+    config: AlarmConfig,
+}
+
+impl AlarmNotifier {
+    pub fn new(config: AlarmConfig) -> Self {
+        AlarmNotifier { config }
+    }
+}
+
+impl Notifier for AlarmNotifier {
+    fn name(&self) -> &str {
+        "alarm"
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, _event: &Event) -> Result<(), DeliveryError> {
+        for _ in 0..self.config.repeat.max(1) {
+            match &self.config.sound_file {
+                Some(path) => play(path)?,
+                None => {
+                    print!("\x07");
+                    let _ = io::stdout().flush();
+                    thread::sleep(Duration::from_secs(1));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn play(path: &str) -> Result<(), DeliveryError> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut c = Command::new("powershell");
+        c.args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "(New-Object Media.SoundPlayer $env:GOLEM_ALARM_FILE).PlaySync()",
+        ])
+        .env("GOLEM_ALARM_FILE", path);
+        c
+    } else if cfg!(target_os = "macos") {
+        let mut c = Command::new("afplay");
+        c.arg(path);
+        c
+    } else {
+        let mut c = Command::new("paplay");
+        c.arg(path);
+        c
+    };
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| DeliveryError::Fatal(format!("cannot play {}: {}", path, e)))?;
+    if !status.success() {
+        return Err(DeliveryError::Fatal(format!("cannot play {}: {}", path, status)));
+    }
+    Ok(())
+}
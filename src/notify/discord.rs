@@ -0,0 +1,77 @@
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+
+use super::{check_response, DeliveryError, Event, Notifier, Severity};
+
+pub struct DiscordNotifier {
+    client: Client,
+    name: String,
+    url: String,
+    min_level: Severity,
+}
+
+impl DiscordNotifier {
+    pub fn new(name: &str, url: &str, min_level: Severity) -> Self {
+        DiscordNotifier {
+            client: Client::new(),
+            name: name.to_string(),
+            url: url.to_string(),
+            min_level,
+        }
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn min_level(&self) -> Severity {
+        self.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        send_message(self.client.post(&self.url), event)?;
+        Ok(())
+    }
+}
+
+/// Message body shared by webhook and bot posting: plain content, or an embed
+/// when the event carries fields.
+pub fn message_payload(event: &Event) -> serde_json::Value {
+    if event.fields.is_empty() {
+        serde_json::json!({
+            "content": event.message
+        })
+    } else {
+        let fields: Vec<_> = event
+            .fields
+            .iter()
+            .map(|(name, value)| serde_json::json!({ "name": name, "value": value, "inline": true }))
+            .collect();
+        serde_json::json!({
+            "embeds": [{
+                "title": event.message,
+                "fields": fields,
+                "timestamp": event.timestamp.to_rfc3339()
+            }]
+        })
+    }
+}
+
+/// Sends the event's payload, switching to multipart when it has attachments.
+pub fn send_message(request: RequestBuilder, event: &Event) -> Result<Response, DeliveryError> {
+    let payload = message_payload(event);
+    let response = if event.attachments.is_empty() {
+        request.json(&payload).send()?
+    } else {
+        let mut form = Form::new().text("payload_json", payload.to_string());
+        for (i, attachment) in event.attachments.iter().enumerate() {
+            let part = Part::bytes(attachment.content.clone())
+                .file_name(attachment.filename.clone());
+            form = form.part(format!("files[{}]", i), part);
+        }
+        request.multipart(form).send()?
+    };
+    check_response(response)
+}
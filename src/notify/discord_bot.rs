@@ -0,0 +1,46 @@
+use reqwest::Method;
+
+use super::discord::send_message;
+use super::{DeliveryError, Escalations, Event, Notifier, Severity};
+use crate::config::DiscordBotConfig;
+use crate::discord_api::DiscordApi;
+
+/// Posts events as a bot user, which (unlike webhooks) can target threads.
+pub struct DiscordBotNotifier {
+    api: DiscordApi,
+    config: DiscordBotConfig,
+    escalations: Option<Escalations>,
+}
+
+impl DiscordBotNotifier {
+    pub fn new(config: DiscordBotConfig, escalations: Option<Escalations>) -> Self {
+        DiscordBotNotifier {
+            api: DiscordApi::new(&config.token),
+            config,
+            escalations,
+        }
+    }
+}
+
+impl Notifier for DiscordBotNotifier {
+    fn name(&self) -> &str {
+        "discord-bot"
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        // Threads are channels too, so a thread ID works here as well
+        let path = format!("/channels/{}/messages", self.config.channel_id);
+        let response = send_message(self.api.request(Method::POST, &path), event)?;
+        if let Some(escalations) = &self.escalations {
+            let body: serde_json::Value = response.json().unwrap_or_default();
+            if let Some(message_id) = body["id"].as_str() {
+                escalations.posted_to_discord(event.id, &self.config.channel_id, message_id);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use reqwest::Method;
+use tracing::{info, warn};
+
+use super::{Event, Notifier, Severity};
+use crate::config::EscalationConfig;
+use crate::discord_api::DiscordApi;
+use crate::messages::Messages;
+
+struct Pending {
+    event: Event,
+    last_step_at: Instant,
+    next_step: usize,
+    /// (channel_id, message_id) of the bot post, whose reactions count as an acknowledgement.
+    discord_message: Option<(String, String)>,
+}
+
+/// Shared view of alerts awaiting acknowledgement. Cheap to clone; acknowledgement
+/// sources (console, Discord reactions, API) all act on the same list.
+#[derive(Clone)]
+pub struct Escalations {
+    pending: Arc<Mutex<Vec<Pending>>>,
+    chains: Arc<HashMap<Severity, Vec<String>>>,
+}
+
+impl Escalations {
+    pub fn spawn(
+        config: &EscalationConfig,
+        backends: Vec<Box<dyn Notifier + Send>>,
+        discord: Option<DiscordApi>,
+        messages: Messages,
+    ) -> Self {
+        let escalations = Escalations {
+            pending: Arc::new(Mutex::new(Vec::new())),
+            chains: Arc::new(config.chains.clone()),
+        };
+        let timeout = Duration::from_secs(config.ack_timeout_minutes * 60);
+        let worker = escalations.clone();
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(30));
+            worker.tick(timeout, &backends, discord.as_ref(), &messages);
+        });
+        escalations
+    }
+
+    /// Starts tracking an alert if its severity has an escalation chain.
+    pub fn register(&self, event: &Event) {
+        if !self.chains.contains_key(&event.severity) {
+            return;
+        }
+        self.pending.lock().unwrap().push(Pending {
+            event: event.clone(),
+            last_step_at: Instant::now(),
+            next_step: 0,
+            discord_message: None,
+        });
+    }
+
+    /// Remembers where the bot posted an alert so reactions can acknowledge it.
+    pub fn posted_to_discord(&self, event_id: u64, channel_id: &str, message_id: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(p) = pending.iter_mut().find(|p| p.event.id == event_id) {
+            p.discord_message = Some((channel_id.to_string(), message_id.to_string()));
+        }
+    }
+
+    /// Acknowledges every outstanding alert, returning how many there were.
+    pub fn acknowledge_all(&self) -> usize {
+        let mut pending = self.pending.lock().unwrap();
+        let count = pending.len();
+        pending.clear();
+        count
+    }
+
+    fn tick(
+        &self,
+        timeout: Duration,
+        backends: &[Box<dyn Notifier + Send>],
+        discord: Option<&DiscordApi>,
+        messages: &Messages,
+    ) {
+        // Check reactions without holding the lock across HTTP calls
+        let to_check: Vec<(u64, String, String)> = self
+            .pending
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|p| {
+                let (channel, message) = p.discord_message.clone()?;
+                Some((p.event.id, channel, message))
+            })
+            .collect();
+        let mut reacted = Vec::new();
+        if let Some(api) = discord {
+            for (id, channel, message) in to_check {
+                if has_reactions(api, &channel, &message) {
+                    reacted.push(id);
+                }
+            }
+        }
+
+        let mut due = Vec::new();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            pending.retain(|p| {
+                if reacted.contains(&p.event.id) {
+                    info!("Alert acknowledged via Discord reaction: {}", p.event.message);
+                    return false;
+                }
+                true
+            });
+            pending.retain_mut(|p| {
+                let chain = &self.chains[&p.event.severity];
+                if p.last_step_at.elapsed() < timeout {
+                    return true;
+                }
+                let Some(target) = chain.get(p.next_step) else {
+                    return false;
+                };
+                let minutes = (p.next_step as u64 + 1) * timeout.as_secs() / 60;
+                let mut escalated = p.event.clone();
+                escalated.message = messages.get(
+                    "escalation_message",
+                    &[("minutes", minutes.to_string()), ("message", p.event.message.clone())],
+                );
+                due.push((target.clone(), escalated));
+                p.next_step += 1;
+                p.last_step_at = Instant::now();
+                true
+            });
+        }
+
+        for (target, event) in due {
+            let mut found = false;
+            for backend in backends.iter().filter(|b| b.name() == target) {
+                found = true;
+                warn!("Escalating unacknowledged alert to {}", target);
+                if let Err(e) = backend.notify(&event) {
+                    warn!("Escalation via {} failed: {}", target, e);
+                }
+            }
+            if !found {
+                warn!("Escalation target {} is not a configured backend", target);
+            }
+        }
+    }
+}
+
+fn has_reactions(api: &DiscordApi, channel_id: &str, message_id: &str) -> bool {
+    let path = format!("/channels/{}/messages/{}", channel_id, message_id);
+    match api.request(Method::GET, &path).send() {
+        Ok(r) if r.status().is_success() => {
+            let body: serde_json::Value = r.json().unwrap_or_default();
+            body["reactions"].as_array().is_some_and(|r| !r.is_empty())
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,75 @@
+use std::process::{Command, Stdio};
+
+use super::{DeliveryError, Event, EventKind, Notifier, Severity};
+use crate::config::EventLogConfig;
+
+// Registering a source takes an elevated prompt once; writing to it does not.
+// Everything is passed through the environment so no quoting is needed.
+const SCRIPT: &str = r#"
+if (-not [System.Diagnostics.EventLog]::SourceExists($env:GOLEM_LOG_SOURCE)) {
+    New-EventLog -LogName $env:GOLEM_LOG_NAME -Source $env:GOLEM_LOG_SOURCE -ErrorAction Stop
+}
+Write-EventLog -LogName $env:GOLEM_LOG_NAME -Source $env:GOLEM_LOG_SOURCE -EntryType $env:GOLEM_LOG_TYPE -EventId $env:GOLEM_LOG_ID -Message $env:GOLEM_LOG_MESSAGE -ErrorAction Stop
+"#;
+
+/// Entries in the Windows Event Log, for monitoring that watches it and for
+/// Event Viewer forensics.
+pub struct EventLogNotifier {
+    config: EventLogConfig,
+}
+
+impl EventLogNotifier {
+    pub fn new(config: EventLogConfig) -> Self {
+        EventLogNotifier { config }
+    }
+}
+
+/// Fixed IDs for the events monitoring rules are most likely to match on.
+fn event_id(event: &Event) -> u32 {
+    match event.kind {
+        EventKind::ServerCrashed => 1001,
+        EventKind::WatchdogGaveUp => 1002,
+        EventKind::ServerStartFailed => 1003,
+        EventKind::UpdateFailed => 1004,
+        EventKind::BackupFailed => 1005,
+        EventKind::ServerOutOfMemory => 1006,
+        _ => 1000,
+    }
+}
+
+impl Notifier for EventLogNotifier {
+    fn name(&self) -> &str {
+        "windows-event-log"
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        let entry_type = match event.severity {
+            Severity::Critical => "Error",
+            Severity::Warn => "Warning",
+            Severity::Info | Severity::Debug => "Information",
+        };
+        let message = format!("[{}] {}", event.kind.as_str(), event.text());
+        let status = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+            .env("GOLEM_LOG_NAME", &self.config.log)
+            .env("GOLEM_LOG_SOURCE", &self.config.source)
+            .env("GOLEM_LOG_TYPE", entry_type)
+            .env("GOLEM_LOG_ID", event_id(event).to_string())
+            .env("GOLEM_LOG_MESSAGE", message)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| DeliveryError::Fatal(e.to_string()))?;
+        if !status.success() {
+            return Err(DeliveryError::Fatal(format!(
+                "powershell exited with {} (is the \"{}\" source registered?)",
+                status, self.config.source
+            )));
+        }
+        Ok(())
+    }
+}
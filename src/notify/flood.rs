@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::Event;
+use crate::messages::Messages;
+
+struct Pending {
+    event: Event,
+    first_seen: Instant,
+    repeats: u32,
+}
+
+/// Collapses identical events inside a time window into one follow-up summary.
+pub struct Deduplicator {
+    window: Duration,
+    pending: Vec<Pending>,
+}
+
+impl Deduplicator {
+    pub fn new(window: Duration) -> Self {
+        Deduplicator {
+            window,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns true if the event should go out now, false if it was folded
+    /// into an earlier identical one.
+    pub fn admit(&mut self, event: &Event) -> bool {
+        if self.window.is_zero() {
+            return true;
+        }
+        let now = Instant::now();
+        if let Some(p) = self
+            .pending
+            .iter_mut()
+            .find(|p| p.event.kind == event.kind && p.event.message == event.message)
+        {
+            if now.duration_since(p.first_seen) < self.window {
+                p.repeats += 1;
+                return false;
+            }
+        }
+        self.pending
+            .retain(|p| !(p.event.kind == event.kind && p.event.message == event.message));
+        self.pending.push(Pending {
+            event: event.clone(),
+            first_seen: now,
+            repeats: 0,
+        });
+        true
+    }
+
+    /// Emits summaries for windows that have closed with suppressed repeats.
+    pub fn flush_expired(&mut self, messages: &Messages) -> Vec<Event> {
+        let now = Instant::now();
+        let mut summaries = Vec::new();
+        let window = self.window;
+        self.pending.retain(|p| {
+            let elapsed = now.duration_since(p.first_seen);
+            if elapsed < window {
+                return true;
+            }
+            if p.repeats > 0 {
+                let mut summary = p.event.clone();
+                summary.message = messages.get(
+                    "flood_summary",
+                    &[
+                        ("message", p.event.message.clone()),
+                        ("count", (p.repeats + 1).to_string()),
+                        ("minutes", elapsed.as_secs().div_ceil(60).to_string()),
+                    ],
+                );
+                summary.timestamp = chrono::Local::now();
+                summaries.push(summary);
+            }
+            false
+        });
+        summaries
+    }
+}
+
+/// Global sliding-window cap on delivered messages per minute.
+pub struct RateLimiter {
+    max_per_minute: u32,
+    sent: VecDeque<Instant>,
+    pub dropped: u32,
+}
+
+impl RateLimiter {
+    pub fn new(max_per_minute: u32) -> Self {
+        RateLimiter {
+            max_per_minute,
+            sent: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    pub fn try_acquire(&mut self) -> bool {
+        if self.max_per_minute == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        while let Some(&t) = self.sent.front() {
+            if now.duration_since(t) >= Duration::from_secs(60) {
+                self.sent.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self.sent.len() as u32 >= self.max_per_minute {
+            self.dropped += 1;
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
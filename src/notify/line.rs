@@ -0,0 +1,49 @@
+use reqwest::blocking::Client;
+
+use super::{check_response, DeliveryError, Event, Notifier, Severity};
+use crate::config::LineConfig;
+
+const PUSH_URL: &str = "https://api.line.me/v2/bot/message/push";
+// LINE rejects text messages longer than this
+const MAX_TEXT_CHARS: usize = 5000;
+
+/// Pushes events to a LINE user, group or room via the Messaging API.
+pub struct LineNotifier {
+    client: Client,
+    config: LineConfig,
+}
+
+impl LineNotifier {
+    pub fn new(config: LineConfig) -> Self {
+        LineNotifier {
+            client: Client::new(),
+            config,
+        }
+    }
+}
+
+impl Notifier for LineNotifier {
+    fn name(&self) -> &str {
+        "line"
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        let text: String = event.text().chars().take(MAX_TEXT_CHARS).collect();
+        let payload = serde_json::json!({
+            "to": self.config.target_id,
+            "messages": [{ "type": "text", "text": text }]
+        });
+        let response = self
+            .client
+            .post(PUSH_URL)
+            .bearer_auth(&self.config.channel_access_token)
+            .json(&payload)
+            .send()?;
+        check_response(response)?;
+        Ok(())
+    }
+}
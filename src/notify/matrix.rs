@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use reqwest::blocking::Client;
+use reqwest::Url;
+
+use super::{check_response, DeliveryError, Event, Notifier, Severity};
+use crate::config::MatrixConfig;
+
+/// Posts events into a Matrix room through the client-server API.
+pub struct MatrixNotifier {
+    client: Client,
+    config: MatrixConfig,
+    txn_counter: AtomicU64,
+}
+
+impl MatrixNotifier {
+    pub fn new(config: MatrixConfig) -> Self {
+        MatrixNotifier {
+            client: Client::new(),
+            config,
+            txn_counter: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Notifier for MatrixNotifier {
+    fn name(&self) -> &str {
+        "matrix"
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        // Transaction IDs make retries idempotent: the homeserver drops duplicates
+        let txn_id = format!(
+            "golem-{}-{}",
+            event.timestamp.timestamp_millis(),
+            self.txn_counter.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut url = Url::parse(&self.config.homeserver_url)
+            .map_err(|e| DeliveryError::Fatal(format!("invalid homeserver_url: {}", e)))?;
+        url.path_segments_mut()
+            .map_err(|_| DeliveryError::Fatal("invalid homeserver_url".to_string()))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.config.room_id,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+
+        let payload = serde_json::json!({
+            "msgtype": "m.text",
+            "body": event.text()
+        });
+        let response = self
+            .client
+            .put(url)
+            .bearer_auth(&self.config.access_token)
+            .json(&payload)
+            .send()?;
+        check_response(response)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,504 @@
+mod alarm;
+mod discord;
+mod discord_bot;
+mod escalation;
+mod event_log;
+mod flood;
+mod line;
+mod matrix;
+mod ntfy;
+mod pushover;
+mod queue;
+mod toast;
+mod webhook;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use reqwest::blocking::Response;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::config::{Config, EventSwitches};
+use crate::discord_api::DiscordApi;
+use crate::feed::LogFeed;
+use crate::messages::Messages;
+
+pub use alarm::AlarmNotifier;
+pub use discord::DiscordNotifier;
+pub use discord_bot::DiscordBotNotifier;
+pub use escalation::Escalations;
+pub use event_log::EventLogNotifier;
+pub use line::LineNotifier;
+pub use matrix::MatrixNotifier;
+pub use ntfy::NtfyNotifier;
+pub use pushover::PushoverNotifier;
+pub use toast::ToastNotifier;
+pub use webhook::WebhookNotifier;
+
+use flood::{Deduplicator, RateLimiter};
+use queue::DeliveryQueue;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    GolemStarted,
+    GolemUnresponsive,
+    ServerStarting,
+    ServerStartFailed,
+    ServerStopping,
+    ServerCrashed,
+    ServerOutOfMemory,
+    ServerFrozen,
+    StopWarning,
+    WatchdogGaveUp,
+    DailyDigest,
+    PlayerJoined,
+    PlayerLeft,
+    BackupCompleted,
+    BackupFailed,
+    BackupRestored,
+    UpdateFailed,
+    ScheduleChanged,
+    DiskSpaceLow,
+    Lag,
+    ProbeFailing,
+    WeeklyReport,
+    StartupSlow,
+    MemoryGrowth,
+    WorldGrowth,
+    LogAnomaly,
+    TestNotification,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::GolemStarted => "golem_started",
+            EventKind::GolemUnresponsive => "golem_unresponsive",
+            EventKind::ServerStarting => "server_starting",
+            EventKind::ServerStartFailed => "server_start_failed",
+            EventKind::ServerStopping => "server_stopping",
+            EventKind::ServerCrashed => "server_crashed",
+            EventKind::ServerOutOfMemory => "server_out_of_memory",
+            EventKind::ServerFrozen => "server_frozen",
+            EventKind::StopWarning => "stop_warning",
+            EventKind::WatchdogGaveUp => "watchdog_gave_up",
+            EventKind::DailyDigest => "daily_digest",
+            EventKind::PlayerJoined => "player_joined",
+            EventKind::PlayerLeft => "player_left",
+            EventKind::BackupCompleted => "backup_completed",
+            EventKind::BackupFailed => "backup_failed",
+            EventKind::BackupRestored => "backup_restored",
+            EventKind::UpdateFailed => "update_failed",
+            EventKind::ScheduleChanged => "schedule_changed",
+            EventKind::DiskSpaceLow => "disk_space_low",
+            EventKind::Lag => "lag",
+            EventKind::ProbeFailing => "probe_failing",
+            EventKind::WeeklyReport => "weekly_report",
+            EventKind::StartupSlow => "startup_slow",
+            EventKind::MemoryGrowth => "memory_growth",
+            EventKind::WorldGrowth => "world_growth",
+            EventKind::LogAnomaly => "log_anomaly",
+            EventKind::TestNotification => "test",
+        }
+    }
+
+    pub fn enabled(&self, switches: &EventSwitches) -> bool {
+        match self {
+            EventKind::GolemStarted => switches.golem_start,
+            EventKind::GolemUnresponsive => switches.golem_unresponsive,
+            EventKind::ServerStarting => switches.start,
+            EventKind::ServerStartFailed => switches.start_failed,
+            EventKind::ServerStopping => switches.stop,
+            EventKind::ServerCrashed => switches.crash,
+            EventKind::ServerOutOfMemory => switches.out_of_memory,
+            EventKind::ServerFrozen => switches.freeze,
+            EventKind::StopWarning => switches.warning,
+            EventKind::WatchdogGaveUp => switches.watchdog,
+            EventKind::DailyDigest => switches.digest,
+            EventKind::PlayerJoined => switches.player_join,
+            EventKind::PlayerLeft => switches.player_leave,
+            EventKind::BackupCompleted => switches.backup,
+            EventKind::BackupFailed => switches.backup_failed,
+            EventKind::BackupRestored => switches.restore,
+            EventKind::UpdateFailed => switches.update_failed,
+            EventKind::ScheduleChanged => switches.schedule,
+            EventKind::DiskSpaceLow => switches.disk_space,
+            EventKind::Lag => switches.lag,
+            EventKind::ProbeFailing => switches.probe,
+            EventKind::WeeklyReport => switches.weekly_report,
+            EventKind::StartupSlow => switches.startup_slow,
+            EventKind::MemoryGrowth => switches.memory_growth,
+            EventKind::WorldGrowth => switches.world_growth,
+            EventKind::LogAnomaly => switches.log_anomaly,
+            EventKind::TestNotification => true,
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            EventKind::GolemStarted
+            | EventKind::ServerStarting
+            | EventKind::StopWarning
+            | EventKind::ServerStopping
+            | EventKind::DailyDigest
+            | EventKind::WeeklyReport
+            | EventKind::PlayerJoined
+            | EventKind::PlayerLeft
+            | EventKind::BackupCompleted
+            | EventKind::ScheduleChanged
+            | EventKind::TestNotification => Severity::Info,
+            EventKind::ServerStartFailed
+            | EventKind::ServerCrashed
+            | EventKind::ServerOutOfMemory
+            | EventKind::ServerFrozen
+            | EventKind::BackupFailed
+            | EventKind::BackupRestored
+            | EventKind::DiskSpaceLow
+            | EventKind::Lag
+            | EventKind::ProbeFailing
+            | EventKind::StartupSlow
+            | EventKind::MemoryGrowth
+            | EventKind::WorldGrowth
+            | EventKind::LogAnomaly => Severity::Warn,
+            EventKind::WatchdogGaveUp | EventKind::UpdateFailed | EventKind::GolemUnresponsive => Severity::Critical,
+        }
+    }
+}
+
+/// A file uploaded alongside the message by backends that support it.
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub filename: String,
+    pub content: Vec<u8>,
+}
+
+static NEXT_EVENT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// Unique per process; used to correlate deliveries of the same event.
+    pub id: u64,
+    pub kind: EventKind,
+    pub severity: Severity,
+    pub message: String,
+    /// Structured details, rendered as embed fields where the backend supports it.
+    pub fields: Vec<(String, String)>,
+    pub attachments: Vec<Attachment>,
+    pub timestamp: DateTime<Local>,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, message: impl Into<String>) -> Self {
+        Event {
+            id: NEXT_EVENT_ID.fetch_add(1, Ordering::Relaxed),
+            kind,
+            severity: kind.severity(),
+            message: message.into(),
+            fields: Vec::new(),
+            attachments: Vec::new(),
+            timestamp: Local::now(),
+        }
+    }
+
+    pub fn with_fields(mut self, fields: Vec<(String, String)>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn with_attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    /// Message followed by one `name: value` line per field, for plain-text backends.
+    pub fn text(&self) -> String {
+        let mut text = self.message.clone();
+        for (name, value) in &self.fields {
+            text.push_str(&format!("\n{}: {}", name, value));
+        }
+        text
+    }
+
+    /// Placeholder values available to user-defined templates.
+    pub fn template_vars(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("event", self.kind.as_str().to_string()),
+            ("severity", self.severity.as_str().to_string()),
+            ("message", self.message.clone()),
+            ("text", self.text()),
+            ("timestamp", self.timestamp.to_rfc3339()),
+        ]
+    }
+}
+
+#[derive(Debug)]
+pub enum DeliveryError {
+    /// Transient failure (network, 5xx, rate limit); `after` is the server-requested delay.
+    Retry {
+        reason: String,
+        after: Option<Duration>,
+    },
+    /// The request will never succeed as-is (bad URL, 4xx), so retrying is pointless.
+    Fatal(String),
+}
+
+impl std::fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeliveryError::Retry { reason, .. } => write!(f, "{} (transient)", reason),
+            DeliveryError::Fatal(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl From<reqwest::Error> for DeliveryError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_builder() {
+            DeliveryError::Fatal(e.to_string())
+        } else {
+            DeliveryError::Retry {
+                reason: e.to_string(),
+                after: None,
+            }
+        }
+    }
+}
+
+/// Maps an HTTP response to a delivery result, honouring 429 `Retry-After` headers.
+pub fn check_response(response: Response) -> Result<Response, DeliveryError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let after = ["retry-after", "x-ratelimit-reset-after"]
+            .iter()
+            .find_map(|name| response.headers().get(*name))
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(Duration::from_secs_f64);
+        return Err(DeliveryError::Retry {
+            reason: "rate limited (HTTP 429)".to_string(),
+            after,
+        });
+    }
+    if status.is_server_error() {
+        return Err(DeliveryError::Retry {
+            reason: format!("HTTP {}", status),
+            after: None,
+        });
+    }
+    let body = response.text().unwrap_or_default();
+    Err(DeliveryError::Fatal(format!("HTTP {} {}", status, body.trim())))
+}
+
+pub trait Notifier {
+    fn name(&self) -> &str;
+    /// Events below this severity are never handed to the backend.
+    fn min_level(&self) -> Severity {
+        Severity::Debug
+    }
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError>;
+}
+
+/// The `max_per_minute` cap, shared by every server's dispatcher.
+static LIMITER: OnceLock<Mutex<RateLimiter>> = OnceLock::new();
+
+/// Fans every event out to all configured notification backends.
+/// Cheap to clone; all clones feed the same dispatcher thread.
+#[derive(Clone)]
+pub struct Notifiers {
+    sender: Sender<Event>,
+    escalations: Option<Escalations>,
+    feed: Option<LogFeed>,
+    server_name: Option<String>,
+}
+
+impl Notifiers {
+    pub fn from_config(config: &Config, messages: &Messages) -> Self {
+        let settings = &config.notifications;
+        let messages = messages.clone();
+        let escalations = config.escalation.as_ref().map(|escalation| {
+            let discord = config.discord_bot.as_ref().map(|bot| DiscordApi::new(&bot.token));
+            Escalations::spawn(escalation, build_backends(config, None), discord, messages.clone())
+        });
+        let queues: Vec<DeliveryQueue> = build_backends(config, escalations.as_ref())
+            .into_iter()
+            .map(|backend| DeliveryQueue::spawn(backend, settings.max_attempts))
+            .collect();
+        let mut dedup = Deduplicator::new(Duration::from_secs(settings.dedup_window_secs));
+        let limiter = LIMITER.get_or_init(|| Mutex::new(RateLimiter::new(settings.max_per_minute)));
+        let level = config.notification_level;
+        let switches = settings.events.clone();
+
+        let tracker = escalations.clone();
+        let (sender, receiver) = mpsc::channel::<Event>();
+        thread::spawn(move || loop {
+            let incoming = match receiver.recv_timeout(Duration::from_secs(5)) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+
+            let mut outgoing = dedup.flush_expired(&messages);
+            let incoming = incoming.filter(|e| e.severity >= level && e.kind.enabled(&switches));
+            if let Some(event) = incoming {
+                if dedup.admit(&event) {
+                    outgoing.push(event);
+                }
+            }
+
+            for event in outgoing {
+                // Critical alerts go out however busy the minute has been
+                if event.severity < Severity::Critical {
+                    let mut limiter = limiter.lock().unwrap();
+                    if !limiter.try_acquire() {
+                        warn!("Notification dropped (rate limit): {}", event.message);
+                        continue;
+                    }
+                    if limiter.dropped > 0 {
+                        // Reuse the slot we just acquired to tell users what they missed
+                        let notice = Event::new(
+                            event.kind,
+                            messages.get("flood_suppressed", &[("count", limiter.dropped.to_string())]),
+                        );
+                        limiter.dropped = 0;
+                        for queue in &queues {
+                            queue.push(notice.clone());
+                        }
+                        if !limiter.try_acquire() {
+                            continue;
+                        }
+                    }
+                }
+                if let Some(tracker) = &tracker {
+                    tracker.register(&event);
+                }
+                for queue in &queues {
+                    queue.push(event.clone());
+                }
+            }
+        });
+        Notifiers {
+            sender,
+            escalations,
+            feed: None,
+            server_name: None,
+        }
+    }
+
+    /// Also shows every event, whatever its level, on the live log feed.
+    pub fn with_feed(mut self, feed: LogFeed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+
+    /// Starts every message with "[<name>] ", to tell servers apart.
+    pub fn with_server_name(mut self, name: &str) -> Self {
+        self.server_name = Some(name.to_string());
+        self
+    }
+
+    pub fn escalations(&self) -> Option<&Escalations> {
+        self.escalations.as_ref()
+    }
+
+    pub fn send(&self, kind: EventKind, message: &str) {
+        self.dispatch(Event::new(kind, message));
+    }
+
+    pub fn dispatch(&self, mut event: Event) {
+        if let Some(name) = &self.server_name {
+            event.message = format!("[{}] {}", name, event.message);
+        }
+        if let Some(feed) = &self.feed {
+            feed.event(&event);
+        }
+        let _ = self.sender.send(event);
+    }
+
+    pub fn send_with_fields(&self, kind: EventKind, message: &str, fields: Vec<(String, String)>) {
+        self.dispatch(Event::new(kind, message).with_fields(fields));
+    }
+}
+
+/// Instantiates every configured backend. `escalations` lets the bot backend
+/// report where alerts were posted so reactions can acknowledge them.
+pub fn build_backends(
+    config: &Config,
+    escalations: Option<&Escalations>,
+) -> Vec<Box<dyn Notifier + Send>> {
+    let mut backends: Vec<Box<dyn Notifier + Send>> = Vec::new();
+    backends.push(Box::new(DiscordNotifier::new(
+        "discord",
+        &config.discord_webhook_url,
+        config.discord_min_level,
+    )));
+    for discord in &config.discord_webhooks {
+        let name = discord.name.as_deref().unwrap_or("discord");
+        backends.push(Box::new(DiscordNotifier::new(name, &discord.url, discord.min_level)));
+    }
+    if let Some(bot) = &config.discord_bot {
+        backends.push(Box::new(DiscordBotNotifier::new(bot.clone(), escalations.cloned())));
+    }
+    for webhook in &config.webhooks {
+        backends.push(Box::new(WebhookNotifier::new(webhook.clone())));
+    }
+    if let Some(ntfy) = &config.ntfy {
+        backends.push(Box::new(NtfyNotifier::new(ntfy.clone())));
+    }
+    if let Some(pushover) = &config.pushover {
+        backends.push(Box::new(PushoverNotifier::new(pushover.clone())));
+    }
+    if let Some(line) = &config.line {
+        backends.push(Box::new(LineNotifier::new(line.clone())));
+    }
+    if let Some(matrix) = &config.matrix {
+        backends.push(Box::new(MatrixNotifier::new(matrix.clone())));
+    }
+    if let Some(alarm) = &config.alarm {
+        backends.push(Box::new(AlarmNotifier::new(alarm.clone())));
+    }
+    if let Some(toast) = &config.windows_toast {
+        if cfg!(target_os = "windows") {
+            backends.push(Box::new(ToastNotifier::new(toast.clone())));
+        } else {
+            warn!("[windows_toast] is configured but only works on Windows; ignoring it.");
+        }
+    }
+    if let Some(event_log) = &config.windows_event_log {
+        if cfg!(target_os = "windows") {
+            backends.push(Box::new(EventLogNotifier::new(event_log.clone())));
+        } else {
+            warn!("[windows_event_log] is configured but only works on Windows; ignoring it.");
+        }
+    }
+    backends
+}
@@ -0,0 +1,51 @@
+use reqwest::blocking::Client;
+
+use super::{check_response, DeliveryError, Event, Notifier, Severity};
+use crate::config::NtfyConfig;
+
+/// Push notifications through an ntfy topic (ntfy.sh or self-hosted).
+pub struct NtfyNotifier {
+    client: Client,
+    config: NtfyConfig,
+}
+
+impl NtfyNotifier {
+    pub fn new(config: NtfyConfig) -> Self {
+        NtfyNotifier {
+            client: Client::new(),
+            config,
+        }
+    }
+}
+
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &str {
+        "ntfy"
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        // ntfy priorities: 1 = min ... 5 = max (bypasses do-not-disturb on most phones)
+        let priority = match event.severity {
+            Severity::Debug => "2",
+            Severity::Info => "3",
+            Severity::Warn => "4",
+            Severity::Critical => "5",
+        };
+        let mut request = self
+            .client
+            .post(&self.config.topic_url)
+            .header("Title", "Rusty-Golem")
+            .header("Priority", priority)
+            .header("Tags", event.kind.as_str())
+            .body(event.text());
+        if let Some(token) = &self.config.token {
+            request = request.bearer_auth(token);
+        }
+        check_response(request.send()?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use reqwest::blocking::Client;
+
+use super::{check_response, DeliveryError, Event, Notifier, Severity};
+use crate::config::PushoverConfig;
+
+const API_URL: &str = "https://api.pushover.net/1/messages.json";
+
+pub struct PushoverNotifier {
+    client: Client,
+    config: PushoverConfig,
+}
+
+impl PushoverNotifier {
+    pub fn new(config: PushoverConfig) -> Self {
+        PushoverNotifier {
+            client: Client::new(),
+            config,
+        }
+    }
+}
+
+impl Notifier for PushoverNotifier {
+    fn name(&self) -> &str {
+        "pushover"
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        // Pushover priorities: -1 quiet, 0 normal, 1 high (bypasses quiet hours)
+        let priority = match event.severity {
+            Severity::Debug => "-1",
+            Severity::Info => "0",
+            Severity::Warn | Severity::Critical => "1",
+        };
+        let text = event.text();
+        let form = [
+            ("token", self.config.token.as_str()),
+            ("user", self.config.user.as_str()),
+            ("title", "Rusty-Golem"),
+            ("message", text.as_str()),
+            ("priority", priority),
+        ];
+        check_response(self.client.post(API_URL).form(&form).send()?)?;
+        Ok(())
+    }
+}
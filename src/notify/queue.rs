@@ -0,0 +1,77 @@
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use tracing::{error, warn};
+
+use super::{DeliveryError, Event, Notifier, Severity};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Delivers events for a single backend on its own thread, so a slow or
+/// unreachable endpoint never blocks the main loop or the other backends.
+pub struct DeliveryQueue {
+    sender: Sender<Event>,
+    min_level: Severity,
+}
+
+impl DeliveryQueue {
+    pub fn spawn(backend: Box<dyn Notifier + Send>, max_attempts: u32) -> Self {
+        let min_level = backend.min_level();
+        let (sender, receiver) = mpsc::channel::<Event>();
+        thread::spawn(move || {
+            for event in receiver {
+                deliver(backend.as_ref(), &event, max_attempts);
+            }
+        });
+        DeliveryQueue { sender, min_level }
+    }
+
+    pub fn push(&self, event: Event) {
+        if event.severity < self.min_level {
+            return;
+        }
+        let _ = self.sender.send(event);
+    }
+}
+
+fn deliver(backend: &dyn Notifier, event: &Event, max_attempts: u32) {
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=max_attempts.max(1) {
+        match backend.notify(event) {
+            Ok(()) => return,
+            Err(DeliveryError::Fatal(reason)) => {
+                error!(
+                    "Notification via {} permanently failed ({}): {}",
+                    backend.name(),
+                    reason,
+                    event.message
+                );
+                return;
+            }
+            Err(DeliveryError::Retry { reason, after }) => {
+                if attempt == max_attempts.max(1) {
+                    error!(
+                        "Notification via {} permanently failed after {} attempts ({}): {}",
+                        backend.name(),
+                        attempt,
+                        reason,
+                        event.message
+                    );
+                    return;
+                }
+                // Rate limits tell us exactly how long to wait; otherwise back off exponentially
+                let wait = after.unwrap_or(backoff);
+                warn!(
+                    "Notification via {} failed ({}), retrying in {}s",
+                    backend.name(),
+                    reason,
+                    wait.as_secs_f32()
+                );
+                thread::sleep(wait);
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
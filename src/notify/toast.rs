@@ -0,0 +1,57 @@
+use std::process::{Command, Stdio};
+
+use super::{DeliveryError, Event, Notifier, Severity};
+use crate::config::ToastConfig;
+
+// Toasts need a registered AppUserModelID; PowerShell's own is always present.
+const POWERSHELL_APP_ID: &str =
+    "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+// Title and body are passed through the environment so no quoting is needed.
+const SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null
+$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $template.GetElementsByTagName('text')
+$text.Item(0).AppendChild($template.CreateTextNode($env:GOLEM_TOAST_TITLE)) > $null
+$text.Item(1).AppendChild($template.CreateTextNode($env:GOLEM_TOAST_BODY)) > $null
+$toast = [Windows.UI.Notifications.ToastNotification]::new($template)
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:GOLEM_TOAST_APP_ID).Show($toast)
+"#;
+
+/// Local Windows toast notifications on the host running the golem.
+pub struct ToastNotifier {
+    config: ToastConfig,
+}
+
+impl ToastNotifier {
+    pub fn new(config: ToastConfig) -> Self {
+        ToastNotifier { config }
+    }
+}
+
+impl Notifier for ToastNotifier {
+    fn name(&self) -> &str {
+        "windows-toast"
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        let app_id = self.config.app_id.as_deref().unwrap_or(POWERSHELL_APP_ID);
+        let status = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+            .env("GOLEM_TOAST_TITLE", "Rusty-Golem")
+            .env("GOLEM_TOAST_BODY", event.text())
+            .env("GOLEM_TOAST_APP_ID", app_id)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| DeliveryError::Fatal(e.to_string()))?;
+        if !status.success() {
+            return Err(DeliveryError::Fatal(format!("powershell exited with {}", status)));
+        }
+        Ok(())
+    }
+}
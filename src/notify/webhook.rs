@@ -0,0 +1,49 @@
+use reqwest::blocking::Client;
+use reqwest::Method;
+
+use super::{check_response, DeliveryError, Event, Notifier, Severity};
+use crate::config::WebhookConfig;
+use crate::template;
+
+/// Posts events to an arbitrary HTTP endpoint using a user-defined JSON body.
+pub struct WebhookNotifier {
+    client: Client,
+    config: WebhookConfig,
+    body: serde_json::Value,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Self {
+        // Checked when the config was loaded
+        let body = config.body_template().unwrap_or_else(|e| panic!("{}", e));
+        WebhookNotifier {
+            client: Client::new(),
+            config,
+            body,
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        self.config.name.as_deref().unwrap_or(&self.config.url)
+    }
+
+    fn min_level(&self) -> Severity {
+        self.config.min_level
+    }
+
+    fn notify(&self, event: &Event) -> Result<(), DeliveryError> {
+        let method = Method::from_bytes(self.config.method.to_uppercase().as_bytes())
+            .map_err(|e| DeliveryError::Fatal(e.to_string()))?;
+        let payload = template::render_json(&self.body, &event.template_vars());
+
+        let mut request = self.client.request(method, &self.config.url).json(&payload);
+        for (name, value) in &self.config.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        check_response(request.send()?)?;
+        Ok(())
+    }
+}
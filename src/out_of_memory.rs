@@ -0,0 +1,163 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tracing::{info, warn};
+
+use crate::config::{Config, OutOfMemoryConfig};
+use crate::lifecycle;
+use crate::messages::Messages;
+use crate::metrics;
+use crate::server_log;
+
+/// Lowercase fragments of what the JVM prints when it runs out, in the
+/// console (`java.lang.OutOfMemoryError: Java heap space`, `... Metaspace`,
+/// `... unable to create native thread`) or in its fatal error log.
+const SIGNATURES: &[&str] = &[
+    "java.lang.outofmemoryerror",
+    "there is insufficient memory for the java runtime environment",
+    "out of memory error",
+];
+
+/// A server that went down for want of memory.
+pub struct OutOfMemory {
+    /// The line saying so.
+    pub line: String,
+    /// The JVM's fatal error log, `hs_err_pid<pid>.log`, if it wrote one.
+    pub error_log: Option<PathBuf>,
+}
+
+/// Whether the console's last lines, or an `hs_err_pid*.log` written since
+/// `since`, say the server ran out of memory. The JVM writes that log to its
+/// working directory: the golem's, unless the start script changes it. With
+/// several servers the golem's is left out, as any of them may have written
+/// one there.
+pub fn detect(lines: &[String], server_dir: &Path, shared: bool, since: SystemTime) -> Option<OutOfMemory> {
+    let dirs: &[&Path] = if shared { &[server_dir] } else { &[server_dir, Path::new(".")] };
+    let error_log = dirs
+        .iter()
+        .filter_map(|dir| newest_error_log(dir, since))
+        .max_by_key(|(modified, _)| *modified)
+        .and_then(|(_, path)| {
+            let line = find(fs::read_to_string(&path).ok()?.lines())?;
+            Some((path, line))
+        });
+    let line = find(lines.iter().map(String::as_str)).or_else(|| error_log.as_ref().map(|(_, line)| line.clone()))?;
+    Some(OutOfMemory { line, error_log: error_log.map(|(path, _)| path) })
+}
+
+fn find<'a>(mut lines: impl Iterator<Item = &'a str>) -> Option<String> {
+    lines
+        .find(|line| {
+            let lower = line.to_lowercase();
+            SIGNATURES.iter().any(|fragment| lower.contains(fragment))
+        })
+        .map(|line| line.trim_start_matches('#').trim().to_string())
+}
+
+fn newest_error_log(dir: &Path, since: SystemTime) -> Option<(SystemTime, PathBuf)> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !(name.starts_with("hs_err_pid") && name.ends_with(".log")) {
+                return None;
+            }
+            let modified = entry.metadata().ok()?.modified().ok().filter(|m| *m >= since)?;
+            Some((modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+}
+
+/// Whether a start script line runs java with a heap limit, and is not a
+/// comment (`REM`, `::` or `#`).
+fn is_java_line(line: &str) -> bool {
+    let command = line.trim_start().trim_start_matches('@').to_ascii_lowercase();
+    let comment = command == "rem" || command.starts_with("rem ") || command.starts_with("::") || command.starts_with('#');
+    !comment && command.contains("java") && line.contains("-Xmx")
+}
+
+/// The `-Xmx` on a start script's java command line, in MB.
+pub fn heap_limit(script: &str) -> Option<u64> {
+    let line = script.lines().find(|line| is_java_line(line))?;
+    let (_, rest) = line.split_once("-Xmx")?;
+    let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    let value: u64 = digits.parse().ok()?;
+    match rest[digits.len()..].chars().next() {
+        Some('g' | 'G') => Some(value * 1024),
+        Some('m' | 'M') => Some(value),
+        Some('k' | 'K') => Some(value / 1024),
+        _ => Some(value / (1024 * 1024)),
+    }
+}
+
+/// Raises the `-Xmx` on the start script's java command line by a step, to
+/// at most the ceiling. The script as it was is kept as `<script>.bak`, and
+/// the new one replaces it in one go. Returns the limit before and after, or
+/// None when the script sets none or it is at the ceiling already.
+pub fn raise_heap(script_path: &str, config: &OutOfMemoryConfig) -> io::Result<Option<(u64, u64)>> {
+    let script = fs::read_to_string(script_path)?;
+    let Some(current) = heap_limit(&script) else {
+        return Ok(None);
+    };
+    let raised = (current + config.step_mb).min(config.max_heap_mb);
+    if raised <= current {
+        return Ok(None);
+    }
+    let mut rewritten = String::with_capacity(script.len());
+    let mut done = false;
+    for line in script.split_inclusive('\n') {
+        match line.split_once("-Xmx").filter(|_| !done && is_java_line(line)) {
+            Some((before, value)) => {
+                let end = value.find(|c: char| c.is_whitespace() || c == '"' || c == '\'').unwrap_or(value.len());
+                rewritten.push_str(&format!("{}-Xmx{}M{}", before, raised, &value[end..]));
+                done = true;
+            }
+            None => rewritten.push_str(line),
+        }
+    }
+    fs::copy(script_path, format!("{}.bak", script_path))?;
+    let temporary = format!("{}.tmp", script_path);
+    fs::write(&temporary, rewritten)?;
+    // Keeping it executable, where that is a permission
+    fs::set_permissions(&temporary, fs::metadata(script_path)?.permissions())?;
+    fs::rename(&temporary, script_path)?;
+    Ok(Some((current, raised)))
+}
+
+/// Notification fields for a server that ran out: what the JVM said, the
+/// heap it had, the memory it last used and, when `[watchdog.out_of_memory]`
+/// is set, the heap it gets on the next start, raised here first.
+pub fn respond(oom: &OutOfMemory, config: &Config, rss: Option<u64>, messages: &Messages) -> Vec<(String, String)> {
+    let mb = |mb: u64| metrics::format_bytes(mb * 1024 * 1024);
+    let mut fields = vec![(messages.get("oom_error", &[]), server_log::message(&oom.line).to_string())];
+    let limit = fs::read_to_string(&config.server_bat_path).ok().and_then(|script| heap_limit(&script));
+    if let Some(limit) = limit {
+        fields.push((messages.get("oom_heap_limit", &[]), mb(limit)));
+    }
+    if let Some(rss) = rss {
+        fields.push((messages.get("oom_memory_used", &[]), metrics::format_bytes(rss)));
+    }
+    let Some(raise) = config.watchdog.out_of_memory.as_ref() else {
+        return fields;
+    };
+    let next = match raise_heap(&config.server_bat_path, raise) {
+        Ok(Some((from, to))) => {
+            info!("Out of memory: raised -Xmx in {} from {} MB to {} MB.", config.server_bat_path, from, to);
+            lifecycle::record("heap_raised", &format!("-Xmx {} MB to {} MB", from, to), None);
+            messages.get("oom_heap_raised", &[("heap", mb(to))])
+        }
+        Ok(None) => match limit {
+            Some(limit) => messages.get("oom_heap_ceiling", &[("heap", mb(limit))]),
+            None => messages.get("oom_heap_not_found", &[]),
+        },
+        Err(e) => {
+            warn!("Out of memory: could not raise -Xmx in {}: {}", config.server_bat_path, e);
+            messages.get("oom_heap_failed", &[])
+        }
+    };
+    fields.push((messages.get("oom_heap_next", &[]), next));
+    fields
+}
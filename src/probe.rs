@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::config::ProbeConfig;
+use crate::messages::Messages;
+use crate::notify::{EventKind, Notifiers};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Asks the server port for its status the way the multiplayer screen does,
+/// from a thread of its own, and warns when answers get slow or stop coming
+/// while java itself still runs.
+#[derive(Clone)]
+pub struct Probe {
+    /// Set by the main loop while the server is up and has finished starting.
+    active: Arc<AtomicBool>,
+    latency: Arc<Mutex<Option<Duration>>>,
+    /// Whether the latest ping got no answer at all.
+    unanswered: Arc<AtomicBool>,
+}
+
+impl Probe {
+    pub fn spawn(config: &ProbeConfig, address: String, messages: Messages, notifiers: Notifiers) -> Probe {
+        let probe = Probe {
+            active: Arc::new(AtomicBool::new(false)),
+            latency: Arc::new(Mutex::new(None)),
+            unanswered: Arc::new(AtomicBool::new(false)),
+        };
+        let interval = Duration::from_secs(config.interval_seconds.max(10));
+        let max_latency = Duration::from_millis(config.max_latency_ms);
+        let (window, max_failure_percent) = (config.window.max(1), config.max_failure_percent);
+        let shared = probe.clone();
+        thread::spawn(move || {
+            // The latest results, true for an answer in time
+            let mut results: VecDeque<bool> = VecDeque::new();
+            let mut alerted = false;
+            loop {
+                thread::sleep(interval);
+                if !shared.active.load(Ordering::Relaxed) {
+                    results.clear();
+                    alerted = false;
+                    *shared.latency.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                    shared.unanswered.store(false, Ordering::Relaxed);
+                    continue;
+                }
+                let outcome = status_ping(&address);
+                shared.unanswered.store(outcome.is_err(), Ordering::Relaxed);
+                *shared.latency.lock().unwrap_or_else(|e| e.into_inner()) = outcome.as_ref().ok().copied();
+                results.push_back(outcome.as_ref().is_ok_and(|rtt| *rtt <= max_latency));
+                if results.len() > window {
+                    results.pop_front();
+                }
+                let failed = results.iter().filter(|ok| !**ok).count();
+                let failure_percent = failed as u64 * 100 / results.len() as u64;
+                let slow = outcome.as_ref().is_ok_and(|rtt| *rtt > max_latency);
+                let failing = results.len() == window && failure_percent >= max_failure_percent;
+                if (slow || failing) && !alerted {
+                    alerted = true;
+                    let message = match &outcome {
+                        Ok(rtt) if slow => messages.get(
+                            "probe_slow",
+                            &[("address", address.clone()), ("ms", rtt.as_millis().to_string())],
+                        ),
+                        _ => messages.get(
+                            "probe_failing",
+                            &[("address", address.clone()), ("percent", failure_percent.to_string())],
+                        ),
+                    };
+                    match &outcome {
+                        Ok(rtt) => warn!("Probe: {} answered in {} ms", address, rtt.as_millis()),
+                        Err(e) => warn!("Probe: {} did not answer: {}", address, e),
+                    }
+                    notifiers.send(EventKind::ProbeFailing, &message);
+                } else if alerted && !slow && failed == 0 {
+                    alerted = false;
+                    info!("Probe: {} answers normally again", address);
+                }
+            }
+        });
+        probe
+    }
+
+    pub fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Relaxed);
+    }
+
+    /// Whether the latest ping went unanswered.
+    pub fn unanswered(&self) -> bool {
+        self.unanswered.load(Ordering::Relaxed)
+    }
+
+    /// The round trip of the last successful probe, while probing.
+    pub fn latency(&self) -> Option<Duration> {
+        *self.latency.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Connects, sends a handshake and a status request and reads the answer:
+/// the time all of that took.
+fn status_ping(address: &str) -> io::Result<Duration> {
+    let started = Instant::now();
+    let target = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("cannot resolve {}", address)))?;
+    let mut stream = TcpStream::connect_timeout(&target, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let (host, port) = address.rsplit_once(':').unwrap_or((address, "25565"));
+    let mut handshake = vec![0x00];
+    // Protocol version -1: "just asking"
+    write_varint(&mut handshake, -1);
+    write_varint(&mut handshake, host.len() as i32);
+    handshake.extend_from_slice(host.as_bytes());
+    handshake.extend_from_slice(&port.parse::<u16>().unwrap_or(25565).to_be_bytes());
+    // Next state: status
+    write_varint(&mut handshake, 1);
+    let mut request = Vec::new();
+    write_varint(&mut request, handshake.len() as i32);
+    request.extend_from_slice(&handshake);
+    // Status request: length 1, packet id 0
+    request.extend_from_slice(&[0x01, 0x00]);
+    stream.write_all(&request)?;
+
+    let length = read_varint(&mut stream)?;
+    if !(1..=1 << 21).contains(&length) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a Minecraft status answer"));
+    }
+    let mut answer = vec![0; length as usize];
+    stream.read_exact(&mut answer)?;
+    if answer[0] != 0x00 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a Minecraft status answer"));
+    }
+    Ok(started.elapsed())
+}
+
+fn write_varint(out: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7f == 0 {
+            out.push(value as u8);
+            return;
+        }
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_varint(stream: &mut impl Read) -> io::Result<i32> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u32) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "varint too long"))
+}
@@ -0,0 +1,116 @@
+use std::fmt::Write;
+
+use chrono::Local;
+
+use crate::backup::JobStats;
+use crate::control::Status;
+
+/// The status in the Prometheus text exposition format. Figures that only
+/// exist while the server runs (TPS, memory, uptime) are left out otherwise,
+/// so they show up as gaps rather than stale values.
+pub fn render(status: &Status) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: Option<f64>| {
+        if let Some(value) = value {
+            let _ = writeln!(out, "# HELP rusty_golem_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE rusty_golem_{} {}", name, kind);
+            let _ = writeln!(out, "rusty_golem_{} {}", name, value);
+        }
+    };
+    let online = |value: Option<f64>| value.filter(|_| status.online);
+    let flag = |on: bool| Some(if on { 1.0 } else { 0.0 });
+
+    metric("server_up", "gauge", "Whether the Minecraft server is running.", flag(status.online));
+    metric(
+        "uptime_seconds",
+        "gauge",
+        "How long the server has been running.",
+        online(status.online_since.map(|t| (Local::now() - t).num_milliseconds() as f64 / 1000.0)),
+    );
+    metric("player_count", "gauge", "Players online.", Some(status.players as f64));
+    metric("tps", "gauge", "Server ticks per second, from tps_command.", online(status.tps));
+    metric(
+        "process_rss_bytes",
+        "gauge",
+        "Resident memory of the server process and its children.",
+        online(status.rss_bytes.map(|b| b as f64)),
+    );
+    metric(
+        "probe_latency_seconds",
+        "gauge",
+        "Round trip of the last status ping to the server port.",
+        online(status.latency.map(|rtt| rtt.as_secs_f64())),
+    );
+    let host = status.host.as_ref();
+    metric("host_cpu_percent", "gauge", "CPU use of the whole machine.", host.map(|h| h.cpu_percent as f64));
+    metric("host_memory_used_bytes", "gauge", "Memory in use on the whole machine.", host.map(|h| h.memory_used as f64));
+    metric("host_load1", "gauge", "One-minute load average of the machine.", host.and_then(|h| h.load));
+    metric("schedule_paused", "gauge", "Whether the schedule is paused.", flag(status.schedule_paused));
+    metric(
+        "server_starts_total",
+        "counter",
+        "Server starts since the golem started.",
+        Some(status.counters.starts as f64),
+    );
+    metric(
+        "restarts_total",
+        "counter",
+        "Server starts after a crash or a restart command.",
+        Some(status.counters.restarts as f64),
+    );
+    metric("crashes_total", "counter", "Unexpected server exits.", Some(status.counters.crashes as f64));
+    metric("freezes_total", "counter", "Kills after the server stopped answering.", Some(status.counters.freezes as f64));
+    let backup = status.last_backup.as_ref();
+    metric(
+        "backup_duration_seconds",
+        "gauge",
+        "How long the newest backup took.",
+        backup.map(|r| r.duration_secs),
+    );
+    metric("backup_size_bytes", "gauge", "Size of the newest backup.", backup.map(|r| r.size_bytes as f64));
+    metric(
+        "last_backup_timestamp_seconds",
+        "gauge",
+        "When the newest backup was made, as a Unix time.",
+        backup.map(|r| r.created.timestamp() as f64),
+    );
+
+    // Per backup job (the world or a set), labelled job="<name>"
+    let jobs = &status.backup_jobs;
+    let mut family = |name: &str, kind: &str, help: &str, value: &dyn Fn(&JobStats) -> Option<f64>| {
+        let values: Vec<(&String, f64)> = jobs.iter().filter_map(|(job, stats)| Some((job, value(stats)?))).collect();
+        if values.is_empty() {
+            return;
+        }
+        let _ = writeln!(out, "# HELP rusty_golem_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE rusty_golem_{} {}", name, kind);
+        for (job, value) in values {
+            let _ = writeln!(out, "rusty_golem_{}{{job=\"{}\"}} {}", name, escape_label(job), value);
+        }
+    };
+    family("backup_job_success_total", "counter", "Successful runs of the backup job.", &|s| Some(s.succeeded as f64));
+    family("backup_job_failure_total", "counter", "Failed runs of the backup job.", &|s| Some(s.failed as f64));
+    family("backup_job_last_ok", "gauge", "Whether the job's latest run succeeded.", &|s| Some(if s.last_ok { 1.0 } else { 0.0 }));
+    family(
+        "backup_job_last_success_timestamp_seconds",
+        "gauge",
+        "When the job last succeeded, as a Unix time.",
+        &|s| s.last_success.map(|t| t.timestamp() as f64),
+    );
+    family("backup_job_duration_seconds", "gauge", "How long the job's latest successful run took.", &|s| s.duration_secs);
+    family("backup_job_size_bytes", "gauge", "Bytes written by the job's latest successful run.", &|s| {
+        s.size_bytes.map(|b| b as f64)
+    });
+    family(
+        "backup_job_throughput_bytes_per_second",
+        "gauge",
+        "Write speed of the job's latest successful run.",
+        &JobStats::throughput,
+    );
+    out
+}
+
+/// Backslashes, quotes and line breaks escaped, as label values need.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,170 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::audit;
+use crate::control::Command;
+
+/// Actions that can wait for their moment.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Stop,
+    Restart,
+    Backup,
+}
+
+impl Action {
+    pub fn parse(text: &str) -> Option<Action> {
+        match text.trim().to_lowercase().as_str() {
+            "stop" => Some(Action::Stop),
+            "restart" => Some(Action::Restart),
+            "backup" => Some(Action::Backup),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Stop => "stop",
+            Action::Restart => "restart",
+            Action::Backup => "backup",
+        }
+    }
+
+    /// Stops and restarts wait for an empty server unless told otherwise.
+    pub fn default_when(self) -> When {
+        match self {
+            Action::Stop | Action::Restart => When::Empty,
+            Action::Backup => When::Idle,
+        }
+    }
+
+    fn command(self) -> Command {
+        match self {
+            Action::Stop => Command::Stop,
+            Action::Restart => Command::Restart,
+            Action::Backup => Command::Backup(None),
+        }
+    }
+}
+
+/// When a queued action goes ahead.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    /// As soon as the main loop is free, e.g. once a running backup is done.
+    Idle,
+    /// Once nobody is on the server (or it is down).
+    Empty,
+}
+
+impl When {
+    pub fn parse(text: &str) -> Option<When> {
+        match text.trim().to_lowercase().as_str() {
+            "idle" => Some(When::Idle),
+            "empty" => Some(When::Empty),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            When::Idle => "idle",
+            When::Empty => "empty",
+        }
+    }
+}
+
+struct Queued {
+    id: u64,
+    action: Action,
+    when: When,
+    requested_by: String,
+    requested_at: DateTime<Local>,
+}
+
+/// Actions waiting for their condition, visible and cancellable until the
+/// main loop takes them. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct ActionQueue {
+    inner: Arc<Mutex<(u64, Vec<Queued>)>>,
+}
+
+impl ActionQueue {
+    /// Queues `action` for `when` and returns its id.
+    pub fn add(&self, action: Action, when: When, requested_by: &str) -> u64 {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.0 += 1;
+        let id = inner.0;
+        inner.1.push(Queued { id, action, when, requested_by: requested_by.to_string(), requested_at: Local::now() });
+        info!("Queue: #{} {} when {}, for {}", id, action.as_str(), when.as_str(), requested_by);
+        id
+    }
+
+    /// False if there is no such action, or it has already gone ahead.
+    pub fn cancel(&self, id: u64) -> bool {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let before = inner.1.len();
+        inner.1.retain(|queued| queued.id != id);
+        inner.1.len() < before
+    }
+
+    pub fn json(&self) -> Value {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let actions: Vec<Value> = inner
+            .1
+            .iter()
+            .map(|queued| {
+                json!({
+                    "id": queued.id,
+                    "action": queued.action.as_str(),
+                    "when": queued.when.as_str(),
+                    "requested_by": queued.requested_by,
+                    "requested_at": queued.requested_at.to_rfc3339(),
+                })
+            })
+            .collect();
+        json!({ "actions": actions })
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.1.is_empty() {
+            return vec!["Nothing queued.".to_string()];
+        }
+        inner
+            .1
+            .iter()
+            .map(|queued| {
+                format!(
+                    "#{:<4} {:<8} when {:<6} by {} at {}",
+                    queued.id,
+                    queued.action.as_str(),
+                    queued.when.as_str(),
+                    queued.requested_by,
+                    queued.requested_at.format("%H:%M")
+                )
+            })
+            .collect()
+    }
+
+    /// Takes the actions whose moment has come, oldest first, as commands for
+    /// the main loop.
+    pub fn take_due(&self, online: bool, players: usize) -> Vec<Command> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (due, waiting): (Vec<Queued>, Vec<Queued>) = inner.1.drain(..).partition(|queued| match queued.when {
+            When::Idle => true,
+            When::Empty => !online || players == 0,
+        });
+        inner.1 = waiting;
+        due.into_iter()
+            .map(|queued| {
+                info!("Queue: #{} {} is going ahead", queued.id, queued.action.as_str());
+                let action = format!("#{} {} when {}", queued.id, queued.action.as_str(), queued.when.as_str());
+                audit::record("queue", &queued.requested_by, &action, "ran");
+                queued.action.command()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Requests counted per token name in fixed one-minute windows. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    windows: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
+}
+
+impl RateLimiter {
+    /// Counts one request by `name`. Once there have been more than
+    /// `per_minute` in the current window, Err with the seconds until it ends.
+    pub fn check(&self, name: &str, per_minute: u32) -> Result<(), u64> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let (started, count) = windows.entry(name.to_string()).or_insert((now, 0));
+        if now.duration_since(*started) >= WINDOW {
+            (*started, *count) = (now, 0);
+        }
+        if *count >= per_minute {
+            return Err((WINDOW - now.duration_since(*started)).as_secs() + 1);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
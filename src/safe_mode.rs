@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use crate::config::SafeModeConfig;
+use crate::server_props;
+
+/// The server's own server.properties while the safe one is in place; its
+/// presence is what says safe mode is on, across golem restarts too.
+const NORMAL_PROPERTIES: &str = "server.properties.normal";
+/// Appended to the datapacks moved aside.
+const DISABLED_SUFFIX: &str = ".safe-mode-disabled";
+
+pub fn active(server_dir: &Path) -> bool {
+    server_dir.join(NORMAL_PROPERTIES).exists()
+}
+
+/// Puts the safe profile in place: server.properties with the overrides and
+/// the listed datapacks moved aside.
+pub fn enter(server_dir: &Path, config: &SafeModeConfig) -> io::Result<()> {
+    let properties = server_dir.join("server.properties");
+    let normal = server_dir.join(NORMAL_PROPERTIES);
+    if !normal.exists() {
+        fs::copy(&properties, &normal)?;
+    }
+    let overrides: BTreeMap<String, String> = config
+        .properties
+        .iter()
+        .map(|(key, value)| match value {
+            toml::Value::String(text) => (key.clone(), text.clone()),
+            other => (key.clone(), other.to_string()),
+        })
+        .collect();
+    fs::write(&properties, server_props::with_overrides(&fs::read_to_string(&normal)?, &overrides))?;
+    let datapacks = datapacks_dir(server_dir);
+    for name in &config.disable_datapacks {
+        let pack = datapacks.join(name);
+        if pack.exists() {
+            fs::rename(&pack, datapacks.join(format!("{}{}", name, DISABLED_SUFFIX)))?;
+            info!("Safe mode: disabled the datapack {}.", name);
+        }
+    }
+    Ok(())
+}
+
+/// Puts the server's own server.properties and every datapack back.
+pub fn leave(server_dir: &Path) -> io::Result<()> {
+    let normal = server_dir.join(NORMAL_PROPERTIES);
+    if normal.exists() {
+        fs::rename(&normal, server_dir.join("server.properties"))?;
+    }
+    let datapacks = datapacks_dir(server_dir);
+    let Ok(entries) = fs::read_dir(&datapacks) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some(original) = name.strip_suffix(DISABLED_SUFFIX) {
+            fs::rename(entry.path(), datapacks.join(original))?;
+        }
+    }
+    Ok(())
+}
+
+fn datapacks_dir(server_dir: &Path) -> PathBuf {
+    server_dir.join(server_props::level_name(server_dir)).join("datapacks")
+}
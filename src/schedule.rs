@@ -0,0 +1,318 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use tracing::warn;
+
+use crate::instance;
+
+/// Per-weekday hours set at runtime, which replace the config's
+/// start_time/end_time on those days.
+const OVERRIDES_FILE: &str = "schedule.json";
+
+/// One day's running window. An end at or before the start runs past
+/// midnight; "24:00" is accepted as an end.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hours {
+    Closed,
+    Open(NaiveTime, NaiveTime),
+}
+
+impl Hours {
+    /// "18:00-24:00" or "closed".
+    pub fn parse(text: &str) -> Result<Hours, String> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("closed") {
+            return Ok(Hours::Closed);
+        }
+        let time = |t: &str| {
+            let t = t.trim();
+            if t == "24:00" {
+                return Ok(NaiveTime::MIN);
+            }
+            NaiveTime::parse_from_str(t, "%H:%M").map_err(|_| format!("{} is not a time like 18:00", t))
+        };
+        let (start, end) = text.split_once('-').ok_or_else(|| format!("expected hours like 18:00-24:00 or closed, not {}", text))?;
+        let (start, end) = (time(start)?, time(end)?);
+        if start == end {
+            return Err("the start and end are the same".to_string());
+        }
+        Ok(Hours::Open(start, end))
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        match *self {
+            Hours::Open(start, end) if start < end => time >= start && time < end,
+            Hours::Open(start, _) => time >= start,
+            Hours::Closed => false,
+        }
+    }
+
+    /// The part after midnight of a window that runs past it.
+    fn spills_into_next_day(&self, time: NaiveTime) -> bool {
+        matches!(*self, Hours::Open(start, end) if end < start && time < end)
+    }
+}
+
+impl fmt::Display for Hours {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Hours::Closed => write!(f, "closed"),
+            Hours::Open(start, end) if *end == NaiveTime::MIN => write!(f, "{}-24:00", start.format("%H:%M")),
+            Hours::Open(start, end) => write!(f, "{}-{}", start.format("%H:%M"), end.format("%H:%M")),
+        }
+    }
+}
+
+/// A weekday by its English name or its first three letters.
+pub fn parse_day(text: &str) -> Option<Weekday> {
+    let text = text.trim().to_lowercase();
+    WEEK.into_iter().find(|day| {
+        let name = day_name(*day);
+        text == name || (text.len() >= 3 && name.starts_with(&text))
+    })
+}
+
+/// A day and its new hours as typed, e.g. "friday" and "18:00-24:00";
+/// "default" puts the day back on the daily window.
+pub fn parse_change(day: &str, hours: &str) -> Result<(Weekday, Option<Hours>), String> {
+    let weekday = parse_day(day).ok_or_else(|| format!("{} is not a day of the week", day))?;
+    if hours.trim().eq_ignore_ascii_case("default") {
+        return Ok((weekday, None));
+    }
+    Ok((weekday, Some(Hours::parse(hours)?)))
+}
+
+/// One line per day of `PlayWindow::week`, marking days with hours of their own.
+pub fn week_lines(week: &[(Weekday, Hours, bool)]) -> Vec<String> {
+    week.iter()
+        .map(|(day, hours, own)| format!("{:<10} {}{}", day_name(*day), hours, if *own { "" } else { " (daily)" }))
+        .collect()
+}
+
+pub fn day_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+pub const WEEK: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// The daily running window plus any manual overrides of it: an ad-hoc
+/// session or extension keeps the server up past the window, a manual stop
+/// keeps it down until the window next opens. Weekdays can have hours of
+/// their own, set at runtime and kept in schedule.json.
+pub struct PlayWindow {
+    /// Every day without hours of its own.
+    daily: Hours,
+    days: BTreeMap<u32, Hours>,
+    /// Stay open until then, even outside the window.
+    open_until: Option<DateTime<Local>>,
+    /// Stopped on request: stay closed until the window next opens.
+    held_closed: bool,
+    was_scheduled: bool,
+    /// While the schedule is paused, whether the server should be up.
+    paused: Option<bool>,
+}
+
+impl PlayWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        let mut window = PlayWindow {
+            daily: Hours::Closed,
+            days: load_overrides(),
+            open_until: None,
+            held_closed: false,
+            was_scheduled: false,
+            paused: None,
+        };
+        window.set_daily(Local::now(), start, end);
+        window
+    }
+
+    pub fn hours(&self, day: Weekday) -> Hours {
+        self.days.get(&day.num_days_from_monday()).copied().unwrap_or(self.daily)
+    }
+
+    /// Gives `day` hours of its own, or with None puts it back on the daily
+    /// window, and saves the change.
+    pub fn set_hours(&mut self, now: DateTime<Local>, day: Weekday, hours: Option<Hours>) -> io::Result<()> {
+        match hours {
+            Some(hours) => self.days.insert(day.num_days_from_monday(), hours),
+            None => self.days.remove(&day.num_days_from_monday()),
+        };
+        // Not the window opening, so a manual stop still holds
+        self.was_scheduled = self.scheduled(now.naive_local());
+        let overrides: BTreeMap<&str, String> =
+            self.days.iter().map(|(day, hours)| (day_name(WEEK[*day as usize]), hours.to_string())).collect();
+        let json = serde_json::to_string_pretty(&overrides).map_err(io::Error::other)?;
+        fs::write(instance::path(OVERRIDES_FILE), json)
+    }
+
+    /// New hours for every day without its own, i.e. an edited
+    /// start_time/end_time.
+    pub fn set_daily(&mut self, now: DateTime<Local>, start: NaiveTime, end: NaiveTime) {
+        self.daily = if start == end { Hours::Closed } else { Hours::Open(start, end) };
+        self.was_scheduled = self.scheduled(now.naive_local());
+    }
+
+    /// Monday to Sunday with their hours, and whether they are the day's own.
+    pub fn week(&self) -> Vec<(Weekday, Hours, bool)> {
+        WEEK.into_iter()
+            .map(|day| (day, self.hours(day), self.days.contains_key(&day.num_days_from_monday())))
+            .collect()
+    }
+
+    fn scheduled(&self, at: NaiveDateTime) -> bool {
+        self.hours(at.weekday()).contains(at.time()) || self.hours(at.weekday().pred()).spills_into_next_day(at.time())
+    }
+
+    /// Call once per loop iteration: expires overrides that ran out, and
+    /// lifts a manual stop when the window opens again.
+    pub fn tick(&mut self, now: DateTime<Local>) {
+        let scheduled = self.scheduled(now.naive_local());
+        if scheduled && !self.was_scheduled {
+            self.held_closed = false;
+        }
+        self.was_scheduled = scheduled;
+        if self.open_until.is_some_and(|t| t <= now) {
+            self.open_until = None;
+        }
+    }
+
+    pub fn is_open(&self, now: DateTime<Local>) -> bool {
+        if let Some(open) = self.paused {
+            return open;
+        }
+        !self.held_closed && (self.scheduled(now.naive_local()) || self.open_until.is_some_and(|t| t > now))
+    }
+
+    /// When the server is due to stop, if it is open now and the schedule runs.
+    pub fn closes_at(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        if self.paused.is_some() || !self.is_open(now) {
+            return None;
+        }
+        scheduled_end(self, now).max(self.open_until)
+    }
+
+    /// When the window next opens by itself, within the coming week.
+    pub fn opens_at(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        (0..=7)
+            .map(|offset| now.date_naive() + Duration::days(offset))
+            .find_map(|date| match self.hours(date.weekday()) {
+                Hours::Open(start, _) => Some(local(date, start)).filter(|t| *t > now),
+                Hours::Closed => None,
+            })
+    }
+
+    /// Opens now: cancels a manual stop and, outside the window, starts a
+    /// session of `minutes`.
+    pub fn open(&mut self, now: DateTime<Local>, minutes: u64) {
+        if self.paused.is_some() {
+            self.paused = Some(true);
+            return;
+        }
+        self.held_closed = false;
+        if !self.scheduled(now.naive_local()) {
+            let until = now + Duration::minutes(minutes as i64);
+            self.open_until = self.open_until.max(Some(until));
+        }
+    }
+
+    pub fn close(&mut self) {
+        if self.paused.is_some() {
+            self.paused = Some(false);
+            return;
+        }
+        self.held_closed = true;
+        self.open_until = None;
+    }
+
+    /// Freezes the server in its current state: no scheduled starts or stops
+    /// until `resume`, though `open` and `close` still work.
+    pub fn pause(&mut self, now: DateTime<Local>) {
+        if self.paused.is_none() {
+            self.paused = Some(self.is_open(now));
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = None;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Pushes the closing time back; returns the new one, or None when closed.
+    pub fn extend(&mut self, now: DateTime<Local>, minutes: u64) -> Option<DateTime<Local>> {
+        let until = self.closes_at(now)? + Duration::minutes(minutes as i64);
+        self.open_until = Some(until);
+        Some(until)
+    }
+}
+
+/// When the scheduled window `now` falls in ends, if it falls in one.
+fn scheduled_end(window: &PlayWindow, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let (today, time) = (now.date_naive(), now.time());
+    let yesterday = today - Duration::days(1);
+    match window.hours(today.weekday()) {
+        Hours::Open(start, end) if start < end && time >= start && time < end => Some(local(today, end)),
+        Hours::Open(start, end) if end < start && time >= start => Some(local(today + Duration::days(1), end)),
+        _ if window.hours(yesterday.weekday()).spills_into_next_day(time) => match window.hours(yesterday.weekday()) {
+            Hours::Open(_, end) => Some(local(today, end)),
+            Hours::Closed => None,
+        },
+        _ => None,
+    }
+}
+
+fn local(date: NaiveDate, time: NaiveTime) -> DateTime<Local> {
+    let naive = date.and_time(time);
+    // A time skipped by a DST change falls back to an hour later
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .or_else(|| (naive + Duration::hours(1)).and_local_timezone(Local).earliest())
+        .unwrap_or_else(Local::now)
+}
+
+/// Weekday hours from schedule.json; a missing file means none.
+fn load_overrides() -> BTreeMap<u32, Hours> {
+    let Ok(content) = fs::read_to_string(instance::path(OVERRIDES_FILE)) else {
+        return BTreeMap::new();
+    };
+    let overrides: BTreeMap<String, String> = match serde_json::from_str(&content) {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            warn!("Ignoring {}: {}", OVERRIDES_FILE, e);
+            return BTreeMap::new();
+        }
+    };
+    let mut days = BTreeMap::new();
+    for (day, hours) in overrides {
+        match (parse_day(&day), Hours::parse(&hours)) {
+            (Some(weekday), Ok(hours)) => {
+                days.insert(weekday.num_days_from_monday(), hours);
+            }
+            (None, _) => warn!("{}: no such day {}", OVERRIDES_FILE, day),
+            (_, Err(e)) => warn!("{}: {}: {}", OVERRIDES_FILE, day, e),
+        }
+    }
+    days
+}
@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use sysinfo::{Pid, ProcessesToUpdate, System};
+
+use crate::feed::LogFeed;
+use crate::server_log::{self, LogEvent};
+
+/// How many console lines are kept for crash excerpts.
+const RECENT_LINES: usize = 500;
+
+/// How long the console must stay quiet for a reply to be over.
+const QUERY_QUIET: Duration = Duration::from_millis(300);
+
+/// The longest reply collected: a server that logs all the time would
+/// otherwise never fall quiet.
+const QUERY_MAX_LINES: usize = 200;
+
+/// A running Minecraft server process whose console output is mirrored to
+/// our stdout and also made available to the main loop line by line.
+pub struct Server {
+    child: Child,
+    lines: Receiver<String>,
+    recent: VecDeque<String>,
+    /// Lines consumed while waiting for a reply, still owed to `drain_lines`.
+    unread: Vec<String>,
+    pub started_at: Instant,
+    pub started_at_wall: SystemTime,
+    /// From launch to "Done", timed as the line is read rather than when the
+    /// main loop gets to it.
+    boot_time: Arc<OnceLock<Duration>>,
+}
+
+impl Server {
+    /// Console lines also go to `feed` as they arrive.
+    pub fn start(path: &str, feed: LogFeed) -> io::Result<Server> {
+        let mut child = if cfg!(target_os = "windows") {
+            Command::new("cmd")
+                .args(["/C", path])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::inherit())
+                .spawn()?
+        } else {
+            // Assume Linux/Unix
+            // Execute directly if executable, or use sh if needed.
+            // For .sh files, often need "sh script.sh" or "./script.sh".
+            // Using "sh -c" helps if path includes arguments or environment setup.
+            // But simply Command::new(path) works if it has shebang and execute perms.
+            // To be safe and mimicking "cmd /C", let's use "sh -c".
+
+            Command::new("sh")
+                .arg("-c")
+                .arg(path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::inherit())
+                .spawn()?
+        };
+
+        let started_at = Instant::now();
+        let boot_time = Arc::new(OnceLock::new());
+        let (sender, lines) = mpsc::channel();
+        if let Some(stdout) = child.stdout.take() {
+            let boot_time = boot_time.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stdout);
+                let mut buf = Vec::new();
+                loop {
+                    buf.clear();
+                    match reader.read_until(b'\n', &mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => {
+                            // Server consoles are not always valid UTF-8 (e.g. Windows code pages)
+                            let line = String::from_utf8_lossy(&buf).trim_end().to_string();
+                            println!("{}", line);
+                            feed.line(&line);
+                            if boot_time.get().is_none() && server_log::parse_line(&line) == Some(LogEvent::Done) {
+                                let _ = boot_time.set(started_at.elapsed());
+                            }
+                            if sender.send(line).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(Server {
+            child,
+            lines,
+            recent: VecDeque::with_capacity(RECENT_LINES),
+            unread: Vec::new(),
+            started_at,
+            started_at_wall: SystemTime::now(),
+            boot_time,
+        })
+    }
+
+    /// How long the server took to log "Done", once it has.
+    pub fn boot_time(&self) -> Option<Duration> {
+        self.boot_time.get().copied()
+    }
+
+    pub fn send_command(&mut self, command: &str) {
+        if let Some(stdin) = self.child.stdin.as_mut() {
+            let _ = writeln!(stdin, "{}", command);
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Returns `Some(exit_code)` once the process has ended. The code itself is
+    /// `None` when it is unknown (killed by a signal, or the process could not be queried).
+    pub fn poll_exit(&mut self) -> Option<Option<i32>> {
+        match self.child.try_wait() {
+            Ok(status) => status.map(|s| s.code()),
+            // Treat an unqueryable process as dead, like the original loop did
+            Err(_) => Some(None),
+        }
+    }
+
+    /// Console lines printed since the last call.
+    pub fn drain_lines(&mut self) -> Vec<String> {
+        let lines: Vec<String> = self.lines.try_iter().collect();
+        for line in &lines {
+            self.remember(line);
+        }
+        let mut all = std::mem::take(&mut self.unread);
+        all.extend(lines);
+        all
+    }
+
+    /// Blocks until a console line matches `predicate`, or the timeout elapses.
+    pub fn wait_for_line(&mut self, predicate: impl Fn(&str) -> bool, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(remaining) {
+                Ok(line) => {
+                    self.remember(&line);
+                    let matched = predicate(&line);
+                    self.unread.push(line);
+                    if matched {
+                        return true;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                    return false
+                }
+            }
+        }
+    }
+
+    /// Sends a command and collects what the server prints in answer: the
+    /// lines from the first one (within `timeout`) until it falls quiet, or
+    /// at the latest `QUERY_QUIET` past the timeout.
+    pub fn query(&mut self, command: &str, timeout: Duration) -> Vec<String> {
+        self.send_command(command);
+        let deadline = Instant::now() + timeout + QUERY_QUIET;
+        let mut answer = Vec::new();
+        let mut wait = timeout;
+        while answer.len() < QUERY_MAX_LINES {
+            let Ok(line) = self.lines.recv_timeout(wait.min(deadline.saturating_duration_since(Instant::now()))) else {
+                break;
+            };
+            self.remember(&line);
+            answer.push(line.clone());
+            self.unread.push(line);
+            wait = QUERY_QUIET;
+        }
+        answer
+    }
+
+    /// Flushes all chunks to disk and waits for the server to confirm.
+    pub fn save_all(&mut self, timeout: Duration) -> bool {
+        self.send_command("save-all flush");
+        self.wait_for_line(|line| line.contains("Saved the game"), timeout)
+    }
+
+    fn remember(&mut self, line: &str) {
+        if self.recent.len() == RECENT_LINES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(line.to_string());
+    }
+
+    /// The last `n` console lines, oldest first.
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let skip = self.recent.len().saturating_sub(n);
+        self.recent.iter().skip(skip).cloned().collect()
+    }
+
+    /// Kills the server and whatever its start script started, for when it no
+    /// longer answers; the exit code.
+    pub fn kill(&mut self) -> Option<i32> {
+        // The script's descendants (java) first, or they would outlive it
+        kill_descendants(self.pid());
+        let _ = self.child.kill();
+        self.child.wait().ok().and_then(|status| status.code())
+    }
+
+    /// Asks the server to stop and waits for it; the exit code.
+    pub fn stop(&mut self) -> Option<i32> {
+        self.send_command("stop");
+        // Wait a bit for it to stop gracefully
+        // In a real production app we might want to wait on child.wait() with a timeout,
+        // but std::process doesn't have a simple timeout wait.
+        // We will just let the main loop handle the cleanup or wait endlessly if that's safer.
+        // For now, let's just send stop and let the watchdog/loop handle the rest.
+        self.child.wait().ok().and_then(|status| status.code())
+    }
+}
+
+/// Kills every process started, directly or not, by `root`.
+pub fn kill_descendants(root: u32) {
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let processes = system.processes();
+    let root = Pid::from_u32(root);
+    for (pid, process) in processes {
+        let mut parent = process.parent();
+        while let Some(p) = parent.filter(|p| *p != root) {
+            parent = processes.get(&p).and_then(|process| process.parent());
+        }
+        if parent.is_some() && *pid != root {
+            process.kill();
+        }
+    }
+}
@@ -0,0 +1,64 @@
+/// Interesting things the server reports on its console.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogEvent {
+    PlayerJoined(String),
+    PlayerLeft(String),
+    /// `<Steve> hello`: a player's chat message.
+    Chat { player: String, message: String },
+    /// `Done (12.345s)! For help, type "help"`: the server finished starting.
+    Done,
+}
+
+/// Extracts the message part of a console line,
+/// e.g. `[12:00:00] [Server thread/INFO]: Steve joined the game` -> `Steve joined the game`.
+pub fn message(line: &str) -> &str {
+    match line.find("]: ") {
+        Some(i) => &line[i + 3..],
+        None => line,
+    }
+}
+
+/// Whether the line was logged at WARN level or above, e.g. `[12:00:00]
+/// [Server thread/WARN]: ...` or Paper's `[12:00:00 ERROR]: ...`.
+pub fn is_warning(line: &str) -> bool {
+    let Some(end) = line.find("]: ") else {
+        return false;
+    };
+    let head = &line[..end];
+    head.ends_with("WARN") || head.ends_with("ERROR") || head.ends_with("FATAL")
+}
+
+pub fn parse_line(line: &str) -> Option<LogEvent> {
+    let msg = message(line);
+    // 1.19+ marks chat without a signed profile key
+    let chat = msg.strip_prefix("[Not Secure] ").unwrap_or(msg);
+    if let Some((name, text)) = chat.strip_prefix('<').and_then(|rest| rest.split_once("> ")) {
+        if is_player_name(name) {
+            return Some(LogEvent::Chat {
+                player: name.to_string(),
+                message: text.to_string(),
+            });
+        }
+    }
+    if let Some(name) = msg.strip_suffix(" joined the game") {
+        if is_player_name(name) {
+            return Some(LogEvent::PlayerJoined(name.to_string()));
+        }
+    }
+    if let Some(name) = msg.strip_suffix(" left the game") {
+        if is_player_name(name) {
+            return Some(LogEvent::PlayerLeft(name.to_string()));
+        }
+    }
+    if msg.starts_with("Done (") && msg.contains(")! For help") {
+        return Some(LogEvent::Done);
+    }
+    None
+}
+
+// Rules out chat lines such as "<Steve> I joined the game"
+fn is_player_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 16
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Reads a key from `server.properties`, if the file and key exist.
+pub fn get(server_dir: &Path, key: &str) -> Option<String> {
+    let content = fs::read_to_string(server_dir.join("server.properties")).ok()?;
+    content
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .find_map(|l| {
+            let (k, v) = l.split_once('=')?;
+            (k.trim() == key).then(|| v.trim().to_string())
+        })
+}
+
+/// The overworld plus the separate nether/end folders Bukkit-style servers create.
+pub fn world_dirs(server_dir: &Path) -> Vec<PathBuf> {
+    let name = level_name(server_dir);
+    [name.clone(), format!("{}_nether", name), format!("{}_the_end", name)]
+        .iter()
+        .map(|n| server_dir.join(n))
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+/// `content` with `overrides` set: existing keys changed in place, the rest
+/// added at the end.
+pub fn with_overrides(content: &str, overrides: &BTreeMap<String, String>) -> String {
+    let mut missing: Vec<&String> = overrides.keys().collect();
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let key = line.split_once('=').map(|(k, _)| k.trim()).filter(|_| !line.trim_start().starts_with('#'));
+            match key.and_then(|k| overrides.get_key_value(k)) {
+                Some((key, value)) => {
+                    missing.retain(|k| *k != key);
+                    format!("{}={}", key, value)
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    lines.extend(missing.into_iter().map(|key| format!("{}={}", key, overrides[key])));
+    lines.join("\n") + "\n"
+}
+
+pub fn level_name(server_dir: &Path) -> String {
+    get(server_dir, "level-name")
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "world".to_string())
+}
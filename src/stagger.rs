@@ -0,0 +1,84 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+use crate::config::StaggerConfig;
+
+/// Each supervisor asks on every pass of its loop, at least every ten
+/// seconds while it wants to start; one that has not asked for this long
+/// stopped waiting (its window closed, it was stopped) and starts over.
+const GAVE_UP_WAITING: Duration = Duration::from_secs(60);
+
+/// The server that started last, and whether it is up yet.
+struct Last {
+    name: String,
+    since: Instant,
+    done: bool,
+}
+
+/// A server held back: since when, and when it last asked.
+struct Waiting {
+    name: String,
+    since: Instant,
+    asked: Instant,
+}
+
+#[derive(Default)]
+struct Turns {
+    last: Option<Last>,
+    waiting: Vec<Waiting>,
+}
+
+/// Has the servers of `[[servers]]` start one after the other, so they do
+/// not all load their worlds from the same disk at once. Cheap to clone.
+#[derive(Clone)]
+pub struct StartGate {
+    config: StaggerConfig,
+    turns: Arc<Mutex<Turns>>,
+}
+
+impl StartGate {
+    pub fn new(config: &StaggerConfig) -> StartGate {
+        StartGate { config: config.clone(), turns: Arc::default() }
+    }
+
+    /// Whether `name` may start now. If so, the next server waits for it.
+    /// No server waits longer than `seconds` in all, however often the one
+    /// before restarts: one that keeps crashing never holds the others down.
+    pub fn try_start(&self, name: &str) -> bool {
+        let mut turns = self.turns.lock().unwrap_or_else(|e| e.into_inner());
+        let Turns { last, waiting } = &mut *turns;
+        let timeout = Duration::from_secs(self.config.seconds);
+        waiting.retain(|waiting| waiting.asked.elapsed() < GAVE_UP_WAITING);
+        if let Some(last) = last.as_ref().filter(|last| last.name != name) {
+            let held = waiting.iter_mut().find(|waiting| waiting.name == name);
+            let waited = held.as_ref().is_some_and(|held| held.since.elapsed() >= timeout) || last.since.elapsed() >= timeout;
+            if !(waited || self.config.wait_for_done && last.done) {
+                match held {
+                    Some(held) => held.asked = Instant::now(),
+                    None => {
+                        info!("Stagger: {} waits for {} to finish starting.", name, last.name);
+                        let now = Instant::now();
+                        waiting.push(Waiting { name: name.to_string(), since: now, asked: now });
+                    }
+                }
+                return false;
+            }
+        }
+        waiting.retain(|waiting| waiting.name != name);
+        *last = Some(Last { name: name.to_string(), since: Instant::now(), done: false });
+        true
+    }
+
+    /// `name` logged "Done".
+    pub fn ready(&self, name: &str) {
+        let mut turns = self.turns.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(last) = turns.last.as_mut().filter(|last| last.name == name && !last.done) {
+            last.done = true;
+            if self.config.wait_for_done {
+                info!("Stagger: {} is up; the next server may start.", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use chrono::{Days, Local};
+use tracing::{info, warn};
+
+use crate::config::StartupTrendConfig;
+use crate::digest::format_duration;
+use crate::history;
+use crate::messages::Messages;
+use crate::notify::{EventKind, Notifiers};
+
+/// Boots older than this do not count towards the baseline.
+const BASELINE_DAYS: u64 = 90;
+/// A few seconds more is noise, however large a share of a quick boot it is.
+const MIN_INCREASE: Duration = Duration::from_secs(10);
+
+/// Boot durations from `stats/startup.csv` within the baseline period, oldest
+/// first.
+fn recent_boots() -> Vec<Duration> {
+    let since = Local::now().checked_sub_days(Days::new(BASELINE_DAYS)).unwrap_or_else(Local::now);
+    history::read_since("startup", since)
+        .into_iter()
+        .filter_map(|(_, value)| value.parse::<f64>().ok())
+        .map(Duration::from_secs_f64)
+        .collect()
+}
+
+/// Notes how long the server took from launch to "Done" and warns when that
+/// is well above the median of the boots before it, which is usually the
+/// first sign of a bloated world or plugin database.
+pub fn record(took: Duration, config: &StartupTrendConfig, messages: &Messages, notifiers: &Notifiers) {
+    let mut previous = recent_boots();
+    history::append("startup", Local::now(), &format!("{:.1}", took.as_secs_f64()));
+    info!("The server started in {:.1} s.", took.as_secs_f64());
+
+    previous.drain(..previous.len().saturating_sub(config.baseline_boots));
+    // A couple of boots are no baseline yet
+    if config.max_growth_percent == 0 || previous.len() < 3 {
+        return;
+    }
+    previous.sort();
+    let usual = previous[previous.len() / 2];
+    let limit = usual.mul_f64(1.0 + config.max_growth_percent as f64 / 100.0).max(usual + MIN_INCREASE);
+    if took > limit {
+        let percent = ((took.as_secs_f64() / usual.as_secs_f64().max(0.1) - 1.0) * 100.0).round();
+        warn!("The server took {:.1} s to start, against a usual {:.1} s.", took.as_secs_f64(), usual.as_secs_f64());
+        let message = messages.get(
+            "startup_slow",
+            &[
+                ("took", format_seconds(took)),
+                ("usual", format_seconds(usual)),
+                ("percent", format!("{}", percent)),
+            ],
+        );
+        notifiers.send(EventKind::StartupSlow, &message);
+    }
+}
+
+/// `stats startup`: the last 20 boot durations and their median.
+pub fn lines() -> Vec<String> {
+    let since = Local::now().checked_sub_days(Days::new(BASELINE_DAYS)).unwrap_or_else(Local::now);
+    let boots = history::read_since("startup", since);
+    if boots.is_empty() {
+        return vec![format!("No boots recorded in the last {} days.", BASELINE_DAYS)];
+    }
+    let mut lines = vec![format!("{:<16} {:>9}", "Started", "Took")];
+    for (time, value) in &boots[boots.len().saturating_sub(20)..] {
+        let took = value.parse::<f64>().map_or("-".to_string(), |s| format_seconds(Duration::from_secs_f64(s)));
+        lines.push(format!("{:<16} {:>9}", time.format("%Y-%m-%d %H:%M"), took));
+    }
+    let mut all = recent_boots();
+    all.sort();
+    if let Some(median) = all.get(all.len() / 2) {
+        lines.push(format!("Median over {} boots: {}", all.len(), format_seconds(*median)));
+    }
+    lines
+}
+
+/// "48 s" for a boot under two minutes, "0h 03m" above.
+fn format_seconds(d: Duration) -> String {
+    if d < Duration::from_secs(120) {
+        format!("{} s", d.as_secs())
+    } else {
+        format_duration(d)
+    }
+}
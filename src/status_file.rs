@@ -0,0 +1,67 @@
+use std::fs;
+
+use chrono::Local;
+use serde_json::{json, Map, Value};
+use tracing::warn;
+
+use crate::control::Status;
+use crate::instance::Handle;
+
+/// The status as a JSON file, rewritten on every pass of the main loop, for
+/// widgets and overlays that read a file rather than call an API. With
+/// `[[servers]]` it is the first server's, with every server's under
+/// `servers` by name.
+pub struct StatusFile {
+    path: String,
+    failing: bool,
+}
+
+impl StatusFile {
+    pub fn new(path: &str) -> StatusFile {
+        StatusFile { path: path.to_string(), failing: false }
+    }
+
+    /// Replaces the file in one step, so a reader never sees half of it.
+    pub fn write(&mut self, servers: &[Handle]) {
+        let Some(first) = servers.first() else {
+            return;
+        };
+        let mut json = render(&first.status());
+        if first.name.is_some() {
+            let by_name: Map<String, Value> = servers
+                .iter()
+                .filter_map(|server| Some((server.name.clone()?, render(&server.status()))))
+                .collect();
+            json["servers"] = Value::Object(by_name);
+        }
+        let temporary = format!("{}.tmp", self.path);
+        match fs::write(&temporary, json.to_string()).and_then(|_| fs::rename(&temporary, &self.path)) {
+            Ok(()) => self.failing = false,
+            // Said once, not every ten seconds
+            Err(e) if !self.failing => {
+                self.failing = true;
+                warn!("Status file: could not write {}: {}", self.path, e);
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+fn render(status: &Status) -> Value {
+    let next_event = match (status.closes_at, status.opens_at) {
+        (Some(at), _) => json!({ "event": "stop", "at": at.to_rfc3339() }),
+        (None, Some(at)) => json!({ "event": "start", "at": at.to_rfc3339() }),
+        (None, None) => Value::Null,
+    };
+    json!({
+        "state": if status.online { "running" } else { "stopped" },
+        "pid": status.pid.filter(|_| status.online),
+        "uptime_secs": status.online_since.filter(|_| status.online).map(|t| (Local::now() - t).num_seconds()),
+        "players": status.players,
+        "player_names": status.player_names,
+        "next_event": next_event,
+        "last_backup": status.last_backup.as_ref().map(|r| json!({ "name": r.name, "at": r.created.to_rfc3339() })),
+        "schedule_paused": status.schedule_paused,
+        "updated_at": Local::now().to_rfc3339(),
+    })
+}
@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::Client;
+use tracing::warn;
+
+use crate::config::StatusMessageConfig;
+
+/// A single Discord message that is edited in place with the current server state.
+pub struct StatusMessage {
+    client: Client,
+    webhook_url: String,
+    interval: Duration,
+    message_id: Option<String>,
+    last_update: Option<Instant>,
+}
+
+impl StatusMessage {
+    pub fn new(config: &StatusMessageConfig, default_webhook_url: &str) -> Self {
+        StatusMessage {
+            client: Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to build HTTP client"),
+            webhook_url: config
+                .webhook_url
+                .clone()
+                .unwrap_or_else(|| default_webhook_url.to_string()),
+            interval: Duration::from_secs(config.interval_minutes * 60),
+            message_id: None,
+            last_update: None,
+        }
+    }
+
+    /// Posts or edits the status embeds, a title and fields for each server,
+    /// if the refresh interval has elapsed.
+    pub fn update_if_due(&mut self, servers: &[(String, Vec<(String, String)>)]) {
+        if let Some(last) = self.last_update {
+            if last.elapsed() < self.interval {
+                return;
+            }
+        }
+        self.last_update = Some(Instant::now());
+
+        // Discord takes at most 10 embeds in a message
+        let embeds: Vec<_> = servers
+            .iter()
+            .take(10)
+            .map(|(title, fields)| {
+                let embed_fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, value)| serde_json::json!({ "name": name, "value": value, "inline": true }))
+                    .collect();
+                serde_json::json!({
+                    "title": title,
+                    "fields": embed_fields,
+                    "timestamp": chrono::Local::now().to_rfc3339()
+                })
+            })
+            .collect();
+        let payload = serde_json::json!({ "embeds": embeds });
+
+        if let Some(id) = &self.message_id {
+            let url = format!("{}/messages/{}", self.webhook_url, id);
+            match self.client.patch(&url).json(&payload).send() {
+                Ok(r) if r.status().is_success() => return,
+                Ok(r) if r.status() == reqwest::StatusCode::NOT_FOUND => {
+                    // Someone deleted the message; post a fresh one below
+                    self.message_id = None;
+                }
+                Ok(r) => {
+                    warn!("Failed to edit status message: HTTP {}", r.status());
+                    return;
+                }
+                Err(e) => {
+                    warn!("Failed to edit status message: {}", e);
+                    return;
+                }
+            }
+        }
+
+        // `wait=true` makes Discord return the created message so we can edit it later
+        let url = format!("{}?wait=true", self.webhook_url);
+        match self.client.post(&url).json(&payload).send() {
+            Ok(r) if r.status().is_success() => {
+                let body: serde_json::Value = r.json().unwrap_or_default();
+                self.message_id = body["id"].as_str().map(|s| s.to_string());
+            }
+            Ok(r) => warn!("Failed to post status message: HTTP {}", r.status()),
+            Err(e) => warn!("Failed to post status message: {}", e),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::Local;
+
+use crate::config::StatusPageConfig;
+use crate::control::Status;
+use crate::template;
+
+const PAGE: &str = include_str!("status_page.html");
+
+/// The page for players: whether the server is up, how many are on and when
+/// it next opens. Nothing on it is secret, so it needs no credentials.
+pub fn render(config: &StatusPageConfig, status: &Status) -> String {
+    let (state, state_class) = if status.online { ("Online", "online") } else { ("Offline", "offline") };
+    let players = match (status.online, status.players) {
+        (false, _) => String::new(),
+        (true, 1) => "1 player on".to_string(),
+        (true, n) => format!("{} players on", n),
+    };
+    let next = match (status.online, status.closes_at, status.opens_at) {
+        (true, Some(closes), _) => format!("Open until {}", closes.format("%H:%M")),
+        (true, None, _) => String::new(),
+        (false, _, Some(opens)) => {
+            let minutes = (opens - Local::now()).num_minutes().max(0);
+            format!("Opens in {}h {}m", minutes / 60, minutes % 60)
+        }
+        (false, _, None) => "No opening scheduled".to_string(),
+    };
+    // Only counted down while the server is down
+    let opens_at = status.opens_at.filter(|_| !status.online).map_or(String::new(), |t| t.to_rfc3339());
+    template::render(
+        PAGE,
+        &[
+            ("name", escape(&config.name)),
+            ("address", escape(config.address.as_deref().unwrap_or_default())),
+            ("accent", escape(&config.accent)),
+            ("background", escape(&config.background)),
+            ("state", state.to_string()),
+            ("state_class", state_class.to_string()),
+            ("players", players),
+            ("next", next),
+            ("opens_at", opens_at),
+        ],
+    )
+}
+
+/// For text and attribute values; braces too, so a value cannot put a
+/// placeholder into the page.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '{' => escaped.push_str("&#123;"),
+            '}' => escaped.push_str("&#125;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
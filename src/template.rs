@@ -0,0 +1,43 @@
+use serde_json::Value;
+
+/// Replaces every `{{name}}` placeholder in `template` with its value from `vars`.
+/// Unknown placeholders are left untouched so typos are visible in the output.
+/// Values are not searched in turn, so chat text that reads `{{player}}` stays as it is.
+pub fn render(template: &str, vars: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after
+            .find("}}")
+            .and_then(|end| vars.iter().find(|(name, _)| *name == &after[..end]).map(|(_, value)| (end, value)));
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Renders placeholders inside every string of a JSON document.
+/// Substitution happens on parsed values, so messages never break the JSON escaping.
+pub fn render_json(template: &Value, vars: &[(&str, String)]) -> Value {
+    match template {
+        Value::String(s) => Value::String(render(s, vars)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_json(v, vars)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render_json(v, vars)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,562 @@
+use std::process::Command;
+
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use tracing::{error, info, warn};
+
+use crate::config::{Exhausted, Policy, WatchdogConfig};
+use crate::crash_logs::Unrecoverable;
+use crate::lifecycle;
+use crate::messages::Messages;
+use crate::notify::{EventKind, Notifiers};
+use crate::server_log;
+
+/// How the server went down, for picking the policy for it.
+#[derive(Clone, Copy)]
+pub enum ExitClass {
+    CleanExit,
+    Crash,
+    Freeze,
+    StartFailure,
+    OutOfMemory,
+}
+
+impl ExitClass {
+    /// As in `[watchdog.policies]`.
+    fn as_str(&self) -> &'static str {
+        match self {
+            ExitClass::CleanExit => "clean_exit",
+            ExitClass::Crash => "crash",
+            ExitClass::Freeze => "freeze",
+            ExitClass::StartFailure => "start_failure",
+            ExitClass::OutOfMemory => "out_of_memory",
+        }
+    }
+}
+
+/// When the server that went down will be started again.
+pub enum NextStart {
+    At(DateTime<Local>),
+    /// Not until the watchdog has dealt with the attempts being used up.
+    OutOfAttempts,
+    /// The policy leaves it down.
+    Halted,
+}
+
+/// Decides whether (and when) a server that is down but should run gets
+/// started again: at most `max_attempts` starts per rolling window, each
+/// restart held back as the policy for the way it went down says (by default
+/// a delay that grows with every crash in a row), and the configured way out
+/// once the attempts are used up.
+pub struct Watchdog {
+    config: WatchdogConfig,
+    /// Starts within the window, oldest first.
+    attempts: Vec<DateTime<Local>>,
+    /// Crashes (and failed starts) in a row, until the server stays up.
+    streak: usize,
+    /// The earliest the next restart may happen, after a crash.
+    not_before: Option<DateTime<Local>>,
+    /// Out of attempts: until when, or None to stay down until reset.
+    holding: Option<Option<DateTime<Local>>>,
+    /// Whether giving up already took its one retry after the cooldown.
+    cooled_down: bool,
+    /// Automatic restarts on the day given.
+    restarts: (NaiveDate, u32),
+    /// Past the daily limit: nothing starts until `rearm`.
+    halted: bool,
+    /// The policy asked for the next start to be in safe mode.
+    safe_mode: bool,
+    /// Its name in `[[servers]]`, for the fallback script.
+    server: Option<String>,
+}
+
+impl Watchdog {
+    pub fn new(config: &WatchdogConfig) -> Watchdog {
+        Watchdog {
+            config: config.clone(),
+            attempts: Vec::new(),
+            streak: 0,
+            not_before: None,
+            holding: None,
+            cooled_down: false,
+            restarts: (Local::now().date_naive(), 0),
+            halted: false,
+            safe_mode: false,
+            server: None,
+        }
+    }
+
+    /// The watchdog of one of several servers: its fallback script finds the
+    /// name in `GOLEM_SERVER`.
+    pub fn with_server_name(mut self, name: &str) -> Watchdog {
+        self.server = Some(name.to_string());
+        self
+    }
+
+    /// A new config picks up where the old one was.
+    pub fn set_config(&mut self, config: &WatchdogConfig) {
+        self.config = config.clone();
+    }
+
+    /// Starts over, e.g. once the window closed; the day's restarts still count.
+    pub fn reset(&mut self) {
+        self.clear();
+        self.cooled_down = false;
+    }
+
+    /// A start by hand (or `watchdog reset`): everything, the daily limit
+    /// included, starts over.
+    pub fn rearm(&mut self) {
+        self.reset();
+        self.restarts.1 = 0;
+        self.halted = false;
+    }
+
+    /// Keeps the server down until it is started by hand or the window closes.
+    pub fn hold(&mut self) {
+        self.holding = Some(None);
+    }
+
+    /// The console showed a problem no restart can fix (`line` says so):
+    /// stays down until it is started by hand, without going through the
+    /// attempts first.
+    pub fn unrecoverable(&mut self, cause: Unrecoverable, line: &str, messages: &Messages, notifiers: &Notifiers) {
+        let cause = messages.get(cause.key(), &[]);
+        error!("Watchdog: not restarting the server: {}", cause);
+        lifecycle::record("watchdog_gave_up", &cause, None);
+        let fields = vec![(messages.get("watchdog_unrecoverable_line", &[]), server_log::message(line).to_string())];
+        notifiers.send_with_fields(EventKind::WatchdogGaveUp, &messages.get("watchdog_unrecoverable", &[("cause", cause)]), fields);
+        self.hold();
+    }
+
+    /// A fresh round of attempts.
+    fn clear(&mut self) {
+        self.attempts.clear();
+        self.streak = 0;
+        self.not_before = None;
+        self.holding = None;
+        self.safe_mode = false;
+    }
+
+    /// Crashes in a row so far.
+    pub fn streak(&self) -> usize {
+        self.streak
+    }
+
+    /// Whether the policy asked for the next start to be in safe mode.
+    pub fn safe_mode(&self) -> bool {
+        self.safe_mode
+    }
+
+    /// Called on every pass while the server runs: once it has stayed up for
+    /// `stable_minutes` the watchdog is re-armed.
+    pub fn up(&mut self, uptime: std::time::Duration) {
+        if uptime.as_secs() < self.config.stable_minutes * 60 || (self.streak == 0 && !self.cooled_down) {
+            return;
+        }
+        info!("Watchdog: the server has stayed up for {} minutes; re-armed.", self.config.stable_minutes);
+        lifecycle::record("watchdog_rearmed", &format!("up for {} minutes", self.config.stable_minutes), None);
+        self.reset();
+    }
+
+    /// The server went down (or did not come up) as `class` and is to be
+    /// started again: applies the policy for it.
+    pub fn down(&mut self, class: ExitClass, now: DateTime<Local>) -> NextStart {
+        let policies = &self.config.policies;
+        let policy = match class {
+            ExitClass::CleanExit => policies.clean_exit.unwrap_or(Policy::Restart),
+            ExitClass::Crash => policies.crash,
+            ExitClass::Freeze => policies.freeze,
+            ExitClass::StartFailure => policies.start_failure,
+            ExitClass::OutOfMemory => policies.out_of_memory,
+        };
+        if !matches!(class, ExitClass::CleanExit) {
+            self.streak += 1;
+        }
+        self.safe_mode = policy == Policy::SafeMode;
+        let delay = match policy {
+            Policy::Restart => 0,
+            Policy::Backoff | Policy::SafeMode => self.delay(),
+            Policy::Halt => {
+                info!("Watchdog: leaving the server down, as the {} policy says.", class.as_str());
+                lifecycle::record("watchdog_halted", &format!("{} policy", class.as_str()), None);
+                self.not_before = None;
+                self.hold();
+                return NextStart::Halted;
+            }
+        };
+        let at = now + Duration::seconds((delay + self.config.restart_delay_seconds) as i64);
+        self.not_before = Some(at);
+        self.prune(now);
+        if self.attempts.len() >= self.config.max_attempts as usize || self.over_daily_limit(now) {
+            NextStart::OutOfAttempts
+        } else {
+            NextStart::At(at)
+        }
+    }
+
+    /// A start was attempted; `ok` is false when it failed outright.
+    pub fn attempted(&mut self, now: DateTime<Local>, ok: bool) {
+        // Only a crash or an exit leaves a restart pending
+        if self.not_before.is_some() {
+            if self.restarts.0 != now.date_naive() {
+                self.restarts = (now.date_naive(), 0);
+            }
+            self.restarts.1 += 1;
+        }
+        self.attempts.push(now);
+        self.not_before = None;
+        self.safe_mode = false;
+        if !ok {
+            self.down(ExitClass::StartFailure, now);
+        }
+    }
+
+    /// Seconds before the restart after the streak's latest crash; the last
+    /// delay repeats.
+    fn delay(&self) -> u64 {
+        let delays = &self.config.delays_seconds;
+        delays.get(self.streak.saturating_sub(1)).or(delays.last()).copied().unwrap_or(0)
+    }
+
+    fn over_daily_limit(&self, now: DateTime<Local>) -> bool {
+        let (day, restarts) = self.restarts;
+        self.config.daily_restart_limit > 0 && day == now.date_naive() && restarts >= self.config.daily_restart_limit
+    }
+
+    fn prune(&mut self, now: DateTime<Local>) {
+        let window = Duration::minutes(self.config.window_minutes as i64);
+        self.attempts.retain(|t| now - *t <= window);
+    }
+
+    /// Whether to start the server on this pass of the main loop.
+    pub fn allows_start(&mut self, now: DateTime<Local>, messages: &Messages, notifiers: &Notifiers) -> bool {
+        self.prune(now);
+        if self.halted {
+            return false;
+        }
+        if self.not_before.is_some() && self.over_daily_limit(now) {
+            let restarts = self.restarts.1.to_string();
+            error!("Watchdog: {} automatic restarts today, the daily limit; stopping until re-armed.", restarts);
+            lifecycle::record("watchdog_halted", &format!("{} automatic restarts today", restarts), None);
+            notifiers.send(EventKind::WatchdogGaveUp, &messages.get("watchdog_daily_limit", &[("restarts", restarts)]));
+            self.halted = true;
+            return false;
+        }
+        match self.holding {
+            Some(Some(until)) if now >= until => {
+                info!("Watchdog: trying again.");
+                lifecycle::record("watchdog_retry", "retry interval passed", None);
+                self.clear();
+                return true;
+            }
+            Some(_) => return false,
+            None => {}
+        }
+
+        if self.attempts.len() >= self.config.max_attempts as usize {
+            self.exhausted(now, messages, notifiers);
+            return false;
+        }
+        self.not_before.is_none_or(|at| now >= at)
+    }
+
+    fn exhausted(&mut self, now: DateTime<Local>, messages: &Messages, notifiers: &Notifiers) {
+        let reason = format!("{} starts in {} minutes", self.config.max_attempts, self.config.window_minutes);
+        let details = [
+            ("crashes", self.config.max_attempts.to_string()),
+            ("minutes", self.config.window_minutes.to_string()),
+        ];
+        error!("Watchdog: too many starts ({}); stopping auto-restart.", reason);
+        match self.config.on_exhausted {
+            Exhausted::GiveUp => self.give_up(now, &reason, &details, "watchdog_gave_up", messages, notifiers),
+            Exhausted::Retry => {
+                let until = now + Duration::minutes(self.config.retry_minutes.max(1) as i64);
+                let time = until.format("%H:%M").to_string();
+                lifecycle::record("watchdog_retry_later", &format!("{}; trying again at {}", reason, time), None);
+                let mut details = details.to_vec();
+                details.push(("time", time));
+                notifiers.send(EventKind::WatchdogGaveUp, &messages.get("watchdog_retry_later", &details));
+                self.holding = Some(Some(until));
+            }
+            Exhausted::Fallback => {
+                let script = self.config.fallback_script.clone().unwrap_or_default();
+                let mut details = details.to_vec();
+                details.push(("script", script.clone()));
+                notifiers.send(EventKind::WatchdogGaveUp, &messages.get("watchdog_fallback", &details));
+                info!("Watchdog: running the fallback script {}...", script);
+                match run_script(&script, self.server.as_deref()) {
+                    Ok(()) => {
+                        lifecycle::record("watchdog_fallback", &format!("{}; {} succeeded", reason, script), None);
+                        // The script may have fixed it; one more round of attempts
+                        self.reset();
+                    }
+                    Err(e) => {
+                        warn!("Watchdog: the fallback script failed: {}", e);
+                        let reason = format!("{}; {} failed: {}", reason, script, e);
+                        let mut details = details.to_vec();
+                        details.push(("error", e));
+                        self.give_up(now, &reason, &details, "watchdog_fallback_failed", messages, notifiers);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Takes one more round of attempts after the cooldown, then stays down
+    /// until the server is started by hand, the next window or a re-arm.
+    fn give_up(
+        &mut self,
+        now: DateTime<Local>,
+        reason: &str,
+        details: &[(&str, String)],
+        key: &str,
+        messages: &Messages,
+        notifiers: &Notifiers,
+    ) {
+        if self.cooled_down || self.config.cooldown_minutes == 0 {
+            lifecycle::record("watchdog_gave_up", reason, None);
+            notifiers.send(EventKind::WatchdogGaveUp, &messages.get(key, details));
+            self.holding = Some(None);
+            return;
+        }
+        let until = now + Duration::minutes(self.config.cooldown_minutes as i64);
+        let time = until.format("%H:%M").to_string();
+        lifecycle::record("watchdog_cooldown", &format!("{}; one more try at {}", reason, time), None);
+        let mut details = details.to_vec();
+        details.push(("time", time));
+        notifiers.send(EventKind::WatchdogGaveUp, &messages.get(&format!("{}_cooldown", key), &details));
+        self.cooled_down = true;
+        self.holding = Some(Some(until));
+    }
+}
+
+/// "15s", "5m", "1m 30s": how a restart delay is shown in notifications.
+pub fn format_delay(seconds: i64) -> String {
+    match (seconds / 60, seconds % 60) {
+        (0, s) => format!("{}s", s),
+        (m, 0) => format!("{}m", m),
+        (m, s) => format!("{}m {}s", m, s),
+    }
+}
+
+/// Runs the script the way the server's start script is run, and waits for it.
+fn run_script(path: &str, server: Option<&str>) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", path]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", path]);
+        command
+    };
+    if let Some(server) = server {
+        command.env("GOLEM_SERVER", server);
+    }
+    let status = command.status().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {}", status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::config;
+    use crate::instance;
+
+    /// Notifications from the watchdog switched off, so nothing is sent.
+    const CONFIG: &str = "server_bat_path = \"start.sh\"\nstart_time = \"00:00\"\nend_time = \"00:00\"\ndiscord_webhook_url = \"\"\n[notifications.events]\nwatchdog = false\n";
+
+    fn setup(config: WatchdogConfig) -> (Watchdog, Messages, Notifiers) {
+        instance::enter_dir(std::env::temp_dir().join(format!("rusty-golem-tests-{}", std::process::id())));
+        let golem = config::parse_config(CONFIG).unwrap();
+        let messages = Messages::from_config(&golem);
+        let notifiers = Notifiers::from_config(&golem, &messages);
+        (Watchdog::new(&config), messages, notifiers)
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 3, 2, hour, minute, 0).unwrap()
+    }
+
+    /// Crashes at `now` and starts again as soon as allowed, if that is
+    /// within the hour; the time it started.
+    fn crash_and_restart(
+        watchdog: &mut Watchdog,
+        now: DateTime<Local>,
+        messages: &Messages,
+        notifiers: &Notifiers,
+    ) -> Option<DateTime<Local>> {
+        let NextStart::At(next) = watchdog.down(ExitClass::Crash, now) else {
+            return None;
+        };
+        if !watchdog.allows_start(next, messages, notifiers) {
+            return None;
+        }
+        watchdog.attempted(next, true);
+        Some(next)
+    }
+
+    #[test]
+    fn delays_grow_with_the_streak_and_the_last_repeats() {
+        let (mut watchdog, _, _) = setup(WatchdogConfig {
+            max_attempts: 100,
+            delays_seconds: vec![15, 60, 300],
+            restart_delay_seconds: 5,
+            ..WatchdogConfig::default()
+        });
+        let now = at(12, 0);
+        let mut waits = Vec::new();
+        for _ in 0..4 {
+            let NextStart::At(next) = watchdog.down(ExitClass::Crash, now) else {
+                panic!("should restart");
+            };
+            waits.push((next - now).num_seconds());
+            watchdog.attempted(next, true);
+        }
+        assert_eq!(waits, [20, 65, 305, 305]);
+        assert_eq!(watchdog.streak(), 4);
+    }
+
+    #[test]
+    fn no_start_before_the_delay_is_over() {
+        let (mut watchdog, messages, notifiers) = setup(WatchdogConfig { delays_seconds: vec![60], ..WatchdogConfig::default() });
+        watchdog.attempted(at(12, 0), true);
+        watchdog.down(ExitClass::Crash, at(12, 0));
+        assert!(!watchdog.allows_start(at(12, 0) + Duration::seconds(59), &messages, &notifiers));
+        assert!(watchdog.allows_start(at(12, 1), &messages, &notifiers));
+    }
+
+    #[test]
+    fn attempts_count_within_the_window_only() {
+        let config = WatchdogConfig { max_attempts: 3, window_minutes: 5, delays_seconds: vec![0], ..WatchdogConfig::default() };
+        let (mut watchdog, messages, notifiers) = setup(config.clone());
+        // Every six minutes: never three within five
+        watchdog.attempted(at(12, 0), true);
+        for minute in [6, 12, 18, 24] {
+            assert!(crash_and_restart(&mut watchdog, at(12, minute), &messages, &notifiers).is_some());
+        }
+
+        // Every minute: the third start in the window is the last
+        let (mut watchdog, messages, notifiers) = setup(config);
+        watchdog.attempted(at(12, 0), true);
+        assert!(crash_and_restart(&mut watchdog, at(12, 1), &messages, &notifiers).is_some());
+        assert!(crash_and_restart(&mut watchdog, at(12, 2), &messages, &notifiers).is_some());
+        assert!(matches!(watchdog.down(ExitClass::Crash, at(12, 3)), NextStart::OutOfAttempts));
+        assert!(!watchdog.allows_start(at(12, 3), &messages, &notifiers));
+    }
+
+    /// Uses up the attempts at noon; the next check says the watchdog gave up.
+    fn exhaust(config: WatchdogConfig) -> (Watchdog, Messages, Notifiers) {
+        let (mut watchdog, messages, notifiers) = setup(WatchdogConfig { max_attempts: 1, delays_seconds: vec![0], ..config });
+        watchdog.attempted(at(12, 0), true);
+        assert!(matches!(watchdog.down(ExitClass::Crash, at(12, 0)), NextStart::OutOfAttempts));
+        assert!(!watchdog.allows_start(at(12, 0), &messages, &notifiers));
+        (watchdog, messages, notifiers)
+    }
+
+    #[test]
+    fn giving_up_takes_one_more_round_after_the_cooldown() {
+        let (mut watchdog, messages, notifiers) =
+            exhaust(WatchdogConfig { on_exhausted: Exhausted::GiveUp, cooldown_minutes: 30, ..WatchdogConfig::default() });
+        assert!(!watchdog.allows_start(at(12, 29), &messages, &notifiers));
+        assert!(watchdog.allows_start(at(12, 30), &messages, &notifiers));
+        watchdog.attempted(at(12, 30), true);
+        watchdog.down(ExitClass::Crash, at(12, 31));
+        assert!(!watchdog.allows_start(at(12, 31), &messages, &notifiers));
+        // For good this time
+        assert!(!watchdog.allows_start(at(18, 0), &messages, &notifiers));
+    }
+
+    #[test]
+    fn retrying_waits_retry_minutes() {
+        let (mut watchdog, messages, notifiers) =
+            exhaust(WatchdogConfig { on_exhausted: Exhausted::Retry, retry_minutes: 60, ..WatchdogConfig::default() });
+        assert!(!watchdog.allows_start(at(12, 59), &messages, &notifiers));
+        assert!(watchdog.allows_start(at(13, 0), &messages, &notifiers));
+        assert_eq!(watchdog.streak(), 0);
+    }
+
+    #[test]
+    fn a_fallback_script_that_works_earns_another_round() {
+        let (mut watchdog, messages, notifiers) = exhaust(WatchdogConfig {
+            on_exhausted: Exhausted::Fallback,
+            fallback_script: Some("exit 0".to_string()),
+            ..WatchdogConfig::default()
+        });
+        assert!(watchdog.allows_start(at(12, 1), &messages, &notifiers));
+    }
+
+    #[test]
+    fn a_fallback_script_that_fails_gives_up() {
+        let (mut watchdog, messages, notifiers) = exhaust(WatchdogConfig {
+            on_exhausted: Exhausted::Fallback,
+            fallback_script: Some("exit 1".to_string()),
+            cooldown_minutes: 0,
+            ..WatchdogConfig::default()
+        });
+        assert!(!watchdog.allows_start(at(12, 1), &messages, &notifiers));
+        assert!(!watchdog.allows_start(at(18, 0), &messages, &notifiers));
+    }
+
+    #[test]
+    fn reset_and_rearm_start_over() {
+        let (mut watchdog, messages, notifiers) = exhaust(WatchdogConfig { cooldown_minutes: 0, ..WatchdogConfig::default() });
+        watchdog.reset();
+        assert_eq!(watchdog.streak(), 0);
+        assert!(watchdog.allows_start(at(12, 1), &messages, &notifiers));
+
+        let (mut watchdog, messages, notifiers) = exhaust(WatchdogConfig { cooldown_minutes: 0, ..WatchdogConfig::default() });
+        watchdog.rearm();
+        assert!(watchdog.allows_start(at(12, 1), &messages, &notifiers));
+    }
+
+    #[test]
+    fn staying_up_rearms() {
+        let (mut watchdog, _, _) = setup(WatchdogConfig { stable_minutes: 10, ..WatchdogConfig::default() });
+        watchdog.attempted(at(12, 0), true);
+        watchdog.down(ExitClass::Crash, at(12, 0));
+        watchdog.up(std::time::Duration::from_secs(9 * 60));
+        assert_eq!(watchdog.streak(), 1);
+        watchdog.up(std::time::Duration::from_secs(10 * 60));
+        assert_eq!(watchdog.streak(), 0);
+    }
+
+    #[test]
+    fn the_daily_limit_halts_until_rearmed() {
+        let (mut watchdog, messages, notifiers) = setup(WatchdogConfig {
+            max_attempts: 100,
+            delays_seconds: vec![0],
+            daily_restart_limit: 2,
+            ..WatchdogConfig::default()
+        });
+        // The first start of the day is not a restart
+        watchdog.attempted(at(8, 0), true);
+        assert!(crash_and_restart(&mut watchdog, at(9, 0), &messages, &notifiers).is_some());
+        assert!(crash_and_restart(&mut watchdog, at(10, 0), &messages, &notifiers).is_some());
+        assert!(matches!(watchdog.down(ExitClass::Crash, at(11, 0)), NextStart::OutOfAttempts));
+        assert!(!watchdog.allows_start(at(11, 0), &messages, &notifiers));
+        // Not even the next day
+        assert!(!watchdog.allows_start(at(11, 0) + Duration::days(1), &messages, &notifiers));
+        watchdog.rearm();
+        assert!(watchdog.allows_start(at(11, 0) + Duration::days(1), &messages, &notifiers));
+    }
+
+    #[test]
+    fn a_new_day_has_its_own_restarts() {
+        let (mut watchdog, messages, notifiers) = setup(WatchdogConfig {
+            max_attempts: 100,
+            delays_seconds: vec![0],
+            daily_restart_limit: 1,
+            ..WatchdogConfig::default()
+        });
+        watchdog.attempted(at(8, 0), true);
+        assert!(crash_and_restart(&mut watchdog, at(23, 0), &messages, &notifiers).is_some());
+        assert!(crash_and_restart(&mut watchdog, at(1, 0) + Duration::days(1), &messages, &notifiers).is_some());
+    }
+}
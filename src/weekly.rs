@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::availability::Availability;
+use crate::digest::format_duration;
+use crate::history;
+use crate::messages::Messages;
+use crate::world_growth;
+
+/// Players listed in the playtime leaderboard.
+const LEADERBOARD_SIZE: usize = 10;
+
+/// Embed fields for the weekly report, from the last 7 days of the stats
+/// store: hours run, players, playtime leaderboard, crashes, average TPS and
+/// world growth.
+pub fn fields(availability: &Availability, messages: &Messages) -> Vec<(String, String)> {
+    let since = history::start_of_last(7);
+
+    let mut playtime: HashMap<String, u64> = HashMap::new();
+    for (_, value) in history::read_since("sessions", since) {
+        let Some((name, seconds)) = value.rsplit_once(',') else {
+            continue;
+        };
+        if let Ok(seconds) = seconds.parse::<u64>() {
+            *playtime.entry(name.to_string()).or_default() += seconds;
+        }
+    }
+    let mut leaders: Vec<(String, u64)> = playtime.into_iter().collect();
+    leaders.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.to_lowercase().cmp(&b.0.to_lowercase())));
+    let unique_players = leaders.len();
+    let leaderboard = if leaders.is_empty() {
+        "-".to_string()
+    } else {
+        leaders
+            .iter()
+            .take(LEADERBOARD_SIZE)
+            .enumerate()
+            .map(|(rank, (name, seconds))| format!("{}. {} — {}", rank + 1, name, format_duration(Duration::from_secs(*seconds))))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let crashes = history::read_since("crashes", since).len();
+
+    let tps: Vec<f64> = history::read_since("tps", since)
+        .iter()
+        .filter_map(|(_, value)| value.split(',').next()?.parse().ok())
+        .collect();
+    let average_tps = if tps.is_empty() {
+        "-".to_string()
+    } else {
+        format!("{:.1}", tps.iter().sum::<f64>() / tps.len() as f64)
+    };
+
+    let world_size = world_growth::weekly_value(messages).unwrap_or_else(|| "-".to_string());
+
+    vec![
+        (messages.get("weekly_uptime", &[]), format_duration(availability.uptime(7))),
+        (messages.get("weekly_unique_players", &[]), unique_players.to_string()),
+        (messages.get("weekly_playtime", &[]), leaderboard),
+        (messages.get("weekly_crashes", &[]), crashes.to_string()),
+        (messages.get("weekly_average_tps", &[]), average_tps),
+        (messages.get("weekly_world_size", &[]), world_size),
+    ]
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Days, Duration, Local, NaiveDate};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::history;
+use crate::messages::Messages;
+use crate::metrics::{self, format_bytes};
+use crate::notify::{EventKind, Notifiers};
+
+/// A server that never stops is measured while running after this long.
+const MAX_AGE_HOURS: i64 = 48;
+
+/// Measures the worlds' size once a day, while the server is down if it is
+/// down at all that day, into `stats/world_size.csv`, and warns when they
+/// grew by more than the configured amount since the last measurement.
+pub struct WorldGrowth {
+    last: Option<(DateTime<Local>, u64)>,
+}
+
+impl WorldGrowth {
+    /// Picks up the last measurement from the stats store.
+    pub fn load() -> WorldGrowth {
+        let since = Local::now().checked_sub_days(Days::new(30)).unwrap_or_else(Local::now);
+        let last = history::read_since("world_size", since)
+            .into_iter()
+            .filter_map(|(time, value)| Some((time, value.parse().ok()?)))
+            .next_back();
+        WorldGrowth { last }
+    }
+
+    pub fn check(&mut self, config: &Config, now: DateTime<Local>, server_down: bool, messages: &Messages, notifiers: &Notifiers) {
+        let due = match self.last {
+            None => true,
+            Some((at, _)) => (server_down && at.date_naive() != now.date_naive()) || now - at >= Duration::hours(MAX_AGE_HOURS),
+        };
+        if !due {
+            return;
+        }
+        let size = metrics::world_size(&config.server_dir());
+        if size == 0 {
+            return;
+        }
+        history::append("world_size", now, &size.to_string());
+        info!("World size: {}", format_bytes(size));
+        let previous = self.last.replace((now, size));
+        let limit = config.world_growth.max_daily_growth_mb * 1024 * 1024;
+        let Some((measured_at, before)) = previous.filter(|_| limit > 0) else {
+            return;
+        };
+        let grown = size.saturating_sub(before);
+        if grown > limit {
+            warn!("World size: grew by {} to {}", format_bytes(grown), format_bytes(size));
+            let message = messages.get(
+                "world_growth",
+                &[
+                    ("grown", format_bytes(grown)),
+                    ("size", format_bytes(size)),
+                    ("since", measured_at.format("%Y-%m-%d").to_string()),
+                ],
+            );
+            notifiers.send(EventKind::WorldGrowth, &message);
+        }
+    }
+}
+
+/// Each day's last measurement over the period, oldest first.
+fn daily_sizes(since: DateTime<Local>) -> BTreeMap<NaiveDate, u64> {
+    history::read_since("world_size", since)
+        .into_iter()
+        .filter_map(|(time, value)| Some((time.date_naive(), value.parse().ok()?)))
+        .collect()
+}
+
+/// `stats world [--week|--month]`: the size per day and the change since the
+/// measurement before.
+pub fn lines(args: &[&str]) -> Vec<String> {
+    let days = match args.first() {
+        None | Some(&"--week") => 7,
+        Some(&"--month") => 30,
+        Some(_) => return vec!["Usage: stats world [--week|--month]".to_string()],
+    };
+    let sizes = daily_sizes(history::start_of_last(days));
+    if sizes.is_empty() {
+        return vec![format!("No world sizes recorded in the last {} days.", days)];
+    }
+    let mut lines = vec![format!("{:<10} {:>10} {:>10}", "Day", "Size", "Change")];
+    let mut previous: Option<u64> = None;
+    for (date, size) in sizes {
+        let change = previous.map_or("-".to_string(), |before| signed_bytes(size as i64 - before as i64));
+        lines.push(format!("{:<10} {:>10} {:>10}", date.format("%a %m-%d"), format_bytes(size), change));
+        previous = Some(size);
+    }
+    lines
+}
+
+/// For the weekly report: the latest size and how much the week added.
+pub fn weekly_value(messages: &Messages) -> Option<String> {
+    let sizes = daily_sizes(history::start_of_last(7));
+    let (&first, &last) = (sizes.values().next()?, sizes.values().next_back()?);
+    Some(messages.get("weekly_world_size_value", &[("size", format_bytes(last)), ("change", signed_bytes(last as i64 - first as i64))]))
+}
+
+fn signed_bytes(change: i64) -> String {
+    if change < 0 {
+        format!("-{}", format_bytes(change.unsigned_abs()))
+    } else {
+        format!("+{}", format_bytes(change as u64))
+    }
+}